@@ -0,0 +1,338 @@
+use crate::findings::{hash_parts, Finding};
+use crate::report::{RunReport, ToolStatus};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+/// Default baseline file name, looked up in the analyzed directory
+pub const BASELINE_FILE: &str = ".codeguard-baseline.json";
+
+const BASELINE_VERSION: u32 = 1;
+
+/// Accepted findings, committed to the repository so that later runs only
+/// report what is new
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Baseline {
+    pub version: u32,
+    pub findings: Vec<BaselineEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BaselineEntry {
+    pub fingerprint: String,
+    pub tool: String,
+    pub file: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rule: Option<String>,
+    pub message: String,
+    /// How many identical findings are accepted
+    pub count: usize,
+}
+
+/// How a run compared to the baseline
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct BaselineSummary {
+    /// Findings not in the baseline; these stay in the report
+    pub new: usize,
+    /// Findings matched by the baseline and removed from the report
+    pub baselined: usize,
+    /// Baseline entries no longer reported
+    pub fixed: usize,
+}
+
+/// Baseline path for `directory`, honouring an explicit override
+pub fn baseline_path(directory: &Path, file: Option<&Path>) -> PathBuf {
+    match file {
+        Some(file) => file.to_path_buf(),
+        None => directory.join(BASELINE_FILE),
+    }
+}
+
+impl Baseline {
+    /// Snapshot every finding in `report`
+    pub fn from_report(report: &RunReport) -> Self {
+        let mut sources = SourceCache::new(&report.directory);
+        let mut entries: BTreeMap<(String, String, String), BaselineEntry> = BTreeMap::new();
+        for finding in report.tools.iter().flat_map(|t| &t.findings) {
+            let fingerprint = sources.fingerprint(finding);
+            entries
+                .entry((
+                    finding.file.clone(),
+                    finding.tool.clone(),
+                    fingerprint.clone(),
+                ))
+                .and_modify(|entry| entry.count += 1)
+                .or_insert_with(|| BaselineEntry {
+                    fingerprint,
+                    tool: finding.tool.clone(),
+                    file: finding.file.clone(),
+                    rule: finding.rule.clone(),
+                    message: finding.message.clone(),
+                    count: 1,
+                });
+        }
+        Self {
+            version: BASELINE_VERSION,
+            findings: entries.into_values().collect(),
+        }
+    }
+
+    /// Load the baseline at `path`, or `None` if there is none
+    pub fn load(path: &Path) -> Result<Option<Self>> {
+        if !path.exists() {
+            return Ok(None);
+        }
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("Could not read baseline {}", path.display()))?;
+        let baseline: Self = serde_json::from_str(&contents)
+            .with_context(|| format!("Invalid baseline {}", path.display()))?;
+        if baseline.version != BASELINE_VERSION {
+            anyhow::bail!(
+                "Unsupported baseline version {} in {}",
+                baseline.version,
+                path.display()
+            );
+        }
+        Ok(Some(baseline))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let mut contents = serde_json::to_string_pretty(self)?;
+        contents.push('\n');
+        std::fs::write(path, contents)
+            .with_context(|| format!("Could not write baseline {}", path.display()))
+    }
+
+    pub fn total(&self) -> usize {
+        self.findings.iter().map(|entry| entry.count).sum()
+    }
+
+    /// Remove baselined findings from `report`, leaving only new ones.
+    ///
+    /// A tool that failed only because of baselined findings is counted as
+    /// passed, since nothing it reported is new. Only its exit code tells
+    /// that findings were all that failed it; otherwise it stays failed.
+    pub fn apply(&self, report: &mut RunReport) -> BaselineSummary {
+        let mut remaining = self.remaining();
+        let mut sources = SourceCache::new(&report.directory);
+        let mut summary = BaselineSummary::default();
+
+        for tool in &mut report.tools {
+            let baselined = take_accepted(&mut remaining, &mut sources, &mut tool.findings);
+            summary.baselined += baselined;
+            summary.new += tool.findings.len();
+            if tool.status == ToolStatus::Failed
+                && baselined > 0
+                && tool.findings.is_empty()
+                && tool.tool.failed_on_findings(tool.exit_code)
+            {
+                tool.status = ToolStatus::Passed;
+            }
+        }
+
+        summary.fixed = remaining.values().sum();
+        summary
+    }
+
+    /// Remove the findings of a single run of one tool that the baseline
+    /// accepts, returning how many were removed. The TUI receives runs one
+    /// at a time; fingerprints name the tool, so this matches what
+    /// [`Baseline::apply`] does for a whole report.
+    pub fn filter(&self, directory: &Path, findings: &mut Vec<Finding>) -> usize {
+        take_accepted(&mut self.remaining(), &mut SourceCache::new(directory), findings)
+    }
+
+    /// A copy without the entries `report` no longer produces. New findings
+    /// are never added, so the baseline can only shrink.
+    pub fn shrink(&self, report: &RunReport) -> Self {
+        let mut sources = SourceCache::new(&report.directory);
+        let mut current: HashMap<String, usize> = HashMap::new();
+        for finding in report.tools.iter().flat_map(|t| &t.findings) {
+            *current.entry(sources.fingerprint(finding)).or_default() += 1;
+        }
+
+        let findings = self
+            .findings
+            .iter()
+            .filter_map(|entry| {
+                let available = current.get_mut(&entry.fingerprint)?;
+                let count = entry.count.min(*available);
+                *available -= count;
+                (count > 0).then(|| BaselineEntry {
+                    count,
+                    ..entry.clone()
+                })
+            })
+            .collect();
+        Self {
+            version: BASELINE_VERSION,
+            findings,
+        }
+    }
+
+    fn remaining(&self) -> HashMap<String, usize> {
+        let mut remaining = HashMap::new();
+        for entry in &self.findings {
+            *remaining.entry(entry.fingerprint.clone()).or_default() += entry.count;
+        }
+        remaining
+    }
+}
+
+/// Remove the findings `remaining` still accepts from `findings`, counting
+/// them off, and return how many were removed
+fn take_accepted(
+    remaining: &mut HashMap<String, usize>,
+    sources: &mut SourceCache,
+    findings: &mut Vec<Finding>,
+) -> usize {
+    let before = findings.len();
+    findings.retain(|finding| match remaining.get_mut(&sources.fingerprint(finding)) {
+        Some(count) if *count > 0 => {
+            *count -= 1;
+            false
+        }
+        _ => true,
+    });
+    before - findings.len()
+}
+
+/// Reads source files on demand to fingerprint findings by the code they
+/// point at rather than by line number
+struct SourceCache<'a> {
+    directory: &'a Path,
+    files: HashMap<String, Option<Vec<String>>>,
+}
+
+impl<'a> SourceCache<'a> {
+    fn new(directory: &'a Path) -> Self {
+        Self {
+            directory,
+            files: HashMap::new(),
+        }
+    }
+
+    /// Hash of the tool, file, rule and the whitespace-normalized source line.
+    /// Findings without a rule use their message in its place; findings whose
+    /// line cannot be read are identified by tool, file and rule alone.
+    fn fingerprint(&mut self, finding: &Finding) -> String {
+        let code = finding
+            .line
+            .and_then(|line| self.line(&finding.file, line))
+            .unwrap_or_default();
+        let rule = finding.rule.as_deref().unwrap_or(&finding.message);
+        hash_parts(&[&finding.tool, &finding.file, rule, &code])
+    }
+
+    fn line(&mut self, file: &str, line: u32) -> Option<String> {
+        let directory = self.directory;
+        let lines = self.files.entry(file.to_string()).or_insert_with(|| {
+            std::fs::read_to_string(directory.join(file))
+                .ok()
+                .map(|contents| contents.lines().map(str::to_string).collect())
+        });
+        let text = lines.as_ref()?.get(line.checked_sub(1)? as usize)?;
+        Some(text.split_whitespace().collect::<Vec<_>>().join(" "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::findings::Severity;
+    use crate::report::ToolReport;
+    use crate::tools::base::Tool;
+    use std::time::Duration;
+
+    const SOURCE: &str = "import os\nimport sys\n\ndef main():\n    print( sys.argv )\n";
+
+    fn finding(line: u32, rule: &str) -> Finding {
+        Finding {
+            tool: "flake8".to_string(),
+            file: "app.py".to_string(),
+            line: Some(line),
+            column: Some(1),
+            severity: Severity::Error,
+            rule: Some(rule.to_string()),
+            message: format!("{} at line {}", rule, line),
+        }
+    }
+
+    fn report(directory: &Path, findings: Vec<Finding>) -> RunReport {
+        RunReport {
+            directory: directory.to_path_buf(),
+            tools: vec![ToolReport {
+                tool: Tool {
+                    name: "flake8".to_string(),
+                    command: "flake8".to_string(),
+                    findings_exit_codes: vec![1],
+                    ..Default::default()
+                },
+                status: ToolStatus::Failed,
+                exit_code: Some(1),
+                duration: Duration::ZERO,
+                output: String::new(),
+                findings,
+                cached: false,
+                version: None,
+            }],
+            gate: None,
+            baseline: None,
+            changed_since: None,
+            coverage: None,
+        }
+    }
+
+    #[test]
+    fn accepts_findings_that_moved_with_their_code() {
+        let directory = tempfile::tempdir().unwrap();
+        let directory = directory.path();
+        std::fs::write(directory.join("app.py"), SOURCE).unwrap();
+        let baseline = Baseline::from_report(&report(directory, vec![finding(1, "F401"), finding(5, "E201")]));
+        assert_eq!(baseline.total(), 2);
+
+        // Two lines inserted above, and the spacing of the flagged line changed
+        let shifted = format!("# header\n\n{}", SOURCE.replace("print( sys.argv )", "print(  sys.argv )"));
+        std::fs::write(directory.join("app.py"), shifted).unwrap();
+        let mut moved = report(directory, vec![finding(3, "F401"), finding(7, "E201"), finding(4, "F401")]);
+        let summary = baseline.apply(&mut moved);
+        assert_eq!(summary, BaselineSummary { new: 1, baselined: 2, fixed: 0 });
+        assert_eq!(moved.tools[0].findings, [finding(4, "F401")]);
+        assert_eq!(moved.tools[0].status, ToolStatus::Failed);
+
+        let mut moved = report(directory, vec![finding(3, "F401"), finding(7, "E201")]);
+        baseline.apply(&mut moved);
+        assert!(moved.tools[0].findings.is_empty());
+        assert_eq!(moved.tools[0].status, ToolStatus::Passed);
+    }
+
+    #[test]
+    fn counts_identical_findings() {
+        let directory = tempfile::tempdir().unwrap();
+        let directory = directory.path();
+        std::fs::write(directory.join("app.py"), "x = 1\nx = 1\nx = 1\n").unwrap();
+        let baseline = Baseline::from_report(&report(directory, vec![finding(1, "E225"), finding(2, "E225")]));
+        assert_eq!(baseline.findings.len(), 1);
+        assert_eq!(baseline.findings[0].count, 2);
+
+        let mut findings = vec![finding(1, "E225"), finding(2, "E225"), finding(3, "E225")];
+        assert_eq!(baseline.filter(directory, &mut findings), 2);
+        assert_eq!(findings.len(), 1);
+    }
+
+    #[test]
+    fn shrinks_to_what_is_still_reported() {
+        let directory = tempfile::tempdir().unwrap();
+        let directory = directory.path();
+        std::fs::write(directory.join("app.py"), SOURCE).unwrap();
+        let baseline = Baseline::from_report(&report(directory, vec![finding(1, "F401"), finding(2, "F401")]));
+
+        let current = report(directory, vec![finding(2, "F401"), finding(5, "E201")]);
+        let mut applied = current.clone();
+        assert_eq!(baseline.apply(&mut applied).fixed, 1);
+        let shrunk = baseline.shrink(&current);
+        assert_eq!(shrunk.total(), 1);
+        assert_eq!(shrunk.findings[0].message, "F401 at line 2");
+    }
+}
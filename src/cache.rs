@@ -0,0 +1,178 @@
+use crate::findings::Finding;
+use crate::paths::{PathFilter, DEPENDENCY_DIRS};
+use crate::report::ToolStatus;
+use crate::tools::{OutputStream, Tool};
+use anyhow::{Context, Result};
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Component, PathBuf};
+
+/// Environment variables that can change what a tool reports. The rest of
+/// the environment (terminal, shell state) is left out of the key so that it
+/// does not defeat the cache.
+const ENV_VARS: [&str; 9] = [
+    "PATH",
+    "VIRTUAL_ENV",
+    "PYTHONPATH",
+    "NODE_ENV",
+    "NODE_OPTIONS",
+    "RUSTFLAGS",
+    "RUSTUP_TOOLCHAIN",
+    "CARGO_TARGET_DIR",
+    "PHP_INI_SCAN_DIR",
+];
+
+/// A tool outcome as stored in the cache
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CachedResult {
+    pub status: ToolStatus,
+    pub exit_code: Option<i32>,
+    pub duration_ms: u64,
+    /// Output lines in the order they were read, for replay
+    pub lines: Vec<(OutputStream, String)>,
+    pub output: String,
+    pub findings: Vec<Finding>,
+}
+
+/// Outcomes of earlier tool runs, stored under `~/.cache/codeguard/results`
+/// and addressed by a hash of everything that can change them: the command
+/// and its arguments, the relevant environment, the tool's version and the
+/// contents of the files matching its input globs
+pub struct ResultCache {
+    directory: PathBuf,
+    filter: PathFilter,
+    /// Tools that always run, by name
+    bypassed: HashSet<String>,
+}
+
+/// Where cached results are stored
+pub fn cache_dir() -> Option<PathBuf> {
+    Some(dirs::cache_dir()?.join("codeguard").join("results"))
+}
+
+impl ResultCache {
+    /// The cache for tools run on the directory `filter` is rooted at, or
+    /// `None` if the platform has no cache directory
+    pub fn new(filter: PathFilter) -> Option<Self> {
+        Some(Self {
+            directory: cache_dir()?,
+            filter,
+            bypassed: HashSet::new(),
+        })
+    }
+
+    /// This cache, never used for the tools named `tools`, e.g. because
+    /// their runs have side effects a replay would not have
+    pub fn bypassing(mut self, tools: impl IntoIterator<Item = String>) -> Self {
+        self.bypassed.extend(tools);
+        self
+    }
+
+    /// The key of `tool`'s outcome in its current state, given the first
+    /// line its version probe printed, or `None` if the tool is bypassed.
+    /// On-demand tools are never replayed, as running them is the point, and
+    /// online ones neither, as a replay would miss e.g. new advisories.
+    pub fn key(&self, tool: &Tool, version: &str) -> Option<String> {
+        if self.bypassed.contains(&tool.name) || tool.on_demand || tool.online {
+            return None;
+        }
+        let mut hasher = Sha256::new();
+        let mut field = |value: &str| {
+            hasher.update(value.len().to_le_bytes());
+            hasher.update(value.as_bytes());
+        };
+        field(&tool.name);
+        field(&tool.command);
+        for arg in &tool.args {
+            field(arg);
+        }
+        for name in ENV_VARS {
+            field(&std::env::var(name).unwrap_or_default());
+        }
+        field(version);
+        for (path, hash) in self.input_hashes(tool) {
+            field(&path);
+            field(&hash);
+        }
+        Some(format!("{:x}", hasher.finalize()))
+    }
+
+    pub fn load(&self, key: &str) -> Option<CachedResult> {
+        let contents = std::fs::read_to_string(self.directory.join(format!("{}.json", key))).ok()?;
+        serde_json::from_str(&contents).ok()
+    }
+
+    /// Store `result` under `key`. Failing to store only costs a rerun, so
+    /// errors are ignored.
+    pub fn store(&self, key: &str, result: &CachedResult) {
+        let Ok(contents) = serde_json::to_string(result) else {
+            return;
+        };
+        if std::fs::create_dir_all(&self.directory).is_err() {
+            return;
+        }
+        // Written aside and renamed, so concurrent runs never read half an entry
+        let path = self.directory.join(format!("{}.json", key));
+        let temporary = path.with_extension(format!("{}.tmp", std::process::id()));
+        if std::fs::write(&temporary, contents).is_ok() {
+            let _ = std::fs::rename(&temporary, &path);
+        }
+    }
+
+    /// Content hashes of the files the tool reads, by path relative to its
+    /// project. Tools without input globs read every file. Dependency
+    /// directories are left out; the lockfiles among the inputs stand in
+    /// for them.
+    fn input_hashes(&self, tool: &Tool) -> BTreeMap<String, String> {
+        let project = tool.working_dir(self.filter.root());
+        let inputs = glob_set(&tool.inputs);
+        self.filter
+            .walk_under(&project, None)
+            .filter(|entry| entry.file_type().is_some_and(|t| t.is_file()))
+            .filter_map(|entry| {
+                let relative = entry.path().strip_prefix(&project).ok()?;
+                let in_dependencies = relative.components().any(|component| {
+                    matches!(component, Component::Normal(name) if DEPENDENCY_DIRS.iter().any(|d| name == *d))
+                });
+                if in_dependencies || inputs.as_ref().is_some_and(|set| !set.is_match(relative)) {
+                    return None;
+                }
+                let contents = std::fs::read(entry.path()).ok()?;
+                Some((
+                    relative.to_string_lossy().to_string(),
+                    format!("{:x}", Sha256::digest(contents)),
+                ))
+            })
+            .collect()
+    }
+}
+
+fn glob_set(patterns: &[String]) -> Option<GlobSet> {
+    if patterns.is_empty() {
+        return None;
+    }
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        if let Ok(glob) = GlobBuilder::new(pattern).literal_separator(true).build() {
+            builder.add(glob);
+        }
+    }
+    builder.build().ok()
+}
+
+/// Delete every cached result
+pub fn clean() -> Result<()> {
+    let Some(directory) = cache_dir() else {
+        println!("No cache directory on this platform");
+        return Ok(());
+    };
+    let entries = std::fs::read_dir(&directory).map(|entries| entries.count()).unwrap_or(0);
+    if directory.exists() {
+        std::fs::remove_dir_all(&directory)
+            .with_context(|| format!("Could not remove {}", directory.display()))?;
+    }
+    println!("Removed {} cached results from {}", entries, directory.display());
+    Ok(())
+}
@@ -1,13 +1,29 @@
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
     pub tools: ToolsConfig,
     pub ui: UiConfig,
     pub ignore_patterns: Vec<String>,
+    /// Also pass `ignore_patterns` to tools that accept exclusion flags
+    #[serde(default)]
+    pub pass_ignore_patterns: bool,
+    #[serde(default)]
+    pub gates: GatesConfig,
+    #[serde(default)]
+    pub hooks: HooksConfig,
+    /// How often "rerun failing tests" runs a test tool to tell flaky tests
+    /// from broken ones
+    #[serde(default = "default_flaky_reruns")]
+    pub flaky_reruns: usize,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+fn default_flaky_reruns() -> usize {
+    3
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ToolsConfig {
     pub pytest: ToolConfig,
     pub flake8: ToolConfig,
@@ -15,19 +31,98 @@ pub struct ToolsConfig {
     pub mypy: ToolConfig,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ToolConfig {
     pub command: String,
     pub args: Vec<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UiConfig {
     pub theme: String,
     pub max_results: usize,
     pub auto_run_on_start: bool,
 }
 
+/// Quality gates deciding whether a run passes
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GatesConfig {
+    /// Maximum number of findings per severity over all gated tools
+    pub max_findings: SeverityThresholds,
+    /// Per-category policies and thresholds, keyed by tool category
+    pub categories: HashMap<String, CategoryGate>,
+    /// Minimum line coverage of runs with coverage collection
+    pub coverage: CoverageGate,
+}
+
+/// Lower bounds on line coverage, in percent
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CoverageGate {
+    /// Over every instrumented line
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_lines: Option<f64>,
+    /// Over the instrumented lines of the change set, when there is one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_changed_lines: Option<f64>,
+}
+
+/// Upper bounds on finding counts; a run fails when a count exceeds its bound
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SeverityThresholds {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub warning: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub info: Option<usize>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CategoryGate {
+    pub policy: GatePolicy,
+    /// Policy used instead of `policy` on draft runs (`--draft`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub draft_policy: Option<GatePolicy>,
+    /// Thresholds for the findings of this category alone
+    pub max_findings: SeverityThresholds,
+}
+
+/// How a tool's outcome counts towards the quality gate
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GatePolicy {
+    /// A failing tool fails the run and its findings count towards thresholds
+    #[default]
+    Required,
+    /// The tool is reported but never fails the run
+    Advisory,
+    /// The tool's outcome and findings are left out of the gate entirely
+    Ignore,
+}
+
+/// Arguments passed to `codeguard run` by the installed git hooks
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HooksConfig {
+    /// Used by the pre-commit hook, which runs against the staged snapshot
+    pub pre_commit: Vec<String>,
+    /// Used by the pre-push hook, which runs against the working tree
+    pub pre_push: Vec<String>,
+}
+
+impl Default for HooksConfig {
+    fn default() -> Self {
+        Self {
+            pre_commit: vec!["--changed-since".to_string(), "HEAD".to_string()],
+            pre_push: vec![],
+        }
+    }
+}
+
 impl Default for Config {
     fn default() -> Self {
         Self {
@@ -61,6 +156,10 @@ impl Default for Config {
                 "venv".to_string(),
                 "node_modules".to_string(),
             ],
+            pass_ignore_patterns: false,
+            gates: GatesConfig::default(),
+            hooks: HooksConfig::default(),
+            flaky_reruns: default_flaky_reruns(),
         }
     }
 }
@@ -0,0 +1,599 @@
+use crate::git::{ChangeSet, HOOK_ENV};
+use crate::testcases::TestRunner;
+use crate::tools::Tool;
+use anyhow::{Context, Result};
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+
+/// Coverage report formats the supported test tools write
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CoverageFormat {
+    Lcov,
+    Cobertura,
+    Clover,
+}
+
+/// A test tool changed to write a coverage report
+#[derive(Debug, Clone)]
+pub struct InstrumentedTool {
+    pub tool: Tool,
+    /// Where the report is written
+    pub report: PathBuf,
+    pub format: CoverageFormat,
+}
+
+/// `tool` changed to collect coverage into a report under `report_dir`:
+/// pytest with pytest-cov, Jest with `--coverage`, PHPUnit with
+/// `--coverage-clover`, and `cargo test` through cargo-llvm-cov or
+/// cargo-tarpaulin, whichever is installed. Fails if the plugin or
+/// subcommand is missing, so that the tests run without coverage instead.
+pub fn instrument(tool: &Tool, directory: &Path, report_dir: &Path) -> Result<InstrumentedTool> {
+    let runner = TestRunner::of(tool).with_context(|| format!("{} is not a test runner", tool.name))?;
+    // One directory per tool, as Jest and tarpaulin only take a directory
+    let output_dir = report_dir.join(tool.name.replace('/', "_"));
+    std::fs::create_dir_all(&output_dir)
+        .with_context(|| format!("Could not create {}", output_dir.display()))?;
+    let path = |name: &str| output_dir.join(name).to_string_lossy().to_string();
+
+    let mut command = tool.command.clone();
+    let mut args = tool.args.clone();
+    let (report, format) = match runner {
+        TestRunner::Pytest => {
+            if !has_pytest_cov(tool, &tool.working_dir(directory)) {
+                anyhow::bail!(
+                    "{} needs pytest-cov for coverage (`pip install pytest-cov`); running it without coverage",
+                    tool.name
+                );
+            }
+            args.extend(["--cov=.".to_string(), format!("--cov-report=xml:{}", path("coverage.xml"))]);
+            ("coverage.xml", CoverageFormat::Cobertura)
+        }
+        TestRunner::Jest => {
+            // npm only passes arguments after `--` on to the script
+            if tool.command == "npm" {
+                args.push("--".to_string());
+            }
+            args.extend([
+                "--coverage".to_string(),
+                "--coverageReporters=lcov".to_string(),
+                format!("--coverageDirectory={}", path("")),
+            ]);
+            ("lcov.info", CoverageFormat::Lcov)
+        }
+        TestRunner::PhpUnit => {
+            args.extend(["--coverage-clover".to_string(), path("clover.xml")]);
+            ("clover.xml", CoverageFormat::Clover)
+        }
+        TestRunner::Cargo => {
+            // Everything after `test` is passed on as is
+            let rest = args.iter().skip(1).cloned();
+            let working_dir = tool.working_dir(directory);
+            command = "cargo".to_string();
+            args = if has_cargo_subcommand("llvm-cov", &working_dir) {
+                ["llvm-cov", "--lcov", "--output-path"]
+                    .map(str::to_string)
+                    .into_iter()
+                    .chain([path("lcov.info")])
+                    .chain(rest)
+                    .collect()
+            } else if has_cargo_subcommand("tarpaulin", &working_dir) {
+                ["tarpaulin", "--out", "Lcov", "--output-dir"]
+                    .map(str::to_string)
+                    .into_iter()
+                    .chain([path("")])
+                    .chain(rest)
+                    .collect()
+            } else {
+                anyhow::bail!(
+                    "{} needs cargo-llvm-cov or cargo-tarpaulin for coverage (`cargo install cargo-llvm-cov`); running it without coverage",
+                    tool.name
+                );
+            };
+            ("lcov.info", CoverageFormat::Lcov)
+        }
+    };
+    Ok(InstrumentedTool {
+        tool: Tool {
+            command,
+            args,
+            file_scope: None,
+            ..tool.clone()
+        },
+        report: output_dir.join(report),
+        format,
+    })
+}
+
+/// Whether pytest, run the way `tool` runs it (and so in the same
+/// environment), has the pytest-cov plugin, which adds `--cov` to its help
+fn has_pytest_cov(tool: &Tool, working_dir: &Path) -> bool {
+    let mut command = std::process::Command::new(&tool.command);
+    for variable in HOOK_ENV {
+        command.env_remove(variable);
+    }
+    command
+        .args(&tool.args)
+        .arg("--help")
+        .current_dir(working_dir)
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .is_ok_and(|output| String::from_utf8_lossy(&output.stdout).contains("--cov="))
+}
+
+fn has_cargo_subcommand(name: &str, working_dir: &Path) -> bool {
+    let mut command = std::process::Command::new("cargo");
+    for variable in HOOK_ENV {
+        command.env_remove(variable);
+    }
+    command
+        .args([name, "--version"])
+        .current_dir(working_dir)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success())
+}
+
+/// Hit counts per instrumented line, by file relative to the analyzed
+/// directory
+#[derive(Debug, Clone, Default)]
+pub struct Coverage {
+    files: BTreeMap<String, BTreeMap<u32, u64>>,
+}
+
+/// Covered and instrumented lines
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct LineCounts {
+    pub covered: usize,
+    pub total: usize,
+}
+
+impl LineCounts {
+    /// Covered lines in percent, or `None` if no line is instrumented
+    pub fn percent(&self) -> Option<f64> {
+        (self.total > 0).then(|| self.covered as f64 * 100.0 / self.total as f64)
+    }
+}
+
+/// The coverage of one file
+#[derive(Debug, Clone, Serialize)]
+pub struct FileCoverage {
+    pub file: String,
+    #[serde(flatten)]
+    pub lines: LineCounts,
+    /// Runs of uncovered lines (inclusive), uninterrupted by covered ones
+    pub uncovered: Vec<(u32, u32)>,
+}
+
+/// Coverage as reported after a run
+#[derive(Debug, Clone, Serialize)]
+pub struct CoverageReport {
+    pub lines: LineCounts,
+    /// Coverage of the added or modified lines, when the run was restricted
+    /// to a change set
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub changed_lines: Option<LineCounts>,
+    /// Per file; only the changed lines of changed files when there is a
+    /// change set
+    pub files: Vec<FileCoverage>,
+}
+
+impl Coverage {
+    /// The report `run` wrote, with paths made relative to the analyzed
+    /// `directory`. Files outside it (e.g. dependencies) are left out.
+    pub fn read(run: &InstrumentedTool, directory: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(&run.report).with_context(|| {
+            format!("{} wrote no coverage report to {}", run.tool.name, run.report.display())
+        })?;
+        let working_dir = run.tool.working_dir(directory);
+        let paths = Paths::new(directory, &working_dir);
+        match run.format {
+            CoverageFormat::Lcov => Ok(parse_lcov(&contents, &paths)),
+            format => parse_xml(&contents, format, &paths)
+                .with_context(|| format!("Could not parse {}", run.report.display())),
+        }
+    }
+
+    /// Add the lines `other` covers
+    pub fn merge(&mut self, other: Coverage) {
+        for (file, lines) in other.files {
+            let merged = self.files.entry(file).or_default();
+            for (line, hits) in lines {
+                *merged.entry(line).or_default() += hits;
+            }
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    /// Totals and per-file coverage, restricted to the lines of `changes`
+    /// if given
+    pub fn report(&self, changes: Option<&ChangeSet>) -> CoverageReport {
+        let mut lines = LineCounts::default();
+        let mut changed_lines = LineCounts::default();
+        let mut files = Vec::new();
+        for (file, hits) in &self.files {
+            let mut counts = LineCounts::default();
+            let mut uncovered: Vec<(u32, u32)> = Vec::new();
+            let mut open = false;
+            for (&line, &count) in hits {
+                lines.total += 1;
+                lines.covered += usize::from(count > 0);
+                let changed = changes.is_none_or(|changes| changes.contains_line(file, line));
+                if !changed {
+                    // A covered line still ends a run of uncovered ones
+                    open &= count == 0;
+                    continue;
+                }
+                changed_lines.total += 1;
+                changed_lines.covered += usize::from(count > 0);
+                counts.total += 1;
+                if count > 0 {
+                    counts.covered += 1;
+                    open = false;
+                } else if open {
+                    if let Some(range) = uncovered.last_mut() {
+                        range.1 = line;
+                    }
+                } else {
+                    uncovered.push((line, line));
+                    open = true;
+                }
+            }
+            if counts.total > 0 {
+                files.push(FileCoverage {
+                    file: file.clone(),
+                    lines: counts,
+                    uncovered,
+                });
+            }
+        }
+        CoverageReport {
+            lines,
+            changed_lines: changes.map(|_| changed_lines),
+            files,
+        }
+    }
+
+    fn add(&mut self, file: String, line: u32, hits: u64) {
+        // Reports may list a line more than once, e.g. per method and class
+        let count = self.files.entry(file).or_default().entry(line).or_default();
+        *count = (*count).max(hits);
+    }
+}
+
+impl CoverageReport {
+    /// Files with uncovered lines, least covered first
+    pub fn least_covered(&self) -> Vec<&FileCoverage> {
+        let mut files: Vec<&FileCoverage> = self
+            .files
+            .iter()
+            .filter(|file| file.lines.covered < file.lines.total)
+            .collect();
+        files.sort_by(|a, b| {
+            let percent = |file: &FileCoverage| file.lines.percent().unwrap_or(100.0);
+            percent(a).total_cmp(&percent(b)).then_with(|| a.file.cmp(&b.file))
+        });
+        files
+    }
+}
+
+impl FileCoverage {
+    /// `3-7, 12` for the uncovered lines
+    pub fn ranges(&self) -> String {
+        self.uncovered
+            .iter()
+            .map(|(start, end)| match start == end {
+                true => start.to_string(),
+                false => format!("{}-{}", start, end),
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+/// Resolves the paths in a report, which may be absolute or relative to the
+/// tool's working directory
+struct Paths {
+    directory: PathBuf,
+    working_dir: PathBuf,
+}
+
+impl Paths {
+    fn new(directory: &Path, working_dir: &Path) -> Self {
+        let canonical = |path: &Path| path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        Self {
+            directory: canonical(directory),
+            working_dir: canonical(working_dir),
+        }
+    }
+
+    fn relative(&self, file: &str, base: Option<&Path>) -> Option<String> {
+        let path = base.unwrap_or(&self.working_dir).join(file);
+        let path = path.canonicalize().unwrap_or(path);
+        let relative = path.strip_prefix(&self.directory).ok()?;
+        Some(relative.to_string_lossy().to_string())
+    }
+}
+
+fn parse_lcov(contents: &str, paths: &Paths) -> Coverage {
+    let mut coverage = Coverage::default();
+    let mut file = None;
+    for line in contents.lines() {
+        if let Some(path) = line.strip_prefix("SF:") {
+            file = paths.relative(path.trim(), None);
+        } else if line == "end_of_record" {
+            file = None;
+        } else if let (Some(file), Some(record)) = (&file, line.strip_prefix("DA:")) {
+            // DA:<line>,<hits>[,<checksum>]
+            let mut fields = record.split(',');
+            if let (Some(Ok(number)), Some(Ok(hits))) = (
+                fields.next().map(str::parse),
+                fields.next().map(str::parse),
+            ) {
+                coverage.add(file.clone(), number, hits);
+            }
+        }
+    }
+    coverage
+}
+
+/// Cobertura (`<class filename>` with `<line number hits>`, relative to the
+/// `<source>` directories) or Clover (`<file name>` with `<line num count>`)
+fn parse_xml(contents: &str, format: CoverageFormat, paths: &Paths) -> Result<Coverage> {
+    let mut coverage = Coverage::default();
+    let mut reader = Reader::from_str(contents);
+    let mut sources: Vec<PathBuf> = Vec::new();
+    let mut in_source = false;
+    let mut file: Option<String> = None;
+    loop {
+        match reader.read_event()? {
+            Event::Start(element) | Event::Empty(element) => {
+                let value = |name: &str| attribute(&element, name);
+                match (format, element.name().as_ref()) {
+                    (CoverageFormat::Cobertura, b"source") => in_source = true,
+                    (CoverageFormat::Cobertura, b"class") => {
+                        file = value("filename").and_then(|name| {
+                            let base = sources.iter().find(|source| source.join(&name).exists());
+                            paths.relative(&name, base.map(PathBuf::as_path))
+                        });
+                    }
+                    (CoverageFormat::Clover, b"file") => {
+                        file = value("path")
+                            .or_else(|| value("name"))
+                            .and_then(|name| paths.relative(&name, None));
+                    }
+                    (CoverageFormat::Cobertura, b"line") => {
+                        let number = value("number").and_then(|n| n.parse().ok());
+                        let hits = value("hits").and_then(|n| n.parse().ok());
+                        if let (Some(file), Some(number), Some(hits)) = (&file, number, hits) {
+                            coverage.add(file.clone(), number, hits);
+                        }
+                    }
+                    // Method lines repeat the count of the method's first
+                    // statement
+                    (CoverageFormat::Clover, b"line") if value("type").as_deref() != Some("method") => {
+                        let number = value("num").and_then(|n| n.parse().ok());
+                        let hits = value("count").and_then(|n| n.parse().ok());
+                        if let (Some(file), Some(number), Some(hits)) = (&file, number, hits) {
+                            coverage.add(file.clone(), number, hits);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            Event::Text(text) if in_source => {
+                sources.push(PathBuf::from(text.unescape()?.trim()));
+            }
+            Event::End(element) if element.name().as_ref() == b"source" => in_source = false,
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    Ok(coverage)
+}
+
+fn attribute(element: &BytesStart, name: &str) -> Option<String> {
+    let value = element.try_get_attribute(name).ok()??;
+    Some(value.unescape_value().ok()?.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(coverage: &Coverage, file: &str) -> Vec<(u32, u64)> {
+        coverage.files[file].iter().map(|(&line, &hits)| (line, hits)).collect()
+    }
+
+    #[test]
+    fn parses_lcov_with_relative_and_absolute_paths() {
+        let directory = tempfile::tempdir().unwrap();
+        let root = directory.path().display();
+        let lcov = format!(
+            "\
+TN:
+SF:src/math.js
+FN:1,add
+FNDA:3,add
+FNF:1
+FNH:1
+DA:1,3
+DA:2,3
+DA:4,0
+LF:3
+LH:2
+end_of_record
+TN:
+SF:{root}/src/lib.rs
+DA:10,1,Vh1YdRHhUnHPDpJhrRPJ3Q
+DA:11,0,kZcSgfTQ2nFbzYKvBDCn1A
+end_of_record
+SF:/home/dev/.cargo/registry/src/serde-1.0.210/src/de.rs
+DA:1,5
+end_of_record
+"
+        );
+        let paths = Paths::new(directory.path(), directory.path());
+        let coverage = parse_lcov(&lcov, &paths);
+        assert_eq!(coverage.files.keys().collect::<Vec<_>>(), ["src/lib.rs", "src/math.js"]);
+        assert_eq!(lines(&coverage, "src/math.js"), [(1, 3), (2, 3), (4, 0)]);
+        assert_eq!(lines(&coverage, "src/lib.rs"), [(10, 1), (11, 0)]);
+    }
+
+    #[test]
+    fn parses_cobertura_relative_to_its_sources() {
+        let directory = tempfile::tempdir().unwrap();
+        let root = directory.path().canonicalize().unwrap();
+        std::fs::create_dir_all(root.join("src/app")).unwrap();
+        std::fs::write(root.join("src/app/cart.py"), "").unwrap();
+        let cobertura = format!(
+            r#"<?xml version="1.0" ?>
+<coverage version="7.6.1" timestamp="1729312000000" lines-valid="5" lines-covered="3" line-rate="0.6" branches-covered="0" branches-valid="0" branch-rate="0" complexity="0">
+	<!-- Generated by coverage.py: https://coverage.readthedocs.io/en/7.6.1 -->
+	<sources>
+		<source>{root}/lib</source>
+		<source>{root}/src</source>
+	</sources>
+	<packages>
+		<package name="app" line-rate="0.6" branch-rate="0" complexity="0">
+			<classes>
+				<class name="cart.py" filename="app/cart.py" complexity="0" line-rate="0.6" branch-rate="0">
+					<methods/>
+					<lines>
+						<line number="1" hits="1"/>
+						<line number="2" hits="1"/>
+						<line number="5" hits="4"/>
+						<line number="6" hits="0"/>
+						<line number="7" hits="0"/>
+					</lines>
+				</class>
+			</classes>
+		</package>
+	</packages>
+</coverage>
+"#,
+            root = root.display()
+        );
+        let paths = Paths::new(&root, &root);
+        let coverage = parse_xml(&cobertura, CoverageFormat::Cobertura, &paths).unwrap();
+        assert_eq!(coverage.files.keys().collect::<Vec<_>>(), ["src/app/cart.py"]);
+        assert_eq!(lines(&coverage, "src/app/cart.py"), [(1, 1), (2, 1), (5, 4), (6, 0), (7, 0)]);
+    }
+
+    #[test]
+    fn parses_clover_from_phpunit_and_jest() {
+        let directory = tempfile::tempdir().unwrap();
+        let root = directory.path().display();
+        // PHPUnit names files by absolute path and lists methods as lines
+        let phpunit = format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<coverage generated="1729312000">
+  <project timestamp="1729312000">
+    <file name="{root}/src/Cart.php">
+      <class name="App\Cart" namespace="global">
+        <metrics complexity="2" methods="1" coveredmethods="0" conditionals="0" coveredconditionals="0" statements="2" coveredstatements="1" elements="3" coveredelements="1"/>
+      </class>
+      <line num="9" type="method" name="total" visibility="public" complexity="2" crap="2.15" count="3"/>
+      <line num="11" type="stmt" count="3"/>
+      <line num="12" type="stmt" count="0"/>
+      <metrics loc="14" ncloc="14" classes="1" methods="1" coveredmethods="0" conditionals="0" coveredconditionals="0" statements="2" coveredstatements="1" elements="3" coveredelements="1"/>
+    </file>
+  </project>
+</coverage>
+"#
+        );
+        let paths = Paths::new(directory.path(), directory.path());
+        let coverage = parse_xml(&phpunit, CoverageFormat::Clover, &paths).unwrap();
+        assert_eq!(lines(&coverage, "src/Cart.php"), [(11, 3), (12, 0)]);
+
+        // Jest gives the file name alone, and the path separately
+        let jest = format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<coverage generated="1729312000" clover="3.2.0">
+  <project timestamp="1729312000" name="All files">
+    <metrics statements="3" coveredstatements="2" conditionals="0" coveredconditionals="0" methods="1" coveredmethods="1" elements="4" coveredelements="3" complexity="0" loc="3" ncloc="3" packages="1" files="1" classes="1"/>
+    <file name="math.js" path="{root}/web/src/math.js">
+      <metrics statements="3" coveredstatements="2" conditionals="0" coveredconditionals="0" methods="1" coveredmethods="1"/>
+      <line num="1" count="3" type="stmt"/>
+      <line num="2" count="3" type="stmt"/>
+      <line num="4" count="0" type="stmt"/>
+    </file>
+  </project>
+</coverage>
+"#
+        );
+        let paths = Paths::new(directory.path(), &directory.path().join("web"));
+        let coverage = parse_xml(&jest, CoverageFormat::Clover, &paths).unwrap();
+        assert_eq!(lines(&coverage, "web/src/math.js"), [(1, 3), (2, 3), (4, 0)]);
+    }
+
+    fn coverage(files: &[(&str, &[(u32, u64)])]) -> Coverage {
+        let mut coverage = Coverage::default();
+        for (file, lines) in files {
+            for &(line, hits) in lines.iter() {
+                coverage.add(file.to_string(), line, hits);
+            }
+        }
+        coverage
+    }
+
+    #[test]
+    fn groups_uncovered_lines_into_ranges() {
+        let coverage = coverage(&[
+            (
+                "src/cart.py",
+                // Line 9 is not instrumented, so 8-10 is one run
+                &[(1, 1), (2, 0), (3, 0), (4, 0), (6, 2), (8, 0), (10, 0), (12, 1), (13, 0)],
+            ),
+            ("src/models.py", &[(1, 1), (2, 0)]),
+            ("src/util.py", &[(1, 1)]),
+        ]);
+        let report = coverage.report(None);
+        assert_eq!(report.lines, LineCounts { covered: 5, total: 12 });
+        assert_eq!(report.changed_lines, None);
+
+        let cart = &report.files[0];
+        assert_eq!(cart.uncovered, [(2, 4), (8, 10), (13, 13)]);
+        assert_eq!(cart.ranges(), "2-4, 8-10, 13");
+
+        let least: Vec<&str> = report.least_covered().iter().map(|file| file.file.as_str()).collect();
+        assert_eq!(least, ["src/cart.py", "src/models.py"]);
+    }
+
+    #[test]
+    fn restricts_the_report_to_changed_lines() {
+        let coverage = coverage(&[
+            ("src/cart.py", &[(1, 1), (2, 0), (3, 0), (4, 0), (5, 1), (6, 0)]),
+            ("src/models.py", &[(1, 0)]),
+        ]);
+        let changes = ChangeSet::from_diff(
+            "\
+diff --git a/src/cart.py b/src/cart.py
+index 3b18e51..a9c4f2d 100644
+--- a/src/cart.py
++++ b/src/cart.py
+@@ -2,0 +3,2 @@ def total(items):
++    if not items:
++        return 0
+@@ -5 +6 @@ def total(items):
+-    return sum(items)
++    return sum(item.price for item in items)
+",
+        );
+        let report = coverage.report(Some(&changes));
+        assert_eq!(report.lines, LineCounts { covered: 2, total: 7 });
+        assert_eq!(report.changed_lines, Some(LineCounts { covered: 0, total: 3 }));
+        assert_eq!(report.files.len(), 1);
+        // Line 5 is unchanged but covered, so it still splits the runs
+        assert_eq!(report.files[0].uncovered, [(3, 4), (6, 6)]);
+    }
+}
@@ -0,0 +1,176 @@
+use crate::config::Config;
+use crate::paths::PathFilter;
+use crate::tools::availability::{self, Availability};
+use crate::tools::detection::{Check, Verdict};
+use crate::tools::{detect_tools_with_config, diagnose_tools};
+use anyhow::Result;
+use std::fmt::Write as _;
+use std::io::Write as _;
+use std::path::Path;
+
+/// Verdicts on tools of one project that share their checks: a detected
+/// tool on its own, or undetected tools that failed the same way
+pub struct Group<'a> {
+    pub verdicts: Vec<&'a Verdict>,
+    /// The checks that decided, up to the first failed one
+    pub checks: &'a [Check],
+}
+
+impl<'a> Group<'a> {
+    pub fn project(&self) -> &'a str {
+        &self.verdicts[0].project
+    }
+
+    pub fn detected(&self) -> bool {
+        self.verdicts[0].detected
+    }
+
+    pub fn suggestion(&self) -> Option<&'a str> {
+        self.verdicts[0].suggestion()
+    }
+
+    /// "ESLint, Jest", without the project prefix
+    pub fn tools(&self) -> String {
+        let names: Vec<&str> = self
+            .verdicts
+            .iter()
+            .map(|verdict| {
+                verdict
+                    .tool
+                    .strip_prefix(&format!("{}/", verdict.project))
+                    .unwrap_or(&verdict.tool)
+            })
+            .collect();
+        names.join(", ")
+    }
+}
+
+/// Group `verdicts`, so that e.g. every Node.js tool of a project without
+/// `package.json` shares one entry
+pub fn groups(verdicts: &[Verdict]) -> Vec<Group<'_>> {
+    let mut groups: Vec<Group> = Vec::new();
+    for verdict in verdicts {
+        let decisive = match verdict.checks.iter().position(|check| !check.passed) {
+            Some(failed) => &verdict.checks[..=failed],
+            None => &verdict.checks[..],
+        };
+        match groups.last_mut() {
+            Some(group)
+                if !verdict.detected
+                    && !group.detected()
+                    && group.project() == verdict.project
+                    && group.checks == decisive =>
+            {
+                group.verdicts.push(verdict);
+            }
+            _ => groups.push(Group {
+                verdicts: vec![verdict],
+                checks: decisive,
+            }),
+        }
+    }
+    groups
+}
+
+/// Print the verdict on every known tool of every project in `directory`:
+/// the checks that decided it, what to add for tools that were not
+/// detected, and whether the program of each detected tool is installed
+pub async fn print(directory: &Path, config: &Config) -> Result<()> {
+    let filter = PathFilter::new(directory, &config.ignore_patterns);
+    let verdicts = diagnose_tools(directory, &filter);
+    let tools = detect_tools_with_config(directory, config);
+
+    let mut out = String::new();
+    let mut project = None;
+    for group in groups(&verdicts) {
+        if project != Some(group.project()) {
+            project = Some(group.project());
+            let header = if group.project().is_empty() { "." } else { group.project() };
+            let _ = writeln!(out, "{}{}", if out.is_empty() { "" } else { "\n" }, header);
+        }
+        let _ = writeln!(out, "  {} {}", mark(group.detected()), group.tools());
+        for check in group.checks {
+            let _ = writeln!(out, "      {} {}", mark(check.passed), check.description);
+        }
+        if let Some(suggestion) = group.suggestion() {
+            let _ = writeln!(out, "      → {}", suggestion);
+        }
+        let verdict = group.verdicts[0];
+        let Some(tool) = tools.iter().find(|tool| verdict.detected && tool.name == verdict.tool) else {
+            continue;
+        };
+        match availability::check(tool, directory).await {
+            Availability::Installed { banner, .. } => {
+                let _ = writeln!(out, "      ✓ installed: {}", banner);
+            }
+            Availability::Missing { reason, hint } => {
+                let _ = writeln!(out, "      ✗ {}", reason);
+                let _ = writeln!(out, "      → {}", hint);
+            }
+        }
+    }
+    let detected = verdicts.iter().filter(|verdict| verdict.detected).count();
+    let _ = writeln!(out, "\n{} of {} known tools detected", detected, verdicts.len());
+    let _ = std::io::stdout().write_all(out.as_bytes());
+    Ok(())
+}
+
+/// The mark of a detected tool or passed check, or of the opposite
+pub fn mark(passed: bool) -> &'static str {
+    if passed {
+        "✓"
+    } else {
+        "✗"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn verdict(tool: &str, project: &str, checks: &[(&str, bool)]) -> Verdict {
+        let checks: Vec<Check> = checks
+            .iter()
+            .map(|(description, passed)| Check::new(*description, *passed))
+            .collect();
+        Verdict {
+            tool: tool.to_string(),
+            project: project.to_string(),
+            detected: checks.iter().all(|check| check.passed),
+            checks,
+        }
+    }
+
+    fn summary(verdicts: &[Verdict]) -> Vec<(String, bool, usize)> {
+        groups(verdicts)
+            .iter()
+            .map(|group| (group.tools(), group.detected(), group.checks.len()))
+            .collect()
+    }
+
+    #[test]
+    fn groups_tools_that_failed_the_same_way() {
+        let no_package = ("found package.json", false);
+        let verdicts = [
+            verdict("web/ESLint", "web", &[no_package, ("found eslint.config.js", false)]),
+            verdict("web/Jest", "web", &[no_package, ("found jest.config.js", false)]),
+            verdict("web/Prettier", "web", &[("found package.json", true), ("found .prettierrc", false)]),
+            verdict("web/TypeScript", "web", &[("found package.json", true), ("found .prettierrc", false)]),
+            verdict("Cargo", "", &[("found Cargo.toml", true)]),
+            verdict("Clippy", "", &[("found Cargo.toml", true)]),
+            verdict("api/ESLint", "api", &[no_package]),
+        ];
+        assert_eq!(
+            summary(&verdicts),
+            [
+                // Only the checks up to the first failure decide
+                ("ESLint, Jest".to_string(), false, 1),
+                ("Prettier, TypeScript".to_string(), false, 2),
+                // Detected tools are listed on their own
+                ("Cargo".to_string(), true, 1),
+                ("Clippy".to_string(), true, 1),
+                ("ESLint".to_string(), false, 1),
+            ]
+        );
+    }
+}
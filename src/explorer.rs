@@ -0,0 +1,146 @@
+use crate::testcases::{listing, parse_listing, TestOutcome, TestRunner};
+use crate::tools::Tool;
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::path::Path;
+use std::process::Stdio;
+
+/// One line of the test explorer: a file, module or class with everything
+/// below it, or a single test
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Row {
+    pub depth: usize,
+    pub label: String,
+    /// The tests this row runs, as the runner names them
+    pub tests: Vec<String>,
+    pub leaf: bool,
+}
+
+/// The tests `tool` would run, listed without running them
+pub async fn discover(tool: &Tool, directory: &Path) -> Result<Vec<String>> {
+    let runner = TestRunner::of(tool).context("Not a test runner")?;
+    let list = listing(tool).context("Not a test runner")?;
+    let working_dir = tool.working_dir(directory);
+    let output = tokio::process::Command::new(&list.command)
+        .args(&list.args)
+        .current_dir(&working_dir)
+        .stdin(Stdio::null())
+        .output()
+        .await
+        .with_context(|| format!("Could not start `{}`", list.command))?;
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    // Jest prints absolute paths of the canonical directory
+    let working_dir = working_dir.canonicalize().unwrap_or(working_dir);
+    let tests = parse_listing(runner, &stdout, &working_dir);
+    if tests.is_empty() && !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let reason = stderr
+            .lines()
+            .chain(stdout.lines())
+            .rfind(|line| !line.trim().is_empty())
+            .unwrap_or("no output");
+        anyhow::bail!("Could not list the tests of {}: {}", tool.name, reason.trim());
+    }
+    Ok(tests)
+}
+
+/// Where `test` sits in the tree: its file (or class, for PHPUnit) first,
+/// then the modules, classes or `describe` blocks around it
+pub fn segments(runner: TestRunner, test: &str) -> Vec<String> {
+    let parts: Vec<&str> = match runner {
+        TestRunner::Pytest | TestRunner::Cargo => test.split("::").collect(),
+        TestRunner::Jest => test.split(" › ").collect(),
+        TestRunner::PhpUnit => match test.split_once("::") {
+            Some((class, method)) => vec![class, method],
+            None => vec![test],
+        },
+    };
+    parts.into_iter().map(str::to_string).collect()
+}
+
+/// The rows of the tree over the tests matching `filter` (see [`fuzzy_match`])
+pub fn rows(runner: TestRunner, tests: &[String], filter: &str) -> Vec<Row> {
+    let mut paths: Vec<(Vec<String>, &String)> = tests
+        .iter()
+        .filter(|test| fuzzy_match(filter, test))
+        .map(|test| (segments(runner, test), test))
+        .collect();
+    paths.sort();
+
+    let mut rows: Vec<Row> = Vec::new();
+    // The rows of the groups around the previous test, outermost first
+    let mut open: Vec<usize> = Vec::new();
+    let mut previous: &[String] = &[];
+    for (path, test) in &paths {
+        let (groups, name) = path.split_at(path.len() - 1);
+        let shared = groups
+            .iter()
+            .zip(previous)
+            .take_while(|(a, b)| a == b)
+            .count();
+        open.truncate(shared);
+        for (depth, group) in groups.iter().enumerate().skip(shared) {
+            open.push(rows.len());
+            rows.push(Row {
+                depth,
+                label: group.clone(),
+                tests: Vec::new(),
+                leaf: false,
+            });
+        }
+        for &group in &open {
+            rows[group].tests.push(test.to_string());
+        }
+        rows.push(Row {
+            depth: groups.len(),
+            label: name[0].clone(),
+            tests: vec![test.to_string()],
+            leaf: true,
+        });
+        previous = groups;
+    }
+    rows
+}
+
+/// Whether the characters of `pattern` appear in `text` in order, ignoring
+/// case and whitespace in the pattern
+pub fn fuzzy_match(pattern: &str, text: &str) -> bool {
+    let mut text = text.chars().flat_map(char::to_lowercase);
+    pattern
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .flat_map(char::to_lowercase)
+        .all(|wanted| text.any(|c| c == wanted))
+}
+
+/// The combined outcome of `tests` in the latest runs: failed if any
+/// failed, passed once all have run. Jest tests listed by file take the
+/// outcomes of the tests in that file.
+pub fn outcome(outcomes: &HashMap<String, TestOutcome>, tests: &[String]) -> Option<TestOutcome> {
+    let mut known = 0;
+    let mut passed = false;
+    for test in tests {
+        let prefix = format!("{} › ", test);
+        let mut found = outcomes
+            .iter()
+            .filter(|(name, _)| *name == test || name.starts_with(&prefix))
+            .map(|(_, outcome)| *outcome)
+            .peekable();
+        if found.peek().is_some() {
+            known += 1;
+        }
+        for outcome in found {
+            match outcome {
+                TestOutcome::Failed => return Some(TestOutcome::Failed),
+                TestOutcome::Passed => passed = true,
+                TestOutcome::Skipped => {}
+            }
+        }
+    }
+    match (known == tests.len() && known > 0, passed) {
+        (false, _) => None,
+        (true, true) => Some(TestOutcome::Passed),
+        (true, false) => Some(TestOutcome::Skipped),
+    }
+}
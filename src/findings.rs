@@ -0,0 +1,331 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+
+/// How serious a finding is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+    Info,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Error => write!(f, "error"),
+            Severity::Warning => write!(f, "warning"),
+            Severity::Info => write!(f, "info"),
+        }
+    }
+}
+
+/// A single issue reported by a tool, located in a file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Finding {
+    /// The name of the tool that reported the finding
+    pub tool: String,
+    /// Path of the affected file, relative to the analyzed directory
+    pub file: String,
+    /// 1-based line number, if the tool reported one
+    pub line: Option<u32>,
+    /// 1-based column number, if the tool reported one
+    pub column: Option<u32>,
+    pub severity: Severity,
+    /// The tool-specific rule or error code (e.g. "F401", "E0308")
+    pub rule: Option<String>,
+    pub message: String,
+}
+
+impl Finding {
+    /// A hash identifying this issue independently of its line number, so it
+    /// keeps its identity when surrounding code moves
+    pub fn fingerprint(&self) -> String {
+        hash_parts(&[
+            &self.tool,
+            &self.file,
+            self.rule.as_deref().unwrap_or(""),
+            &self.message,
+        ])
+    }
+}
+
+/// Fingerprints for `findings`, made unique by mixing in the occurrence index
+/// of issues that would otherwise hash the same (e.g. the same warning on two
+/// lines of one file)
+pub fn unique_fingerprints<'a>(findings: impl IntoIterator<Item = &'a Finding>) -> Vec<String> {
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    findings
+        .into_iter()
+        .map(|finding| {
+            let fingerprint = finding.fingerprint();
+            let occurrence = seen.entry(fingerprint.clone()).or_default();
+            *occurrence += 1;
+            if *occurrence == 1 {
+                fingerprint
+            } else {
+                hash_parts(&[&fingerprint, &occurrence.to_string()])
+            }
+        })
+        .collect()
+}
+
+/// Hex-encoded SHA-256 over `parts`, separated so that ("ab", "c") and
+/// ("a", "bc") differ
+pub fn hash_parts(parts: &[&str]) -> String {
+    let mut hasher = Sha256::new();
+    for part in parts {
+        hasher.update(part.as_bytes());
+        hasher.update([0]);
+    }
+    hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// Extract structured findings from a tool's raw output.
+///
+/// Tools are not asked for a machine-readable format, so this recognises the
+/// common human-readable shapes instead:
+/// - `path:line[:col]: message` (flake8, pylint, mypy, eslint unix, phpcs emacs)
+/// - `path(line,col): message` (tsc)
+/// - rustc/clippy diagnostics with a ` --> path:line:col` location line
+/// - pytest `FAILED path::test - reason` summary lines
+pub fn parse_findings(tool: &str, output: &str, directory: &Path) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    let mut pending_rust: Option<(Severity, Option<String>, String)> = None;
+
+    for line in output.lines() {
+        if let Some(header) = parse_rust_header(line) {
+            pending_rust = Some(header);
+            continue;
+        }
+
+        if let Some(location) = line.trim_start().strip_prefix("--> ") {
+            if let Some((severity, rule, message)) = pending_rust.take() {
+                if let Some((file, line, column)) = split_location(location) {
+                    findings.push(Finding {
+                        tool: tool.to_string(),
+                        file: relative_path(file, directory),
+                        line: Some(line),
+                        column,
+                        severity,
+                        rule,
+                        message,
+                    });
+                }
+            }
+            continue;
+        }
+
+        // Clippy names its lint in a trailing help link; attach it to the
+        // finding we just produced.
+        if let Some(lint) = line.split("index.html#").nth(1) {
+            if let Some(last) = findings.last_mut() {
+                if last.rule.is_none() {
+                    last.rule = Some(format!("clippy::{}", lint.trim()));
+                }
+            }
+            continue;
+        }
+
+        if let Some(finding) = parse_pytest_failure(tool, line, directory)
+            .or_else(|| parse_tsc_line(tool, line, directory))
+            .or_else(|| parse_colon_line(tool, line, directory))
+        {
+            findings.push(finding);
+        }
+    }
+
+    findings
+}
+
+fn parse_rust_header(line: &str) -> Option<(Severity, Option<String>, String)> {
+    let (severity, rest) = if let Some(rest) = line.strip_prefix("error") {
+        (Severity::Error, rest)
+    } else if let Some(rest) = line.strip_prefix("warning") {
+        (Severity::Warning, rest)
+    } else {
+        return None;
+    };
+
+    let (rule, rest) = match rest.strip_prefix('[') {
+        Some(rest) => {
+            let end = rest.find(']')?;
+            (Some(rest[..end].to_string()), &rest[end + 1..])
+        }
+        None => (None, rest),
+    };
+
+    let message = rest.strip_prefix(": ")?.trim();
+    // Summary lines such as "warning: 3 warnings emitted" carry no location
+    if message.ends_with("emitted") || message.starts_with("could not compile") {
+        return None;
+    }
+    Some((severity, rule, message.to_string()))
+}
+
+fn parse_pytest_failure(tool: &str, line: &str, directory: &Path) -> Option<Finding> {
+    let rest = line
+        .strip_prefix("FAILED ")
+        .or_else(|| line.strip_prefix("ERROR "))?;
+    let (id, reason) = match rest.split_once(" - ") {
+        Some((id, reason)) => (id, reason),
+        None => (rest, "test failed"),
+    };
+    let (file, test) = id.split_once("::")?;
+    Some(Finding {
+        tool: tool.to_string(),
+        file: relative_path(file, directory),
+        line: None,
+        column: None,
+        severity: Severity::Error,
+        rule: Some(test.to_string()),
+        message: reason.trim().to_string(),
+    })
+}
+
+fn parse_tsc_line(tool: &str, line: &str, directory: &Path) -> Option<Finding> {
+    let open = line.find('(')?;
+    let close = line[open..].find("):")? + open;
+    let file = &line[..open];
+    if !looks_like_path(file) {
+        return None;
+    }
+    let (line_no, column) = line[open + 1..close].split_once(',')?;
+    let rest = line[close + 2..].trim();
+    let (severity, rest) = strip_severity(rest);
+    let (rule, message) = match rest.split_once(": ") {
+        Some((code, message)) if code.starts_with("TS") => (Some(code.to_string()), message),
+        _ => (None, rest),
+    };
+    Some(Finding {
+        tool: tool.to_string(),
+        file: relative_path(file, directory),
+        line: line_no.trim().parse().ok(),
+        column: column.trim().parse().ok(),
+        severity: severity.unwrap_or(Severity::Error),
+        rule,
+        message: message.to_string(),
+    })
+}
+
+fn parse_colon_line(tool: &str, line: &str, directory: &Path) -> Option<Finding> {
+    let mut parts = line.splitn(4, ':');
+    let file = parts.next()?;
+    if !looks_like_path(file) {
+        return None;
+    }
+    let line_no: u32 = parts.next()?.trim().parse().ok()?;
+    let third = parts.next()?;
+    let (column, rest) = match third.trim().parse::<u32>() {
+        Ok(column) => (Some(column), parts.next()?),
+        Err(_) => {
+            // No column: re-join what splitn separated
+            let rest = line.splitn(3, ':').nth(2)?;
+            (None, rest)
+        }
+    };
+
+    let rest = rest.trim();
+    let (severity, rest) = strip_severity(rest);
+    let (rule, message) = split_rule(rest);
+    let severity = severity
+        .or_else(|| rule.as_deref().map(severity_from_code))
+        .unwrap_or(Severity::Warning);
+
+    Some(Finding {
+        tool: tool.to_string(),
+        file: relative_path(file, directory),
+        line: Some(line_no),
+        column,
+        severity,
+        rule,
+        message: message.to_string(),
+    })
+}
+
+fn split_location(location: &str) -> Option<(&str, u32, Option<u32>)> {
+    let mut parts = location.trim().rsplitn(3, ':');
+    let last = parts.next()?;
+    let middle = parts.next()?;
+    match parts.next() {
+        Some(file) => Some((file, middle.parse().ok()?, last.parse().ok())),
+        None => Some((middle, last.parse().ok()?, None)),
+    }
+}
+
+/// Strip a leading "error:" / "warning:" / "note:" marker (mypy, eslint unix)
+fn strip_severity(text: &str) -> (Option<Severity>, &str) {
+    for (prefix, severity) in [
+        ("error", Severity::Error),
+        ("warning", Severity::Warning),
+        ("note", Severity::Info),
+    ] {
+        if let Some(rest) = text.strip_prefix(prefix) {
+            let rest = rest.trim_start_matches(':').trim_start();
+            return (Some(severity), rest);
+        }
+    }
+    (None, text)
+}
+
+/// Split a leading rule code ("F401 ...", "C0114: ...") or a trailing mypy
+/// style "[code]" from the message
+fn split_rule(text: &str) -> (Option<String>, &str) {
+    if let Some((code, message)) = text.split_once(' ') {
+        let code = code.trim_end_matches(':');
+        let mut chars = code.chars();
+        if code.len() >= 2
+            && chars.next().is_some_and(|c| c.is_ascii_uppercase())
+            && chars.all(|c| c.is_ascii_digit())
+        {
+            return (Some(code.to_string()), message.trim());
+        }
+    }
+    if let Some(open) = text.rfind(" [") {
+        if text.ends_with(']') {
+            let code = &text[open + 2..text.len() - 1];
+            if !code.contains(' ') {
+                return (Some(code.to_string()), text[..open].trim());
+            }
+        }
+    }
+    (None, text)
+}
+
+/// Map flake8/pylint style code prefixes to a severity
+fn severity_from_code(code: &str) -> Severity {
+    match code.chars().next() {
+        Some('E') | Some('F') => Severity::Error,
+        Some('C') | Some('R') | Some('I') => Severity::Info,
+        _ => Severity::Warning,
+    }
+}
+
+fn looks_like_path(candidate: &str) -> bool {
+    !candidate.is_empty()
+        && !candidate.contains(' ')
+        && !candidate.starts_with("http")
+        && (candidate.contains('/') || candidate.contains('.'))
+}
+
+/// Express `file` relative to `directory` when the tool reported it absolute
+fn relative_path(file: &str, directory: &Path) -> String {
+    let path = Path::new(file);
+    let relative = directory
+        .canonicalize()
+        .ok()
+        .and_then(|root| path.strip_prefix(root).ok().map(Path::to_path_buf))
+        .or_else(|| path.strip_prefix(directory).ok().map(Path::to_path_buf))
+        .unwrap_or_else(|| path.to_path_buf());
+    relative
+        .to_string_lossy()
+        .trim_start_matches("./")
+        .to_string()
+}
@@ -0,0 +1,228 @@
+use crate::paths::{link_dir, PathFilter, DEPENDENCY_DIRS};
+use crate::tools::Tool;
+use anyhow::{Context, Result};
+use similar::TextDiff;
+use std::collections::BTreeSet;
+use std::path::Path;
+use std::process::Stdio;
+
+/// One file a fix changes; contents are `None` where the file does not exist
+#[derive(Debug, Clone)]
+pub struct FileChange {
+    /// Path relative to the analyzed directory
+    pub path: String,
+    pub before: Option<Vec<u8>>,
+    pub after: Option<Vec<u8>>,
+}
+
+/// The changes a tool's fix makes, computed in a scratch copy and not yet
+/// applied to the analyzed directory
+#[derive(Debug, Clone)]
+pub struct FixPreview {
+    pub tool: String,
+    pub changes: Vec<FileChange>,
+    /// Something to tell the user about what was left out
+    pub note: Option<String>,
+    /// File and message of each finding the fix is meant to resolve, checked
+    /// when the tool reruns; empty when the fix is not about single findings
+    pub resolves: Vec<(String, String)>,
+}
+
+/// A fix that has been applied and can be undone
+#[derive(Debug, Clone)]
+pub struct AppliedFix {
+    pub tool: String,
+    changes: Vec<FileChange>,
+}
+
+/// Run the fix of `tool` in a scratch copy of `directory` and collect the
+/// changes it makes there. `directory` itself is left untouched.
+pub async fn preview(tool: &Tool, directory: &Path, filter: &PathFilter) -> Result<FixPreview> {
+    let fix = tool
+        .fix
+        .as_ref()
+        .ok_or_else(|| anyhow::anyhow!("{} cannot fix what it reports", tool.name))?;
+    let scratch = tempfile::Builder::new()
+        .prefix("codeguard-fix-")
+        .tempdir()
+        .context("Could not create a scratch directory")?;
+    copy_tree(directory, scratch.path(), filter)?;
+
+    let output = tokio::process::Command::new(&fix.command)
+        .args(&fix.args)
+        .current_dir(tool.working_dir(scratch.path()))
+        .stdin(Stdio::null())
+        .output()
+        .await
+        .with_context(|| format!("Could not start `{}`", fix.command))?;
+    // Fixers exit non-zero for findings they cannot fix; what they did fix
+    // is still worth showing, so only a fix that changed nothing fails
+    let changes = compare(directory, scratch.path(), filter);
+    if changes.is_empty() && !output.status.success() {
+        anyhow::bail!(
+            "`{} {}` failed: {}",
+            fix.command,
+            fix.args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(FixPreview {
+        tool: tool.name.clone(),
+        changes,
+        note: None,
+        resolves: Vec::new(),
+    })
+}
+
+impl FixPreview {
+    /// The changes as a unified diff
+    pub fn diff(&self) -> String {
+        let mut diff = String::new();
+        for change in &self.changes {
+            let before = String::from_utf8_lossy(change.before.as_deref().unwrap_or_default());
+            let after = String::from_utf8_lossy(change.after.as_deref().unwrap_or_default());
+            let old_header = match change.before {
+                Some(_) => format!("a/{}", change.path),
+                None => "/dev/null".to_string(),
+            };
+            let new_header = match change.after {
+                Some(_) => format!("b/{}", change.path),
+                None => "/dev/null".to_string(),
+            };
+            diff.push_str(
+                &TextDiff::from_lines(&before, &after)
+                    .unified_diff()
+                    .header(&old_header, &new_header)
+                    .to_string(),
+            );
+        }
+        diff
+    }
+
+    /// Write the changes to `directory`. Fails without writing anything if
+    /// any affected file changed since the preview was made.
+    pub fn apply(&self, directory: &Path) -> Result<AppliedFix> {
+        write_changes(directory, &self.changes, false)?;
+        Ok(AppliedFix {
+            tool: self.tool.clone(),
+            changes: self.changes.clone(),
+        })
+    }
+}
+
+impl AppliedFix {
+    /// Restore the files the fix changed to their prior contents. Fails
+    /// without writing anything if any of them changed after the fix.
+    pub fn undo(&self, directory: &Path) -> Result<()> {
+        write_changes(directory, &self.changes, true)
+    }
+}
+
+/// Replace the `before` side of each change with its `after` side, or the
+/// other way round when `reverse` is set
+fn write_changes(directory: &Path, changes: &[FileChange], reverse: bool) -> Result<()> {
+    let sides = |change: &FileChange| match reverse {
+        false => (change.before.clone(), change.after.clone()),
+        true => (change.after.clone(), change.before.clone()),
+    };
+    for change in changes {
+        let (expected, _) = sides(change);
+        if std::fs::read(directory.join(&change.path)).ok() != expected {
+            anyhow::bail!("{} changed in the meantime; nothing was written", change.path);
+        }
+    }
+    for change in changes {
+        let path = directory.join(&change.path);
+        match sides(change).1 {
+            Some(contents) => {
+                if let Some(parent) = path.parent() {
+                    std::fs::create_dir_all(parent)?;
+                }
+                std::fs::write(&path, contents)
+                    .with_context(|| format!("Could not write {}", path.display()))?;
+            }
+            None => std::fs::remove_file(&path)
+                .with_context(|| format!("Could not remove {}", path.display()))?,
+        }
+    }
+    Ok(())
+}
+
+/// Copy the files of `source` that are not ignored into `target`, linking
+/// ignored dependency directories instead of copying them
+fn copy_tree(source: &Path, target: &Path, filter: &PathFilter) -> Result<()> {
+    link_dependencies(source, target, filter)?;
+    for entry in filter.walk() {
+        let Ok(relative) = entry.path().strip_prefix(source) else {
+            continue;
+        };
+        // Whatever lies inside a link is the original itself
+        if relative.ancestors().skip(1).any(|ancestor| {
+            !ancestor.as_os_str().is_empty() && target.join(ancestor).is_symlink()
+        }) {
+            continue;
+        }
+        let destination = target.join(relative);
+        match entry.file_type() {
+            Some(file_type) if file_type.is_dir() => {
+                std::fs::create_dir_all(&destination)?;
+                link_dependencies(entry.path(), &destination, filter)?;
+            }
+            Some(file_type) if file_type.is_file() => {
+                std::fs::copy(entry.path(), &destination)
+                    .with_context(|| format!("Could not copy {}", entry.path().display()))?;
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Link the dependency directories of `source` that the walk skips. The
+/// others, e.g. a committed `vendor/`, are copied like any directory:
+/// copying into a link would write onto the original files, and fixers
+/// would change them without a preview.
+fn link_dependencies(source: &Path, target: &Path, filter: &PathFilter) -> Result<()> {
+    for name in DEPENDENCY_DIRS {
+        let dependencies = source.join(name);
+        if dependencies.is_dir()
+            && filter.is_ignored(&dependencies, true)
+            && !target.join(name).exists()
+        {
+            link_dir(&dependencies, &target.join(name))?;
+        }
+    }
+    Ok(())
+}
+
+/// Files whose contents differ between `original` and its scratch copy,
+/// ignoring what the filter ignores
+fn compare(original: &Path, scratch: &Path, filter: &PathFilter) -> Vec<FileChange> {
+    let scratch_filter = PathFilter::new(scratch, filter.patterns());
+    let files = |root: &Path, filter: &PathFilter| -> BTreeSet<String> {
+        filter
+            .walk()
+            .filter(|entry| entry.file_type().is_some_and(|t| t.is_file()))
+            .filter_map(|entry| {
+                let relative = entry.path().strip_prefix(root).ok()?;
+                Some(relative.to_string_lossy().to_string())
+            })
+            .collect()
+    };
+    let mut paths = files(original, filter);
+    paths.extend(files(scratch, &scratch_filter));
+
+    let mut changes = Vec::new();
+    for path in paths {
+        let before = std::fs::read(original.join(&path)).ok();
+        let after = std::fs::read(scratch.join(&path)).ok();
+        if before != after {
+            changes.push(FileChange {
+                path,
+                before,
+                after,
+            });
+        }
+    }
+    changes
+}
@@ -0,0 +1,321 @@
+use crate::config::{GatePolicy, GatesConfig, SeverityThresholds};
+use crate::coverage::CoverageReport;
+use crate::findings::Finding;
+use crate::report::{FindingCounts, RunReport, ToolStatus};
+use serde::Serialize;
+
+/// The category of the test tools that collect coverage, whose policy the
+/// coverage gate follows
+const COVERAGE_CATEGORY: &str = "testing";
+
+/// What the quality gate needs to know about one tool's outcome
+pub struct GateInput<'a> {
+    pub tool: &'a str,
+    pub category: &'a str,
+    pub status: ToolStatus,
+    pub findings: &'a [Finding],
+}
+
+/// The verdict of the quality gate over a run
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct GateOutcome {
+    pub passed: bool,
+    /// Human-readable reasons the gate failed
+    pub violations: Vec<String>,
+    /// Advisory tools that failed without failing the gate
+    pub advisory_failures: Vec<String>,
+    /// Reasons an advisory gate would have failed, e.g. coverage below its
+    /// minimum while the `testing` category is advisory
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub advisory_violations: Vec<String>,
+}
+
+/// Evaluate the gates in `config` over the outcome of each tool
+pub fn evaluate<'a>(
+    config: &GatesConfig,
+    draft: bool,
+    inputs: impl IntoIterator<Item = GateInput<'a>>,
+) -> GateOutcome {
+    let mut outcome = GateOutcome::default();
+    let mut gated: Vec<&Finding> = Vec::new();
+    let mut by_category: Vec<(&str, Vec<&Finding>)> = Vec::new();
+
+    for input in inputs {
+        match policy(config, input.category, draft) {
+            GatePolicy::Ignore => continue,
+            GatePolicy::Advisory => {
+                if input.status == ToolStatus::Failed {
+                    outcome.advisory_failures.push(input.tool.to_string());
+                }
+                continue;
+            }
+            GatePolicy::Required => {}
+        }
+
+        if input.status == ToolStatus::Failed {
+            outcome.violations.push(format!("{} failed", input.tool));
+        }
+        gated.extend(input.findings);
+        match by_category.iter_mut().find(|(c, _)| *c == input.category) {
+            Some((_, findings)) => findings.extend(input.findings),
+            None => by_category.push((input.category, input.findings.iter().collect())),
+        }
+    }
+
+    check_thresholds(
+        &config.max_findings,
+        FindingCounts::of(gated),
+        "",
+        &mut outcome.violations,
+    );
+    for (category, findings) in by_category {
+        if let Some(gate) = config.categories.get(category) {
+            check_thresholds(
+                &gate.max_findings,
+                FindingCounts::of(findings),
+                &format!("{} ", category),
+                &mut outcome.violations,
+            );
+        }
+    }
+
+    outcome.passed = outcome.violations.is_empty();
+    outcome
+}
+
+/// Evaluate the gates over a finished headless run
+pub fn evaluate_report(config: &GatesConfig, draft: bool, report: &RunReport) -> GateOutcome {
+    let mut outcome = evaluate(
+        config,
+        draft,
+        report.tools.iter().map(|tool| GateInput {
+            tool: &tool.tool.name,
+            category: &tool.tool.category,
+            status: tool.status,
+            findings: &tool.findings,
+        }),
+    );
+    if let Some(coverage) = &report.coverage {
+        check_coverage(config, draft, coverage, &mut outcome);
+    }
+    outcome
+}
+
+/// The policy of `category`, or its draft policy on draft runs
+fn policy(config: &GatesConfig, category: &str, draft: bool) -> GatePolicy {
+    config
+        .categories
+        .get(category)
+        .map(|gate| match gate.draft_policy {
+            Some(draft_policy) if draft => draft_policy,
+            _ => gate.policy,
+        })
+        .unwrap_or_default()
+}
+
+/// Check `coverage` against the minimums of the coverage gate, under the
+/// policy of the test tools that collected it. A minimum fails when no line
+/// was instrumented at all, as a broken collection must not pass as covered;
+/// without instrumented changed lines there is nothing to hold to the
+/// changed-line minimum.
+pub fn check_coverage(
+    config: &GatesConfig,
+    draft: bool,
+    coverage: &CoverageReport,
+    outcome: &mut GateOutcome,
+) {
+    let gate = &config.coverage;
+    let mut shortfalls = Vec::new();
+    if coverage.lines.percent().is_none() && (gate.min_lines.is_some() || gate.min_changed_lines.is_some()) {
+        shortfalls.push("no coverage collected for the coverage minimums".to_string());
+    }
+    for (name, minimum, lines) in [
+        ("line coverage", gate.min_lines, Some(coverage.lines)),
+        ("changed-line coverage", gate.min_changed_lines, coverage.changed_lines),
+    ] {
+        if let (Some(minimum), Some(percent)) = (minimum, lines.and_then(|lines| lines.percent())) {
+            if percent < minimum {
+                shortfalls.push(format!("{} {:.1}% (minimum {}%)", name, percent, minimum));
+            }
+        }
+    }
+    match policy(config, COVERAGE_CATEGORY, draft) {
+        GatePolicy::Ignore => {}
+        GatePolicy::Advisory => outcome.advisory_violations.extend(shortfalls),
+        GatePolicy::Required => {
+            if !shortfalls.is_empty() {
+                outcome.passed = false;
+            }
+            outcome.violations.extend(shortfalls);
+        }
+    }
+}
+
+fn check_thresholds(
+    thresholds: &SeverityThresholds,
+    counts: FindingCounts,
+    scope: &str,
+    violations: &mut Vec<String>,
+) {
+    for (name, limit, count) in [
+        ("errors", thresholds.error, counts.errors),
+        ("warnings", thresholds.warning, counts.warnings),
+        ("infos", thresholds.info, counts.infos),
+    ] {
+        if let Some(limit) = limit {
+            if count > limit {
+                violations.push(format!("{} {}{} (limit {})", count, scope, name, limit));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::coverage::LineCounts;
+    use crate::findings::Severity;
+
+    const CONFIG: &str = "
+max_findings:
+  error: 0
+  warning: 2
+categories:
+  dependency:
+    policy: advisory
+  formatting:
+    draft_policy: ignore
+  linting:
+    max_findings:
+      warning: 1
+";
+
+    fn config(yaml: &str) -> GatesConfig {
+        serde_yaml::from_str(yaml).unwrap()
+    }
+
+    fn findings(severities: &[Severity]) -> Vec<Finding> {
+        severities
+            .iter()
+            .map(|&severity| Finding {
+                tool: "tool".to_string(),
+                file: "src/app.py".to_string(),
+                line: Some(1),
+                column: None,
+                severity,
+                rule: None,
+                message: "message".to_string(),
+            })
+            .collect()
+    }
+
+    fn input<'a>(tool: &'a str, category: &'a str, status: ToolStatus, findings: &'a [Finding]) -> GateInput<'a> {
+        GateInput { tool, category, status, findings }
+    }
+
+    #[test]
+    fn passes_within_the_thresholds() {
+        let warnings = findings(&[Severity::Warning, Severity::Info]);
+        let outcome = evaluate(
+            &config(CONFIG),
+            false,
+            [
+                input("flake8", "linting", ToolStatus::Passed, &warnings),
+                input("mypy", "typing", ToolStatus::Passed, &warnings),
+            ],
+        );
+        assert_eq!(
+            outcome,
+            GateOutcome {
+                passed: true,
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn fails_on_exceeded_thresholds_and_failed_tools() {
+        let linting = findings(&[Severity::Warning, Severity::Warning]);
+        let typing = findings(&[Severity::Error, Severity::Warning]);
+        let outcome = evaluate(
+            &config(CONFIG),
+            false,
+            [
+                input("flake8", "linting", ToolStatus::Passed, &linting),
+                input("mypy", "typing", ToolStatus::Failed, &typing),
+            ],
+        );
+        assert!(!outcome.passed);
+        assert_eq!(
+            outcome.violations,
+            ["mypy failed", "1 errors (limit 0)", "3 warnings (limit 2)", "2 linting warnings (limit 1)"]
+        );
+    }
+
+    #[test]
+    fn follows_the_category_policies() {
+        let errors = findings(&[Severity::Error]);
+        let inputs = || {
+            [
+                input("pip-audit", "dependency", ToolStatus::Failed, &errors),
+                input("black", "formatting", ToolStatus::Failed, &errors),
+            ]
+        };
+
+        let outcome = evaluate(&config(CONFIG), false, inputs());
+        assert_eq!(outcome.violations, ["black failed", "1 errors (limit 0)"]);
+        assert_eq!(outcome.advisory_failures, ["pip-audit"]);
+
+        let outcome = evaluate(&config(CONFIG), true, inputs());
+        assert!(outcome.passed);
+        assert_eq!(outcome.advisory_failures, ["pip-audit"]);
+    }
+
+    #[test]
+    fn checks_the_coverage_minimums() {
+        let coverage = |covered, changed: Option<LineCounts>| CoverageReport {
+            lines: LineCounts { covered, total: 100 },
+            changed_lines: changed,
+            files: Vec::new(),
+        };
+        let check = |config: &GatesConfig, coverage: &CoverageReport| {
+            let mut outcome = GateOutcome {
+                passed: true,
+                ..Default::default()
+            };
+            check_coverage(config, false, coverage, &mut outcome);
+            outcome
+        };
+        let required = config("coverage: {min_lines: 80, min_changed_lines: 90}");
+
+        assert!(check(&required, &coverage(80, None)).passed);
+        let outcome = check(&required, &coverage(79, Some(LineCounts { covered: 1, total: 2 })));
+        assert!(!outcome.passed);
+        assert_eq!(
+            outcome.violations,
+            ["line coverage 79.0% (minimum 80%)", "changed-line coverage 50.0% (minimum 90%)"]
+        );
+        // Nothing instrumented is a shortfall, no changed line is not
+        let nothing = CoverageReport {
+            lines: LineCounts::default(),
+            changed_lines: Some(LineCounts::default()),
+            files: Vec::new(),
+        };
+        assert_eq!(
+            check(&required, &nothing).violations,
+            ["no coverage collected for the coverage minimums"]
+        );
+        assert!(check(&config("{}"), &nothing).passed);
+
+        let advisory = config(
+            "
+coverage: {min_lines: 80}
+categories:
+  testing: {policy: advisory}
+",
+        );
+        let outcome = check(&advisory, &coverage(50, None));
+        assert!(outcome.passed);
+        assert_eq!(outcome.advisory_violations, ["line coverage 50.0% (minimum 80%)"]);
+    }
+}
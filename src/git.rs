@@ -0,0 +1,421 @@
+use crate::findings::Finding;
+use crate::paths::PathFilter;
+use crate::report::{RunReport, ToolStatus};
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::process::Command;
+
+/// How findings are matched against a change set
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum ChangeScope {
+    /// Keep findings on added or modified lines
+    #[default]
+    Lines,
+    /// Keep every finding in a changed file
+    Files,
+}
+
+/// Files and line ranges changed relative to a git base, with paths relative
+/// to the analyzed directory
+#[derive(Debug, Clone, Default)]
+pub struct ChangeSet {
+    pub base: String,
+    /// Changed files and their added or modified line ranges (inclusive).
+    /// Untracked files have a single range covering every line.
+    files: BTreeMap<String, Vec<(u32, u32)>>,
+}
+
+impl ChangeSet {
+    /// Everything that differs between the working tree in `directory` and
+    /// its merge base with `base`, including untracked files, leaving out
+    /// paths ignored by `filter`
+    pub fn since(directory: &Path, base: &str, filter: &PathFilter) -> Result<Self> {
+        let merge_base = git(directory, &["merge-base", "HEAD", base])
+            .with_context(|| format!("Could not find a merge base with `{}`", base))?;
+        let diff = git(
+            directory,
+            &[
+                "diff",
+                "--unified=0",
+                "--no-color",
+                "--no-ext-diff",
+                "--relative",
+                merge_base.trim(),
+            ],
+        )?;
+
+        let mut changes = Self {
+            base: base.to_string(),
+            files: parse_diff(&diff),
+        };
+        // NUL-separated, so that unusual file names are listed unquoted
+        let untracked = git(directory, &["ls-files", "-z", "--others", "--exclude-standard"])?;
+        for file in untracked.split('\0').filter(|file| !file.is_empty()) {
+            changes.files.insert(file.to_string(), vec![(1, u32::MAX)]);
+        }
+        changes
+            .files
+            .retain(|file, _| !filter.is_ignored(Path::new(file), false));
+        Ok(changes)
+    }
+
+    /// Paths of all changed files that still exist
+    pub fn files(&self) -> Vec<String> {
+        self.files.keys().cloned().collect()
+    }
+
+    pub fn contains(&self, finding: &Finding, scope: ChangeScope) -> bool {
+        if !self.files.contains_key(&finding.file) {
+            return false;
+        }
+        match (scope, finding.line) {
+            (ChangeScope::Lines, Some(line)) => self.contains_line(&finding.file, line),
+            // Findings without a line (e.g. failing tests) can only be
+            // matched by file
+            _ => true,
+        }
+    }
+
+    /// Whether `line` of `file` was added or modified
+    pub fn contains_line(&self, file: &str, line: u32) -> bool {
+        self.files.get(file).is_some_and(|ranges| {
+            ranges
+                .iter()
+                .any(|(start, end)| (*start..=*end).contains(&line))
+        })
+    }
+
+    /// Drop findings outside the change set from `report`.
+    ///
+    /// A tool that failed only because of findings outside the change set is
+    /// counted as passed, when its exit code tells that findings were all
+    /// that failed it.
+    pub fn filter_report(&self, report: &mut RunReport, scope: ChangeScope) {
+        for tool in &mut report.tools {
+            let before = tool.findings.len();
+            tool.findings
+                .retain(|finding| self.contains(finding, scope));
+            if tool.status == ToolStatus::Failed
+                && before > 0
+                && tool.findings.is_empty()
+                && tool.tool.failed_on_findings(tool.exit_code)
+            {
+                tool.status = ToolStatus::Passed;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+impl ChangeSet {
+    /// The changes in a `--unified=0` diff
+    pub fn from_diff(diff: &str) -> Self {
+        Self {
+            base: "main".to_string(),
+            files: parse_diff(diff),
+        }
+    }
+}
+
+/// Variables git sets for hooks, pointing at the repository a commit is being
+/// made in. Commands codeguard starts act on their own directory instead
+/// (e.g. a staged snapshot), so they are removed from their environment.
+pub const HOOK_ENV: [&str; 3] = ["GIT_INDEX_FILE", "GIT_DIR", "GIT_WORK_TREE"];
+
+/// Run git in `directory` and return its stdout, without the variables of a
+/// running hook
+pub fn git(directory: &Path, args: &[&str]) -> Result<String> {
+    let mut command = Command::new("git");
+    for variable in HOOK_ENV {
+        command.env_remove(variable);
+    }
+    run_git(command, directory, args)
+}
+
+/// Like [`git`], but keeping the variables of a running hook, e.g. the
+/// `GIT_INDEX_FILE` of the commit being made
+pub fn git_in_hook(directory: &Path, args: &[&str]) -> Result<String> {
+    run_git(Command::new("git"), directory, args)
+}
+
+fn run_git(mut command: Command, directory: &Path, args: &[&str]) -> Result<String> {
+    let output = command
+        .current_dir(directory)
+        .args(args)
+        .output()
+        .context("Could not run git")?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// The commit checked out in `directory`, if it is in a git repository with
+/// at least one commit
+pub fn head_commit(directory: &Path) -> Option<String> {
+    let head = git(directory, &["rev-parse", "HEAD"]).ok()?;
+    Some(head.trim().to_string())
+}
+
+/// The commit checked out in `directory` plus, when the working tree has
+/// uncommitted changes to tracked files, a hash of them, so that two runs
+/// agree only if they saw the same code
+pub fn tree_state(directory: &Path) -> Option<String> {
+    let commit = head_commit(directory)?;
+    let diff = git(directory, &["diff", "HEAD", "--binary"]).ok()?;
+    if diff.is_empty() {
+        return Some(commit);
+    }
+    let hash = format!("{:x}", Sha256::digest(diff.as_bytes()));
+    Some(format!("{}+{}", commit, &hash[..12]))
+}
+
+/// Collect the new-side line ranges of each file in a `--unified=0` diff
+fn parse_diff(diff: &str) -> BTreeMap<String, Vec<(u32, u32)>> {
+    let mut files: BTreeMap<String, Vec<(u32, u32)>> = BTreeMap::new();
+    let mut current: Option<String> = None;
+    // Between a "diff --git" line and the first hunk, so that an added line
+    // starting with "++ " is not mistaken for a file header
+    let mut in_header = false;
+
+    for line in diff.lines() {
+        if line.starts_with("diff --git ") {
+            in_header = true;
+            current = None;
+        } else if let Some(path) = line.strip_prefix("+++ ").filter(|_| in_header) {
+            // Deleted files have no new side to report findings on
+            current = unquote_path(path)
+                .strip_prefix("b/")
+                .map(str::to_string);
+            if let Some(file) = &current {
+                files.entry(file.clone()).or_default();
+            }
+        } else if let Some(hunk) = line.strip_prefix("@@ ") {
+            in_header = false;
+            let Some(file) = &current else { continue };
+            // "@@ -a,b +c,d @@": lines c..c+d of the new file; d defaults to 1
+            let Some(new) = hunk
+                .split_whitespace()
+                .find_map(|part| part.strip_prefix('+'))
+            else {
+                continue;
+            };
+            let (start, count) = match new.split_once(',') {
+                Some((start, count)) => (start.parse().unwrap_or(0), count.parse().unwrap_or(0)),
+                None => (new.parse().unwrap_or(0), 1),
+            };
+            if count > 0 {
+                if let Some(ranges) = files.get_mut(file) {
+                    ranges.push((start, start + count - 1));
+                }
+            }
+        }
+    }
+
+    files
+}
+
+/// A path from a diff header, which git wraps in double quotes with C-style
+/// escapes when it has unusual characters, and follows with a tab when it
+/// has spaces
+fn unquote_path(path: &str) -> String {
+    let path = path.strip_suffix('\t').unwrap_or(path);
+    let Some(quoted) = path
+        .strip_prefix('"')
+        .and_then(|path| path.strip_suffix('"'))
+    else {
+        return path.to_string();
+    };
+
+    // Octal escapes are single bytes of UTF-8 sequences
+    let mut bytes = Vec::with_capacity(quoted.len());
+    let mut chars = quoted.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            let mut buffer = [0; 4];
+            bytes.extend_from_slice(c.encode_utf8(&mut buffer).as_bytes());
+            continue;
+        }
+        match chars.next() {
+            Some('a') => bytes.push(0x07),
+            Some('b') => bytes.push(0x08),
+            Some('f') => bytes.push(0x0c),
+            Some('n') => bytes.push(b'\n'),
+            Some('r') => bytes.push(b'\r'),
+            Some('t') => bytes.push(b'\t'),
+            Some('v') => bytes.push(0x0b),
+            Some(digit @ '0'..='7') => {
+                let mut value = digit.to_digit(8).unwrap_or(0);
+                for _ in 0..2 {
+                    match chars.clone().next().and_then(|c| c.to_digit(8)) {
+                        Some(next) => {
+                            value = value * 8 + next;
+                            chars.next();
+                        }
+                        None => break,
+                    }
+                }
+                bytes.push(value as u8);
+            }
+            Some(other) => {
+                let mut buffer = [0; 4];
+                bytes.extend_from_slice(other.encode_utf8(&mut buffer).as_bytes());
+            }
+            None => bytes.push(b'\\'),
+        }
+    }
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::findings::Severity;
+    use crate::report::ToolReport;
+    use crate::tools::base::Tool;
+    use std::path::PathBuf;
+    use std::time::Duration;
+
+    fn ranges(diff: &str) -> Vec<(String, Vec<(u32, u32)>)> {
+        parse_diff(diff).into_iter().collect()
+    }
+
+    fn file(path: &str, ranges: &[(u32, u32)]) -> (String, Vec<(u32, u32)>) {
+        (path.to_string(), ranges.to_vec())
+    }
+
+    fn finding(file: &str, line: Option<u32>) -> Finding {
+        Finding {
+            tool: "flake8".to_string(),
+            file: file.to_string(),
+            line,
+            column: None,
+            severity: Severity::Error,
+            rule: Some("F401".to_string()),
+            message: "'os' imported but unused".to_string(),
+        }
+    }
+
+    const DIFF: &str = "\
+diff --git a/src/app.py b/src/app.py
+index 3b18e51..a9c4f2d 100644
+--- a/src/app.py
++++ b/src/app.py
+@@ -2,0 +3,2 @@ import sys
++import os
++++ counter
+@@ -10 +12 @@ def main():
+-    run()
++    run(sys.argv)
+@@ -20,3 +21,0 @@ def main():
+-    cleanup()
+-    cleanup()
+-    cleanup()
+diff --git a/src/old.py b/src/old.py
+deleted file mode 100644
+index 3b18e51..0000000
+--- a/src/old.py
++++ /dev/null
+@@ -1,2 +0,0 @@
+-import os
+-print(os.name)
+diff --git a/docs/read me.md b/docs/read me.md
+index 3b18e51..a9c4f2d 100644
+--- a/docs/read me.md\t
++++ b/docs/read me.md\t
+@@ -1 +1 @@
+-Hello
++Hello, world
+diff --git \"a/src/caf\\303\\251 \\\"v2\\\".py\" \"b/src/caf\\303\\251 \\\"v2\\\".py\"
+index 3b18e51..a9c4f2d 100644
+--- \"a/src/caf\\303\\251 \\\"v2\\\".py\"
++++ \"b/src/caf\\303\\251 \\\"v2\\\".py\"
+@@ -4,0 +5,3 @@
++a
++b
++c
+";
+
+    #[test]
+    fn collects_the_new_side_of_each_hunk() {
+        assert_eq!(
+            ranges(DIFF),
+            [
+                file("docs/read me.md", &[(1, 1)]),
+                file("src/app.py", &[(3, 4), (12, 12)]),
+                file("src/café \"v2\".py", &[(5, 7)]),
+            ]
+        );
+    }
+
+    #[test]
+    fn unquotes_header_paths() {
+        assert_eq!(unquote_path("b/src/app.py"), "b/src/app.py");
+        assert_eq!(unquote_path("b/read me.md\t"), "b/read me.md");
+        assert_eq!(unquote_path(r#""b/caf\303\251.py""#), "b/café.py");
+        assert_eq!(unquote_path(r#""b/a\"b\\c\td.py""#), "b/a\"b\\c\td.py");
+    }
+
+    #[test]
+    fn keeps_findings_on_changed_lines() {
+        let changes = ChangeSet::from_diff(DIFF);
+        assert!(changes.contains(&finding("src/app.py", Some(4)), ChangeScope::Lines));
+        assert!(!changes.contains(&finding("src/app.py", Some(5)), ChangeScope::Lines));
+        assert!(changes.contains(&finding("src/app.py", Some(5)), ChangeScope::Files));
+        // A pure deletion leaves no line of the new file changed
+        assert!(!changes.contains(&finding("src/app.py", Some(21)), ChangeScope::Lines));
+        assert!(changes.contains(&finding("src/app.py", None), ChangeScope::Lines));
+        assert!(!changes.contains(&finding("src/old.py", None), ChangeScope::Files));
+    }
+
+    #[test]
+    fn passes_tools_that_failed_only_outside_the_change_set() {
+        let tool = Tool {
+            name: "flake8".to_string(),
+            command: "flake8".to_string(),
+            findings_exit_codes: vec![1],
+            ..Default::default()
+        };
+        let tool_report = |exit_code, findings| ToolReport {
+            tool: tool.clone(),
+            status: ToolStatus::Failed,
+            exit_code: Some(exit_code),
+            duration: Duration::ZERO,
+            output: String::new(),
+            findings,
+            cached: false,
+            version: None,
+        };
+        let mut report = RunReport {
+            directory: PathBuf::from("."),
+            tools: vec![
+                tool_report(1, vec![finding("src/app.py", Some(3)), finding("src/app.py", Some(30))]),
+                tool_report(1, vec![finding("src/app.py", Some(30))]),
+                // Crashed rather than reporting findings
+                tool_report(2, vec![finding("src/app.py", Some(30))]),
+            ],
+            gate: None,
+            baseline: None,
+            changed_since: None,
+            coverage: None,
+        };
+
+        ChangeSet::from_diff(DIFF).filter_report(&mut report, ChangeScope::Lines);
+        let outcomes: Vec<_> = report
+            .tools
+            .iter()
+            .map(|tool| (tool.status, tool.findings.len()))
+            .collect();
+        assert_eq!(
+            outcomes,
+            [(ToolStatus::Failed, 1), (ToolStatus::Passed, 0), (ToolStatus::Failed, 0)]
+        );
+    }
+}
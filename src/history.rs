@@ -0,0 +1,464 @@
+use crate::git::{head_commit, tree_state};
+use crate::report::{FindingCounts, RunReport, ToolStatus};
+use crate::testcases::{parse_tests, TestCase, TestOutcome, TestRunner};
+use crate::tools::Tool;
+use anyhow::{Context, Result};
+use rusqlite::{params, Connection};
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Characters of a text sparkline, from lowest to highest
+const SPARK: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// One tool outcome as recorded in the history
+#[derive(Debug, Clone)]
+pub struct Entry {
+    pub tool: String,
+    pub status: ToolStatus,
+    pub exit_code: Option<i32>,
+    pub duration_ms: u64,
+    pub findings: FindingCounts,
+    /// Whether the outcome was replayed from the result cache
+    pub cached: bool,
+}
+
+/// A recorded outcome with when and where it happened
+#[derive(Debug, Clone)]
+pub struct Record {
+    pub entry: Entry,
+    /// Seconds since the Unix epoch
+    pub recorded_at: i64,
+    pub commit: Option<String>,
+}
+
+/// Every tool outcome of every run, in a SQLite database under the user
+/// data directory (e.g. `~/.local/share/codeguard/history.sqlite`)
+pub struct History {
+    connection: Connection,
+}
+
+/// Where the history database is stored
+pub fn database_path() -> Option<PathBuf> {
+    Some(dirs::data_dir()?.join("codeguard").join("history.sqlite"))
+}
+
+impl History {
+    pub fn open() -> Result<Self> {
+        let path = database_path()
+            .ok_or_else(|| anyhow::anyhow!("Could not find the user data directory"))?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Could not create {}", parent.display()))?;
+        }
+        let connection = Connection::open(&path)
+            .with_context(|| format!("Could not open {}", path.display()))?;
+        connection.execute_batch(
+            "CREATE TABLE IF NOT EXISTS tool_runs (
+                id INTEGER PRIMARY KEY,
+                directory TEXT NOT NULL,
+                commit_sha TEXT,
+                recorded_at INTEGER NOT NULL,
+                tool TEXT NOT NULL,
+                status TEXT NOT NULL,
+                exit_code INTEGER,
+                duration_ms INTEGER NOT NULL,
+                errors INTEGER NOT NULL,
+                warnings INTEGER NOT NULL,
+                infos INTEGER NOT NULL,
+                cached INTEGER NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS tool_runs_by_tool
+                ON tool_runs (directory, tool, recorded_at);
+            CREATE TABLE IF NOT EXISTS test_runs (
+                id INTEGER PRIMARY KEY,
+                directory TEXT NOT NULL,
+                tree_state TEXT NOT NULL,
+                recorded_at INTEGER NOT NULL,
+                tool TEXT NOT NULL,
+                test TEXT NOT NULL,
+                outcome TEXT NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS test_runs_by_test
+                ON test_runs (directory, tool, test, tree_state);",
+        )?;
+        Ok(Self { connection })
+    }
+
+    /// Record `entries`, outcomes of tools run on `directory`
+    pub fn record(&self, directory: &Path, entries: &[Entry]) -> Result<()> {
+        let directory = directory_key(directory);
+        let commit = head_commit(Path::new(&directory));
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_secs() as i64);
+        let mut insert = self.connection.prepare_cached(
+            "INSERT INTO tool_runs (directory, commit_sha, recorded_at, tool, status, exit_code,
+                duration_ms, errors, warnings, infos, cached)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+        )?;
+        for entry in entries {
+            insert.execute(params![
+                directory,
+                commit,
+                now,
+                entry.tool,
+                entry.status.label(),
+                entry.exit_code,
+                entry.duration_ms as i64,
+                entry.findings.errors as i64,
+                entry.findings.warnings as i64,
+                entry.findings.infos as i64,
+                entry.cached,
+            ])?;
+        }
+        Ok(())
+    }
+
+    /// Record the tests in the `output` of a run of `tool`, if it is a test
+    /// runner. `whole_suite` tells whether the run was unrestricted;
+    /// `selected` names the tests a targeted run was restricted to, which
+    /// passed if the run did.
+    pub fn record_test_output(
+        &self,
+        directory: &Path,
+        tool: &Tool,
+        status: ToolStatus,
+        output: &str,
+        whole_suite: bool,
+        selected: &[String],
+    ) -> Result<()> {
+        let Some(runner) = TestRunner::of(tool) else {
+            return Ok(());
+        };
+        let mut tests = parse_tests(runner, output);
+        if status == ToolStatus::Passed {
+            for name in selected {
+                if !tests.iter().any(|test| &test.name == name) {
+                    tests.push(TestCase {
+                        name: name.clone(),
+                        outcome: TestOutcome::Passed,
+                    });
+                }
+            }
+        }
+        let suite_passed = whole_suite && status == ToolStatus::Passed;
+        if tests.is_empty() && !suite_passed {
+            return Ok(());
+        }
+        self.record_tests(directory, &tool.name, &tests, suite_passed)
+    }
+
+    /// Record every tool outcome of `report`, and the tests of the test
+    /// runners among them unless replayed from the cache. Runs of less than
+    /// the `whole_suite` (e.g. restricted to changed files) are left out of
+    /// the trends, as their counts would distort them.
+    pub fn record_report(&self, report: &RunReport, whole_suite: bool) -> Result<()> {
+        for tool in report.tools.iter().filter(|tool| !tool.cached) {
+            self.record_test_output(
+                &report.directory,
+                &tool.tool,
+                tool.status,
+                &tool.output,
+                whole_suite,
+                &[],
+            )?;
+        }
+        if !whole_suite {
+            return Ok(());
+        }
+        let entries: Vec<Entry> = report
+            .tools
+            .iter()
+            .map(|tool| Entry {
+                tool: tool.tool.name.clone(),
+                status: tool.status,
+                exit_code: tool.exit_code,
+                duration_ms: tool.duration.as_millis() as u64,
+                findings: FindingCounts::of(&tool.findings),
+                cached: tool.cached,
+            })
+            .collect();
+        self.record(&report.directory, &entries)
+    }
+
+    /// Record the outcomes of the tests one run of `tool` reported. With
+    /// `suite_passed`, the run covered the whole suite and passed, so tests
+    /// seen earlier on the same code but not listed now (runners like
+    /// PHPUnit list failures only) are recorded as passed.
+    pub fn record_tests(
+        &self,
+        directory: &Path,
+        tool: &str,
+        tests: &[TestCase],
+        suite_passed: bool,
+    ) -> Result<()> {
+        let directory_key = directory_key(directory);
+        // Outside git there is no telling whether two runs saw the same code
+        let Some(state) = tree_state(Path::new(&directory_key)) else {
+            return Ok(());
+        };
+        let mut outcomes: Vec<(String, TestOutcome)> =
+            tests.iter().map(|test| (test.name.clone(), test.outcome)).collect();
+        if suite_passed {
+            let mut known = self.connection.prepare_cached(
+                "SELECT DISTINCT test FROM test_runs
+                 WHERE directory = ?1 AND tree_state = ?2 AND tool = ?3",
+            )?;
+            let names = known.query_map(params![directory_key, state, tool], |row| row.get(0))?;
+            for name in names {
+                let name: String = name?;
+                if !tests.iter().any(|test| test.name == name) {
+                    outcomes.push((name, TestOutcome::Passed));
+                }
+            }
+        }
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_secs() as i64);
+        let mut insert = self.connection.prepare_cached(
+            "INSERT INTO test_runs (directory, tree_state, recorded_at, tool, test, outcome)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        )?;
+        for (name, outcome) in outcomes {
+            insert.execute(params![directory_key, state, now, tool, name, outcome.label()])?;
+        }
+        Ok(())
+    }
+
+    /// Tests of `directory` that both passed and failed on the same code,
+    /// most often flipping first
+    pub fn flaky_tests(&self, directory: &Path) -> Result<Vec<FlakyTest>> {
+        let mut query = self.connection.prepare_cached(
+            "SELECT tool, test, tree_state, outcome FROM test_runs
+             WHERE directory = ?1 AND outcome != 'skipped'
+             ORDER BY tool, test, tree_state, id",
+        )?;
+        let rows = query.query_map(params![directory_key(directory)], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, String>(3)?,
+            ))
+        })?;
+
+        // Outcomes per test and per tree state, in the order they happened
+        let mut runs: BTreeMap<(String, String), BTreeMap<String, Vec<TestOutcome>>> =
+            BTreeMap::new();
+        for row in rows {
+            let (tool, test, state, outcome) = row?;
+            if let Some(outcome) = TestOutcome::parse(&outcome) {
+                runs.entry((tool, test)).or_default().entry(state).or_default().push(outcome);
+            }
+        }
+        let mut flaky: Vec<FlakyTest> = runs
+            .into_iter()
+            .filter_map(|((tool, test), states)| {
+                let mixed = states.values().any(|outcomes| {
+                    outcomes.contains(&TestOutcome::Passed) && outcomes.contains(&TestOutcome::Failed)
+                });
+                if !mixed {
+                    return None;
+                }
+                let mut flips = 0;
+                let mut transitions = 0;
+                let mut runs = 0;
+                let mut failures = 0;
+                for outcomes in states.values() {
+                    runs += outcomes.len();
+                    failures += outcomes.iter().filter(|o| **o == TestOutcome::Failed).count();
+                    transitions += outcomes.len().saturating_sub(1);
+                    flips += outcomes.windows(2).filter(|pair| pair[0] != pair[1]).count();
+                }
+                Some(FlakyTest {
+                    tool,
+                    test,
+                    runs,
+                    failures,
+                    flip_rate: flips as f64 / transitions.max(1) as f64,
+                })
+            })
+            .collect();
+        flaky.sort_by(|a, b| b.flip_rate.total_cmp(&a.flip_rate));
+        Ok(flaky)
+    }
+
+    /// The latest `limit` outcomes of each tool run on `directory`, oldest
+    /// first, by tool name. Skipped runs (e.g. of tools not installed) and
+    /// results replayed from the cache say nothing about the tool's
+    /// duration or findings, so they are left out.
+    pub fn recent(&self, directory: &Path, limit: usize) -> Result<BTreeMap<String, Vec<Record>>> {
+        let mut query = self.connection.prepare_cached(
+            "SELECT tool, status, exit_code, duration_ms, errors, warnings, infos, cached,
+                recorded_at, commit_sha
+             FROM (
+                SELECT *, ROW_NUMBER() OVER (PARTITION BY tool ORDER BY id DESC) AS age
+                FROM tool_runs WHERE directory = ?1 AND status != 'SKIP' AND cached = 0
+             )
+             WHERE age <= ?2
+             ORDER BY id",
+        )?;
+        let rows = query.query_map(params![directory_key(directory), limit as i64], |row| {
+            let status: String = row.get(1)?;
+            Ok(Record {
+                entry: Entry {
+                    tool: row.get(0)?,
+                    status: match status.as_str() {
+                        "PASS" => ToolStatus::Passed,
+                        "FAIL" => ToolStatus::Failed,
+                        _ => ToolStatus::Skipped,
+                    },
+                    exit_code: row.get(2)?,
+                    duration_ms: row.get::<_, i64>(3)? as u64,
+                    findings: FindingCounts {
+                        errors: row.get::<_, i64>(4)? as usize,
+                        warnings: row.get::<_, i64>(5)? as usize,
+                        infos: row.get::<_, i64>(6)? as usize,
+                    },
+                    cached: row.get(7)?,
+                },
+                recorded_at: row.get(8)?,
+                commit: row.get(9)?,
+            })
+        })?;
+
+        let mut by_tool: BTreeMap<String, Vec<Record>> = BTreeMap::new();
+        for record in rows {
+            let record = record?;
+            by_tool.entry(record.entry.tool.clone()).or_default().push(record);
+        }
+        Ok(by_tool)
+    }
+}
+
+/// A test that both passed and failed on the same code
+#[derive(Debug, Clone)]
+pub struct FlakyTest {
+    pub tool: String,
+    pub test: String,
+    /// Recorded runs of the test, over all versions of the code
+    pub runs: usize,
+    pub failures: usize,
+    /// How often the outcome changed from one run to the next on the same
+    /// code, between 0 and 1
+    pub flip_rate: f64,
+}
+
+/// Print the flaky tests of `directory` with their flip rates
+pub fn print_flaky(directory: &Path) -> Result<()> {
+    let flaky = History::open()?.flaky_tests(directory)?;
+    if flaky.is_empty() {
+        println!("No flaky tests recorded for {}", directory.display());
+        return Ok(());
+    }
+    let mut out = String::new();
+    let _ = writeln!(out, "{} flaky tests in {}\n", flaky.len(), directory.display());
+    for test in &flaky {
+        let _ = writeln!(
+            out,
+            "{:>4.0}% flips  {}/{} failed  [{}] {}",
+            test.flip_rate * 100.0,
+            test.failures,
+            test.runs,
+            test.tool,
+            test.test
+        );
+    }
+    let _ = std::io::stdout().write_all(out.as_bytes());
+    Ok(())
+}
+
+/// The directory as stored in the history, absolute so that runs from
+/// different working directories agree
+fn directory_key(directory: &Path) -> String {
+    directory
+        .canonicalize()
+        .unwrap_or_else(|_| directory.to_path_buf())
+        .to_string_lossy()
+        .to_string()
+}
+
+/// `values` as a line of block characters scaled between their minimum and
+/// maximum
+pub fn sparkline(values: &[u64]) -> String {
+    let (Some(min), Some(max)) = (values.iter().min(), values.iter().max()) else {
+        return String::new();
+    };
+    values
+        .iter()
+        .map(|value| match max - min {
+            0 => SPARK[0],
+            range => SPARK[((value - min) * (SPARK.len() as u64 - 1) / range) as usize],
+        })
+        .collect()
+}
+
+/// Print the trends of each tool run on `directory`
+pub fn print(directory: &Path, tool: Option<&str>, limit: usize) -> Result<()> {
+    let history = History::open()?;
+    let recent = history.recent(directory, limit)?;
+    if recent.is_empty() {
+        println!("No runs recorded for {}", directory.display());
+        return Ok(());
+    }
+    let mut out = String::new();
+    for (name, records) in recent.iter().filter(|(name, _)| tool.is_none_or(|t| t == *name)) {
+        let durations: Vec<u64> = records.iter().map(|r| r.entry.duration_ms).collect();
+        let findings: Vec<u64> = records.iter().map(|r| finding_total(&r.entry)).collect();
+        let Some(last) = records.last() else {
+            continue;
+        };
+        let commit = last
+            .commit
+            .as_deref()
+            .map(|commit| format!(" at {}", &commit[..commit.len().min(8)]))
+            .unwrap_or_default();
+        let _ = writeln!(
+            out,
+            "{} ({} runs, last {} {}{})",
+            name,
+            records.len(),
+            last.entry.status.label(),
+            ago(last.recorded_at),
+            commit
+        );
+        let _ = writeln!(
+            out,
+            "  duration  {}  {:.1}s",
+            sparkline(&durations),
+            last.entry.duration_ms as f64 / 1000.0
+        );
+        let _ = writeln!(
+            out,
+            "  findings  {}  {} errors, {} warnings",
+            sparkline(&findings),
+            last.entry.findings.errors,
+            last.entry.findings.warnings
+        );
+    }
+    // A closed pipe (e.g. `| head`) is not an error worth reporting
+    let _ = std::io::stdout().write_all(out.as_bytes());
+    Ok(())
+}
+
+/// How long ago the Unix time `at` was, e.g. "3h ago"
+pub fn ago(at: i64) -> String {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs() as i64);
+    match (now - at).max(0) {
+        seconds if seconds < 60 => format!("{}s ago", seconds),
+        seconds if seconds < 3600 => format!("{}m ago", seconds / 60),
+        seconds if seconds < 86400 => format!("{}h ago", seconds / 3600),
+        seconds => format!("{}d ago", seconds / 86400),
+    }
+}
+
+/// Errors and warnings of an outcome, the lint debt its trend shows
+pub fn finding_total(entry: &Entry) -> u64 {
+    (entry.findings.errors + entry.findings.warnings) as u64
+}
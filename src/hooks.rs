@@ -0,0 +1,285 @@
+use crate::git::{git, git_in_hook};
+use crate::paths::{link_dir, DEPENDENCY_DIRS};
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use std::path::{Path, PathBuf};
+
+/// First comment line of every hook script codeguard writes
+const MARKER: &str = "# Installed by codeguard";
+/// Suffix under which a replaced hook is kept until uninstall
+const BACKUP_SUFFIX: &str = ".codeguard-backup";
+
+/// The git hooks codeguard can install
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum HookKind {
+    PreCommit,
+    PrePush,
+}
+
+impl HookKind {
+    pub const ALL: [HookKind; 2] = [HookKind::PreCommit, HookKind::PrePush];
+
+    /// File name of the hook in the hooks directory
+    pub fn file_name(self) -> &'static str {
+        match self {
+            HookKind::PreCommit => "pre-commit",
+            HookKind::PrePush => "pre-push",
+        }
+    }
+}
+
+/// What currently occupies a hook file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HookState {
+    Missing,
+    /// A hook written by codeguard
+    Installed,
+    /// Some other hook, described by the manager that wrote it if known
+    Foreign(String),
+}
+
+/// The hooks directory of the repository containing `directory`, honouring
+/// `core.hooksPath`
+pub fn hooks_dir(directory: &Path) -> Result<PathBuf> {
+    let path = git(directory, &["rev-parse", "--git-path", "hooks"])
+        .context("Not a git repository")?;
+    Ok(directory.join(path.trim()))
+}
+
+/// The configured `core.hooksPath`, if any. Hook managers such as husky
+/// point it at a directory they generate.
+pub fn hooks_path_override(directory: &Path) -> Option<String> {
+    git(directory, &["config", "--get", "core.hooksPath"])
+        .ok()
+        .map(|path| path.trim().to_string())
+        .filter(|path| !path.is_empty())
+}
+
+pub fn hook_state(path: &Path) -> HookState {
+    let Ok(contents) = std::fs::read_to_string(path) else {
+        return if path.exists() {
+            HookState::Foreign("unreadable".to_string())
+        } else {
+            HookState::Missing
+        };
+    };
+    if contents.lines().nth(1).is_some_and(|line| line.starts_with(MARKER)) {
+        return HookState::Installed;
+    }
+    let manager = if contents.contains("husky") {
+        "husky"
+    } else if contents.contains("File generated by pre-commit") {
+        "pre-commit framework"
+    } else if contents.contains("lefthook") {
+        "lefthook"
+    } else {
+        "custom script"
+    };
+    HookState::Foreign(manager.to_string())
+}
+
+/// Write the pre-commit and pre-push hooks.
+///
+/// Existing hooks written by something else are only replaced with `force`,
+/// in which case they are kept next to the new hook and restored on
+/// uninstall.
+pub fn install(directory: &Path, force: bool) -> Result<()> {
+    if let Some(path) = hooks_path_override(directory) {
+        if !force {
+            anyhow::bail!(
+                "core.hooksPath is set to `{}`, so hooks are managed by another tool; \
+                 use --force to install there anyway",
+                path
+            );
+        }
+    }
+
+    let dir = hooks_dir(directory)?;
+    let foreign: Vec<(HookKind, String)> = HookKind::ALL
+        .into_iter()
+        .filter_map(|kind| match hook_state(&dir.join(kind.file_name())) {
+            HookState::Foreign(manager) => Some((kind, manager)),
+            _ => None,
+        })
+        .collect();
+    if !force {
+        if let Some((kind, manager)) = foreign.first() {
+            anyhow::bail!(
+                "{} already has a {} hook ({}); use --force to replace it, \
+                 the original is restored by `codeguard hooks uninstall`",
+                dir.display(),
+                kind.file_name(),
+                manager
+            );
+        }
+    }
+
+    std::fs::create_dir_all(&dir)
+        .with_context(|| format!("Could not create {}", dir.display()))?;
+    let exe = std::env::current_exe().context("Could not locate the codeguard binary")?;
+    for kind in HookKind::ALL {
+        let path = dir.join(kind.file_name());
+        if foreign.iter().any(|(k, _)| *k == kind) {
+            let backup = backup_path(&path);
+            std::fs::rename(&path, &backup)
+                .with_context(|| format!("Could not back up {}", path.display()))?;
+            println!("Moved existing {} to {}", kind.file_name(), backup.display());
+        }
+        std::fs::write(&path, script(kind, &exe))
+            .with_context(|| format!("Could not write {}", path.display()))?;
+        make_executable(&path)?;
+        println!("Installed {}", path.display());
+    }
+    Ok(())
+}
+
+/// Remove the hooks codeguard wrote and restore any hooks they replaced
+pub fn uninstall(directory: &Path) -> Result<()> {
+    let dir = hooks_dir(directory)?;
+    for kind in HookKind::ALL {
+        let path = dir.join(kind.file_name());
+        match hook_state(&path) {
+            HookState::Installed => {
+                std::fs::remove_file(&path)
+                    .with_context(|| format!("Could not remove {}", path.display()))?;
+                println!("Removed {}", path.display());
+            }
+            HookState::Foreign(manager) => {
+                println!("Left {} alone ({})", path.display(), manager);
+                continue;
+            }
+            HookState::Missing => {}
+        }
+        let backup = backup_path(&path);
+        if backup.exists() {
+            std::fs::rename(&backup, &path)
+                .with_context(|| format!("Could not restore {}", backup.display()))?;
+            println!("Restored {}", path.display());
+        }
+    }
+    Ok(())
+}
+
+/// Describe which hooks are installed and by whom
+pub fn status(directory: &Path) -> Result<()> {
+    let dir = hooks_dir(directory)?;
+    if let Some(path) = hooks_path_override(directory) {
+        println!("core.hooksPath: {}", path);
+    }
+    for kind in HookKind::ALL {
+        let state = match hook_state(&dir.join(kind.file_name())) {
+            HookState::Missing => "not installed".to_string(),
+            HookState::Installed => "codeguard".to_string(),
+            HookState::Foreign(manager) => manager,
+        };
+        println!("{:<10} {}", kind.file_name(), state);
+    }
+    Ok(())
+}
+
+fn backup_path(path: &Path) -> PathBuf {
+    let mut backup = path.as_os_str().to_owned();
+    backup.push(BACKUP_SUFFIX);
+    PathBuf::from(backup)
+}
+
+/// The hook script, preferring the binary that installed it and falling back
+/// to `codeguard` on the PATH if that has moved
+fn script(kind: HookKind, exe: &Path) -> String {
+    format!(
+        "#!/bin/sh\n\
+         {}; remove with `codeguard hooks uninstall`\n\
+         CODEGUARD='{}'\n\
+         [ -x \"$CODEGUARD\" ] || CODEGUARD=codeguard\n\
+         exec \"$CODEGUARD\" hooks run {}\n",
+        MARKER,
+        exe.display().to_string().replace('\'', "'\\''"),
+        kind.file_name()
+    )
+}
+
+#[cfg(unix)]
+fn make_executable(path: &Path) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o755))
+        .with_context(|| format!("Could not make {} executable", path.display()))
+}
+
+#[cfg(not(unix))]
+fn make_executable(_path: &Path) -> Result<()> {
+    Ok(())
+}
+
+/// A temporary git worktree holding exactly the staged content of a
+/// repository, removed again on drop.
+///
+/// Its HEAD is the repository's HEAD, so `git diff HEAD` inside it shows the
+/// staged changes and nothing else. Before the first commit there is no
+/// HEAD to check out, and the snapshot is a plain directory instead.
+pub struct StagedSnapshot {
+    repository: PathBuf,
+    path: PathBuf,
+    /// Whether the snapshot is a worktree, i.e. the repository has commits
+    worktree: bool,
+}
+
+impl StagedSnapshot {
+    pub fn create(directory: &Path) -> Result<Self> {
+        // Inside a hook git points GIT_INDEX_FILE at the index being
+        // committed, which is the one write-tree must read. Everything else
+        // acts on the snapshot's own index and work tree, including the
+        // tools run in it, so they run without the hook's variables.
+        let tree = git_in_hook(directory, &["write-tree"]).context("Could not read the staged content")?;
+
+        let repository = PathBuf::from(git(directory, &["rev-parse", "--show-toplevel"])?.trim());
+        let path = std::env::temp_dir().join(format!("codeguard-staged-{}", std::process::id()));
+        let path_arg = path.to_string_lossy().to_string();
+        let snapshot = if git(&repository, &["rev-parse", "--verify", "--quiet", "HEAD"]).is_ok() {
+            git(
+                &repository,
+                &["worktree", "add", "--detach", "--quiet", &path_arg, "HEAD"],
+            )
+            .context("Could not create a worktree for the staged snapshot")?;
+            let snapshot = Self { repository, path, worktree: true };
+            git(&snapshot.path, &["read-tree", "--reset", "-u", tree.trim()])?;
+            snapshot
+        } else {
+            let snapshot = Self { repository, path, worktree: false };
+            git_in_hook(
+                directory,
+                &["checkout-index", "--all", &format!("--prefix={}/", path_arg)],
+            )
+            .context("Could not copy the staged content for the snapshot")?;
+            snapshot
+        };
+        for name in DEPENDENCY_DIRS {
+            let source = snapshot.repository.join(name);
+            let target = snapshot.path.join(name);
+            if source.is_dir() && !target.exists() {
+                link_dir(&source, &target)?;
+            }
+        }
+        Ok(snapshot)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Whether the snapshot is of the first commit, which has no HEAD to
+    /// compare against
+    pub fn is_initial_commit(&self) -> bool {
+        !self.worktree
+    }
+}
+
+impl Drop for StagedSnapshot {
+    fn drop(&mut self) {
+        if self.worktree {
+            let path = self.path.to_string_lossy().to_string();
+            let _ = git(&self.repository, &["worktree", "remove", "--force", &path]);
+        } else {
+            let _ = std::fs::remove_dir_all(&self.path);
+        }
+    }
+}
@@ -1,23 +1,232 @@
+mod baseline;
+mod cache;
 mod config;
+mod coverage;
+mod doctor;
+mod explorer;
+mod findings;
+mod fix;
+mod gates;
+mod git;
+mod history;
+mod hooks;
+mod paths;
+mod projects;
+mod report;
+mod runner;
+mod suggestions;
+mod testcases;
 mod tools;
 mod ui;
+mod watch;
 
-use anyhow::Result;
-use clap::Parser;
+use anyhow::{Context, Result};
+use clap::{Args, Parser, Subcommand};
 use crossterm::{
     event::{DisableMouseCapture, EnableMouseCapture},
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
 use ratatui::prelude::*;
-use std::{io, path::PathBuf};
+use std::{
+    io::{self, Write},
+    path::PathBuf,
+};
 
 #[derive(Parser)]
-#[command(author, version, about, long_about = None)]
+#[command(author, version, about, long_about = None, args_conflicts_with_subcommands = true)]
 struct Cli {
     /// Directory to analyze
     #[arg(default_value = ".")]
     directory: PathBuf,
+
+    /// Apply the `draft_policy` of each gate category
+    #[arg(long)]
+    draft: bool,
+
+    /// Git base used by the "changed only" toggle [default: HEAD]
+    #[arg(long, value_name = "REF")]
+    changed_since: Option<String>,
+
+    /// Always run tools instead of replaying cached results
+    #[arg(long)]
+    no_cache: bool,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Run all detected tools without the TUI and print a report
+    Run(RunArgs),
+    /// Rerun the tools affected by each file change until interrupted
+    Watch {
+        /// Directory to watch
+        #[arg(default_value = ".")]
+        directory: PathBuf,
+    },
+    /// Manage the baseline of accepted findings
+    Baseline {
+        #[command(subcommand)]
+        action: BaselineAction,
+    },
+    /// Manage git hooks that run codeguard before commits and pushes
+    Hooks {
+        #[command(subcommand)]
+        action: HooksAction,
+    },
+    /// Show trends of duration and findings per tool over recorded runs
+    History {
+        /// Directory whose runs to show
+        #[arg(default_value = ".")]
+        directory: PathBuf,
+
+        /// Only show this tool
+        #[arg(long)]
+        tool: Option<String>,
+
+        /// Number of most recent runs per tool
+        #[arg(long, default_value_t = 30)]
+        limit: usize,
+    },
+    /// List tests that both passed and failed on the same code
+    Flaky {
+        /// Directory whose tests to list
+        #[arg(default_value = ".")]
+        directory: PathBuf,
+
+        /// Run the test tools first, then rerun the tests that failed this
+        /// many times
+        #[arg(long, value_name = "N")]
+        rerun: Option<usize>,
+    },
+    /// Explain why each known tool was or was not detected
+    Doctor {
+        /// Directory to examine
+        #[arg(default_value = ".")]
+        directory: PathBuf,
+    },
+    /// Manage the cache of tool results
+    Cache {
+        #[command(subcommand)]
+        action: CacheAction,
+    },
+}
+
+#[derive(Subcommand)]
+enum CacheAction {
+    /// Delete all cached results
+    Clean,
+}
+
+#[derive(Subcommand)]
+enum HooksAction {
+    /// Install the pre-commit and pre-push hooks
+    Install {
+        /// Directory inside the repository
+        #[arg(default_value = ".")]
+        directory: PathBuf,
+
+        /// Replace hooks written by other tools (they are restored on uninstall)
+        #[arg(long)]
+        force: bool,
+    },
+    /// Remove the hooks and restore any hooks they replaced
+    Uninstall {
+        /// Directory inside the repository
+        #[arg(default_value = ".")]
+        directory: PathBuf,
+    },
+    /// Show which hooks are installed
+    Status {
+        /// Directory inside the repository
+        #[arg(default_value = ".")]
+        directory: PathBuf,
+    },
+    /// Run the checks of a hook; called by the installed hook scripts
+    #[command(hide = true)]
+    Run {
+        #[arg(value_enum)]
+        hook: hooks::HookKind,
+
+        #[arg(default_value = ".")]
+        directory: PathBuf,
+    },
+}
+
+#[derive(Subcommand)]
+enum BaselineAction {
+    /// Run all tools and accept every current finding
+    Create(BaselineArgs),
+    /// Run all tools and drop baseline entries that have been fixed
+    Update(BaselineArgs),
+}
+
+#[derive(Args)]
+struct BaselineArgs {
+    /// Directory to analyze
+    #[arg(default_value = ".")]
+    directory: PathBuf,
+
+    /// Baseline file [default: <directory>/.codeguard-baseline.json]
+    #[arg(long)]
+    file: Option<PathBuf>,
+}
+
+/// The arguments of a hook profile, parsed as if given to `codeguard run`
+#[derive(Parser)]
+struct HookProfile {
+    #[command(flatten)]
+    run: RunArgs,
+}
+
+#[derive(Args)]
+struct RunArgs {
+    /// Directory to analyze
+    #[arg(default_value = ".")]
+    directory: PathBuf,
+
+    /// Report format [default: github when GITHUB_ACTIONS=true, text otherwise]
+    #[arg(long, value_enum)]
+    format: Option<report::OutputFormat>,
+
+    /// Maximum number of findings listed per tool
+    #[arg(long, default_value_t = 10)]
+    max_findings: usize,
+
+    /// Apply the `draft_policy` of each gate category
+    #[arg(long)]
+    draft: bool,
+
+    /// URL prefix for file links in Markdown reports
+    /// (e.g. https://github.com/owner/repo/blob/<sha>)
+    #[arg(long)]
+    link_base: Option<String>,
+
+    /// Baseline file [default: <directory>/.codeguard-baseline.json, if present]
+    #[arg(long, conflicts_with = "no_baseline")]
+    baseline: Option<PathBuf>,
+
+    /// Report all findings, ignoring any baseline
+    #[arg(long)]
+    no_baseline: bool,
+
+    /// Only report findings changed relative to this git ref (e.g. origin/main)
+    #[arg(long, value_name = "REF")]
+    changed_since: Option<String>,
+
+    /// Whether `--changed-since` keeps findings on changed lines or in changed files
+    #[arg(long, value_enum, default_value_t = git::ChangeScope::Lines, requires = "changed_since")]
+    changed_scope: git::ChangeScope,
+
+    /// Always run tools instead of replaying cached results
+    #[arg(long)]
+    no_cache: bool,
+
+    /// Collect line coverage from the test tools
+    #[arg(long)]
+    coverage: bool,
 }
 
 #[tokio::main]
@@ -25,7 +234,41 @@ async fn main() -> Result<()> {
     let cli = Cli::parse();
     
     // Load configuration
-    let _config = config::load_config()?;
+    let config = config::load_config()?;
+
+    match cli.command {
+        Some(Command::Run(args)) => {
+            let code = run_headless(args, &config, true).await?;
+            std::process::exit(code);
+        }
+        Some(Command::Watch { directory }) => return run_watch(&directory, &config).await,
+        Some(Command::Baseline { action }) => return run_baseline(action, &config).await,
+        Some(Command::Hooks { action }) => match action {
+            HooksAction::Install { directory, force } => return hooks::install(&directory, force),
+            HooksAction::Uninstall { directory } => return hooks::uninstall(&directory),
+            HooksAction::Status { directory } => return hooks::status(&directory),
+            HooksAction::Run { hook, directory } => {
+                let code = run_hook(hook, directory, &config).await?;
+                std::process::exit(code);
+            }
+        },
+        Some(Command::History {
+            directory,
+            tool,
+            limit,
+        }) => return history::print(&directory, tool.as_deref(), limit),
+        Some(Command::Flaky { directory, rerun }) => {
+            if let Some(times) = rerun {
+                rerun_tests(&directory, times, &config).await;
+            }
+            return history::print_flaky(&directory);
+        }
+        Some(Command::Doctor { directory }) => return doctor::print(&directory, &config).await,
+        Some(Command::Cache { action }) => match action {
+            CacheAction::Clean => return cache::clean(),
+        },
+        None => {}
+    }
 
     // Setup terminal
     enable_raw_mode()?;
@@ -35,7 +278,7 @@ async fn main() -> Result<()> {
     let mut terminal = Terminal::new(backend)?;
 
     // Create app and run it
-    let mut app = ui::App::new(&cli.directory);
+    let mut app = ui::App::new(&cli.directory, &config, cli.draft, cli.changed_since, cli.no_cache);
     run_app(&mut terminal, &mut app).await?;
 
     // Restore terminal
@@ -50,6 +293,260 @@ async fn main() -> Result<()> {
     Ok(())
 }
 
+/// Run every detected tool once, print the report and return the exit code.
+/// With `record`, the run is added to the history; runs in a throwaway
+/// snapshot are not, as they would be recorded under its temporary path.
+async fn run_headless(args: RunArgs, config: &config::Config, record: bool) -> Result<i32> {
+    let mut tools = tools::detect_tools_with_config(&args.directory, config);
+    let format = args.format.unwrap_or_else(report::OutputFormat::detect);
+
+    let filter = paths::PathFilter::new(&args.directory, &config.ignore_patterns);
+    let changes = match &args.changed_since {
+        Some(base) => Some(git::ChangeSet::since(&args.directory, base, &filter)?),
+        None => None,
+    };
+    if let Some(changes) = &changes {
+        let files = changes.files();
+        tools = tools.iter().filter_map(|tool| tool.scoped_to(&files)).collect();
+    }
+    let report_dir = match args.coverage {
+        true => Some(tempfile::tempdir().context("Could not create a directory for coverage reports")?),
+        false => None,
+    };
+    let mut instrumented = Vec::new();
+    if let Some(report_dir) = &report_dir {
+        for tool in tools.iter_mut().filter(|tool| testcases::TestRunner::of(tool).is_some()) {
+            match coverage::instrument(tool, &args.directory, report_dir.path()) {
+                Ok(run) => {
+                    *tool = run.tool.clone();
+                    instrumented.push(run);
+                }
+                Err(err) => eprintln!("Warning: {:#}", err),
+            }
+        }
+    }
+    // Replaying an instrumented run would not write its coverage report
+    let cache = match args.no_cache {
+        true => None,
+        false => cache::ResultCache::new(filter)
+            .map(|cache| cache.bypassing(instrumented.iter().map(|run| run.tool.name.clone()))),
+    };
+
+    let (events, mut event_receiver) = tokio::sync::mpsc::channel(100);
+    let stream = format == report::OutputFormat::Jsonl;
+    let printer = tokio::spawn(async move {
+        while let Some(event) = event_receiver.recv().await {
+            // Findings and totals are only final after the baseline, the
+            // change filter and the gate; the report renders them
+            if !stream || matches!(event, runner::RunEvent::Diagnostic(_) | runner::RunEvent::Summary(_)) {
+                continue;
+            }
+            // Stop streaming once the consumer goes away (e.g. a closed pipe)
+            if writeln!(io::stdout(), "{}", serde_json::to_string(&event)?).is_err() {
+                break;
+            }
+        }
+        anyhow::Ok(())
+    });
+    let mut report = runner::run_tools(&tools, &args.directory, cache.as_ref(), &events).await;
+    drop(events);
+    if record {
+        record_history(&report, args.changed_since.is_none());
+    }
+    if !args.no_baseline {
+        let path = baseline::baseline_path(&args.directory, args.baseline.as_deref());
+        if let Some(accepted) = baseline::Baseline::load(&path)? {
+            report.baseline = Some(accepted.apply(&mut report));
+        } else if args.baseline.is_some() {
+            anyhow::bail!("Baseline {} does not exist", path.display());
+        }
+    }
+    if let Some(changes) = &changes {
+        changes.filter_report(&mut report, args.changed_scope);
+        report.changed_since = Some(changes.base.clone());
+    }
+    if report_dir.is_some() {
+        let mut collected = coverage::Coverage::default();
+        for run in &instrumented {
+            match coverage::Coverage::read(run, &args.directory) {
+                Ok(coverage) => collected.merge(coverage),
+                Err(err) => eprintln!("Warning: {:#}", err),
+            }
+        }
+        report.coverage = Some(collected.report(changes.as_ref()));
+    }
+    report.gate = Some(gates::evaluate_report(&config.gates, args.draft, &report));
+    printer.await??;
+
+    let options = report::RenderOptions {
+        max_findings: args.max_findings,
+        link_base: args.link_base,
+    };
+    // A closed pipe (e.g. `| head`) is not an error worth reporting
+    let _ = io::stdout().write_all(report::render(&report, format, &options).as_bytes());
+    Ok(if report.passed() { 0 } else { 1 })
+}
+
+/// Run every test tool of `directory` once, then rerun only the tests that
+/// failed `times` times, bypassing the cache, as `R` does in the TUI, so
+/// that failures which pass on a rerun show up as flaky
+async fn rerun_tests(directory: &std::path::Path, times: usize, config: &config::Config) {
+    let tools: Vec<_> = tools::detect_tools_with_config(directory, config)
+        .into_iter()
+        .filter(|tool| testcases::TestRunner::of(tool).is_some())
+        .collect();
+    println!("Running the test tools...");
+    let (events, _) = tokio::sync::mpsc::channel(1);
+    let report = runner::run_tools(&tools, directory, None, &events).await;
+    record_history(&report, true);
+
+    for tool in report.tools.iter().filter(|tool| tool.status == report::ToolStatus::Failed) {
+        let Some(test_runner) = testcases::TestRunner::of(&tool.tool) else {
+            continue;
+        };
+        let failed: Vec<String> = testcases::parse_tests(test_runner, &tool.output)
+            .into_iter()
+            .filter(|test| test.outcome == testcases::TestOutcome::Failed)
+            .map(|test| test.name)
+            .collect();
+        let Some(targeted) = testcases::rerun_failed(&tool.tool, &failed) else {
+            continue;
+        };
+        for run in 1..=times {
+            println!(
+                "Rerunning the failed tests of {} ({} of {})...",
+                tool.tool.name, run, times
+            );
+            let rerun = runner::run_tools(std::slice::from_ref(&targeted), directory, None, &events).await;
+            let recorded = history::History::open().and_then(|history| {
+                rerun.tools.iter().try_for_each(|rerun| {
+                    history.record_test_output(directory, &tool.tool, rerun.status, &rerun.output, false, &failed)
+                })
+            });
+            if let Err(err) = recorded {
+                eprintln!("Warning: could not record run history: {:#}", err);
+            }
+        }
+    }
+}
+
+/// Add the outcomes of `report` to the run history. The history is a
+/// convenience, so failing to record only produces a warning.
+fn record_history(report: &report::RunReport, whole_suite: bool) {
+    if let Err(err) =
+        history::History::open().and_then(|history| history.record_report(report, whole_suite))
+    {
+        eprintln!("Warning: could not record run history: {:#}", err);
+    }
+}
+
+/// Run the checks of `hook` with its configured profile. Pre-commit checks
+/// run against a snapshot of the staged content, so unstaged edits cannot
+/// affect the outcome.
+async fn run_hook(hook: hooks::HookKind, directory: PathBuf, config: &config::Config) -> Result<i32> {
+    let profile = match hook {
+        hooks::HookKind::PreCommit => &config.hooks.pre_commit,
+        hooks::HookKind::PrePush => &config.hooks.pre_push,
+    };
+    let mut args = HookProfile::try_parse_from(
+        std::iter::once("codeguard run").chain(profile.iter().map(String::as_str)),
+    )
+    .with_context(|| format!("Invalid {} hook profile", hook.file_name()))?
+    .run;
+
+    match hook {
+        hooks::HookKind::PreCommit => {
+            let snapshot = hooks::StagedSnapshot::create(&directory)?;
+            args.directory = snapshot.path().to_path_buf();
+            if snapshot.is_initial_commit() {
+                // Everything in the first commit is new, and the snapshot
+                // is no repository to diff in
+                args.changed_since = None;
+            }
+            run_headless(args, config, false).await
+        }
+        hooks::HookKind::PrePush => {
+            args.directory = directory;
+            run_headless(args, config, true).await
+        }
+    }
+}
+
+/// Run every tool once, then rerun the tools whose inputs change and print
+/// a report of each rerun
+async fn run_watch(directory: &std::path::Path, config: &config::Config) -> Result<()> {
+    let tools = tools::detect_tools_with_config(directory, config);
+    let mut watcher = watch::ChangeWatcher::new(directory, &config.ignore_patterns)?;
+    let cache = cache::ResultCache::new(paths::PathFilter::new(directory, &config.ignore_patterns));
+    let options = report::RenderOptions {
+        max_findings: 10,
+        link_base: None,
+    };
+    let mut affected = tools.clone();
+    loop {
+        if !affected.is_empty() {
+            let (events, _) = tokio::sync::mpsc::channel(1);
+            let report = runner::run_tools(&affected, directory, cache.as_ref(), &events).await;
+            record_history(&report, true);
+            print!("{}", report::render(&report, report::OutputFormat::Text, &options));
+            println!("Watching {} for changes...", directory.display());
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        affected = match watcher.poll() {
+            Some(changed) => {
+                println!("\nChanged: {}", changed.join(", "));
+                let affected: Vec<_> = tools
+                    .iter()
+                    .filter(|tool| tool.is_affected_by(&changed))
+                    .cloned()
+                    .collect();
+                if affected.is_empty() {
+                    println!("No tools affected");
+                }
+                affected
+            }
+            None => Vec::new(),
+        };
+    }
+}
+
+/// Create or shrink the baseline file from a fresh run of every tool
+async fn run_baseline(action: BaselineAction, config: &config::Config) -> Result<()> {
+    let (update, args) = match action {
+        BaselineAction::Create(args) => (false, args),
+        BaselineAction::Update(args) => (true, args),
+    };
+    let path = baseline::baseline_path(&args.directory, args.file.as_deref());
+    let existing = if update {
+        Some(baseline::Baseline::load(&path)?.ok_or_else(|| {
+            anyhow::anyhow!("No baseline at {}; run `codeguard baseline create` first", path.display())
+        })?)
+    } else {
+        None
+    };
+
+    let tools = tools::detect_tools_with_config(&args.directory, config);
+    let cache = cache::ResultCache::new(paths::PathFilter::new(&args.directory, &config.ignore_patterns));
+    let (events, _) = tokio::sync::mpsc::channel(1);
+    let report = runner::run_tools(&tools, &args.directory, cache.as_ref(), &events).await;
+
+    let updated = match &existing {
+        Some(existing) => existing.shrink(&report),
+        None => baseline::Baseline::from_report(&report),
+    };
+    updated.save(&path)?;
+    match existing {
+        Some(existing) => println!(
+            "Baseline {}: {} findings ({} fixed)",
+            path.display(),
+            updated.total(),
+            existing.total() - updated.total()
+        ),
+        None => println!("Baseline {}: {} findings", path.display(), updated.total()),
+    }
+    Ok(())
+}
+
 async fn run_app<B: Backend>(terminal: &mut Terminal<B>, app: &mut ui::App) -> Result<()> {
     loop {
         terminal.draw(|f| app.draw(f))?;
@@ -0,0 +1,239 @@
+use anyhow::Result;
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use ignore::{DirEntry, WalkBuilder};
+use std::path::{Component, Path, PathBuf};
+
+/// Files holding gitignore-syntax rules, read in every directory
+const IGNORE_FILES: [&str; 2] = [".gitignore", ".ignore"];
+
+/// Directories holding installed dependencies or build output. Their
+/// manifests belong to someone else, and copies of a project (staged
+/// snapshots, fix previews) link them rather than copy them.
+pub const DEPENDENCY_DIRS: [&str; 5] = ["node_modules", "vendor", ".venv", "venv", "target"];
+
+/// Decides which paths under a directory codeguard should leave alone.
+///
+/// Rules come from `.gitignore` and `.ignore` files at any depth,
+/// `.git/info/exclude` and the configured `ignore_patterns`, which take
+/// precedence over everything else. All file walking goes through this filter.
+#[derive(Clone)]
+pub struct PathFilter {
+    root: PathBuf,
+    patterns: Vec<String>,
+    /// The configured patterns, rooted at `root`
+    configured: Gitignore,
+    /// Matchers of the ignore files found, deepest directories first so the
+    /// closest rule wins as in git
+    nested: Vec<(PathBuf, Gitignore)>,
+}
+
+impl PathFilter {
+    /// `patterns` use gitignore syntax and are relative to `root`
+    pub fn new(root: &Path, patterns: &[String]) -> Self {
+        let mut builder = GitignoreBuilder::new(root);
+        for pattern in patterns {
+            // An invalid pattern is skipped rather than disabling the rest
+            let _ = builder.add_line(None, pattern);
+        }
+        let mut filter = Self {
+            root: root.to_path_buf(),
+            patterns: patterns.to_vec(),
+            configured: builder.build().unwrap_or_else(|_| Gitignore::empty()),
+            nested: Vec::new(),
+        };
+
+        let directories: Vec<PathBuf> = std::iter::once(root.to_path_buf())
+            .chain(
+                filter
+                    .walk()
+                    .filter(|entry| entry.file_type().is_some_and(|t| t.is_dir()))
+                    .map(|entry| entry.into_path()),
+            )
+            .collect();
+        for directory in directories {
+            let mut builder = GitignoreBuilder::new(&directory);
+            let mut found = false;
+            for name in IGNORE_FILES {
+                let file = directory.join(name);
+                if file.is_file() {
+                    let _ = builder.add(file);
+                    found = true;
+                }
+            }
+            if let (true, Ok(matcher), Ok(relative)) =
+                (found, builder.build(), directory.strip_prefix(root))
+            {
+                filter.nested.push((relative.to_path_buf(), matcher));
+            }
+        }
+        // Lowest precedence of all, after the root's own ignore files
+        let mut exclude = GitignoreBuilder::new(root);
+        let _ = exclude.add(root.join(".git/info/exclude"));
+        if let Ok(exclude) = exclude.build() {
+            filter.nested.push((PathBuf::new(), exclude));
+        }
+        // Stable, so equally deep matchers keep the order above
+        filter
+            .nested
+            .sort_by_key(|(directory, _)| std::cmp::Reverse(directory.components().count()));
+        filter
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// The configured `ignore_patterns`
+    pub fn patterns(&self) -> &[String] {
+        &self.patterns
+    }
+
+    /// Whether `path` (absolute, or relative to the root) or any directory
+    /// containing it is ignored. Paths outside the root are never ignored.
+    pub fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        let relative = if path.is_absolute() {
+            match path.strip_prefix(&self.root) {
+                Ok(relative) => relative,
+                Err(_) => return false,
+            }
+        } else {
+            path
+        };
+        if relative.as_os_str().is_empty() {
+            return false;
+        }
+        // Repository internals are never interesting, whatever the rules say
+        if relative.components().any(|c| c == Component::Normal(".git".as_ref())) {
+            return true;
+        }
+        let configured = self.configured.matched_path_or_any_parents(relative, is_dir);
+        if !configured.is_none() {
+            return configured.is_ignore();
+        }
+        for (directory, matcher) in &self.nested {
+            let Ok(inner) = relative.strip_prefix(directory) else {
+                continue;
+            };
+            if inner.as_os_str().is_empty() {
+                continue;
+            }
+            let matched = matcher.matched_path_or_any_parents(inner, is_dir);
+            if !matched.is_none() {
+                return matched.is_ignore();
+            }
+        }
+        false
+    }
+
+    /// Every file and directory under the root that is not ignored,
+    /// excluding the root itself. Ignored directories are not descended into.
+    pub fn walk(&self) -> impl Iterator<Item = DirEntry> {
+        self.walk_under(&self.root, None)
+    }
+
+    /// Like [`PathFilter::walk`], but starting at `directory` (under the
+    /// root) and going at most `max_depth` levels below it
+    pub fn walk_under(
+        &self,
+        directory: &Path,
+        max_depth: Option<usize>,
+    ) -> impl Iterator<Item = DirEntry> {
+        let configured = self.configured.clone();
+        let root = self.root.clone();
+        WalkBuilder::new(directory)
+            .max_depth(max_depth)
+            .hidden(false)
+            .require_git(false)
+            .git_global(false)
+            .filter_entry(move |entry| {
+                let is_dir = entry.file_type().is_some_and(|t| t.is_dir());
+                let relative = entry.path().strip_prefix(&root).unwrap_or(entry.path());
+                entry.file_name() != ".git"
+                    && (relative.as_os_str().is_empty()
+                        || !configured.matched(relative, is_dir).is_ignore())
+            })
+            .build()
+            .filter_map(Result::ok)
+            .filter(|entry| entry.depth() > 0)
+    }
+}
+
+/// Link the directory `source` at `target`, where symlinks are supported
+#[cfg(unix)]
+pub fn link_dir(source: &Path, target: &Path) -> Result<()> {
+    use anyhow::Context;
+    std::os::unix::fs::symlink(source, target)
+        .with_context(|| format!("Could not link {}", source.display()))
+}
+
+#[cfg(not(unix))]
+pub fn link_dir(_source: &Path, _target: &Path) -> Result<()> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn write(root: &Path, path: &str, contents: &str) {
+        let path = root.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+    }
+
+    fn patterns(patterns: &[&str]) -> Vec<String> {
+        patterns.iter().map(|pattern| pattern.to_string()).collect()
+    }
+
+    #[test]
+    fn combines_ignore_files_and_configured_patterns() {
+        let root = tempfile::tempdir().unwrap();
+        let root = root.path();
+        write(root, ".gitignore", "*.log\nbuild/\n");
+        write(root, "web/.gitignore", "dist/\n!keep.log\n");
+        write(root, "web/.ignore", "*.snap\n");
+        let filter = PathFilter::new(root, &patterns(&["__pycache__", "!web/important.log"]));
+
+        let ignored = |path: &str, is_dir| filter.is_ignored(Path::new(path), is_dir);
+        assert!(ignored("debug.log", false));
+        assert!(ignored("build", true));
+        assert!(ignored("build/out.o", false));
+        // Only directories match directory-only patterns
+        assert!(!ignored("build", false));
+        assert!(ignored("src/__pycache__/a.pyc", false));
+        assert!(ignored("web/dist/app.js", false));
+        assert!(!ignored("dist/app.js", false));
+        assert!(ignored("web/ui.snap", false));
+        // The closest rule wins, and configured patterns win over all
+        assert!(!ignored("web/keep.log", false));
+        assert!(!ignored("web/important.log", false));
+        assert!(ignored("web/other.log", false));
+        assert!(ignored(".git/config", false));
+        assert!(!ignored("src/app.py", false));
+        assert!(ignored(&root.join("a.log").to_string_lossy(), false));
+        assert!(!ignored("/elsewhere/a.log", false));
+    }
+
+    #[test]
+    fn walks_only_what_is_not_ignored() {
+        let root = tempfile::tempdir().unwrap();
+        let root = root.path();
+        write(root, ".gitignore", "*.log\n");
+        write(root, "src/app.py", "");
+        write(root, "src/debug.log", "");
+        write(root, "venv/lib/site.py", "");
+        write(root, ".git/HEAD", "");
+        let filter = PathFilter::new(root, &patterns(&["venv"]));
+
+        let mut walked: Vec<String> = filter
+            .walk()
+            .map(|entry| {
+                let path = entry.path().strip_prefix(root).unwrap();
+                path.to_string_lossy().replace('\\', "/")
+            })
+            .collect();
+        walked.sort();
+        assert_eq!(walked, [".gitignore", "src", "src/app.py"]);
+    }
+}
@@ -0,0 +1,299 @@
+use crate::paths::{PathFilter, DEPENDENCY_DIRS};
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use std::collections::BTreeMap;
+use std::path::{Component, Path};
+
+/// How many directory levels below the analyzed directory discovery looks
+/// for project manifests. Workspace members are found at any depth.
+pub const MAX_DEPTH: usize = 4;
+
+/// Files marking a directory as a project of its own
+const MANIFESTS: [&str; 8] = [
+    "Cargo.toml",
+    "package.json",
+    "composer.json",
+    "pyproject.toml",
+    "setup.py",
+    "setup.cfg",
+    "requirements.txt",
+    "pytest.ini",
+];
+
+/// A directory whose tools run with it as their working directory
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Project {
+    /// Path relative to the analyzed directory; empty for the directory itself
+    pub path: String,
+    /// Directory whose lockfile decides the JavaScript package manager: the
+    /// npm, yarn, pnpm or bun workspace root for workspace members
+    pub js_root: String,
+    /// Whether the project is a member of a Cargo workspace, whose root
+    /// already runs the Cargo tools for it
+    pub in_cargo_workspace: bool,
+}
+
+/// Find the analyzed directory and every project below it.
+///
+/// Directories up to [`MAX_DEPTH`] levels deep that hold a manifest are
+/// projects, as are the members of npm, yarn and pnpm workspaces and Composer
+/// path repositories. Ignored paths are skipped. The analyzed directory comes
+/// first, followed by the others sorted by path.
+pub fn discover_projects(directory: &Path, filter: &PathFilter) -> Vec<Project> {
+    let mut projects: BTreeMap<String, Project> = BTreeMap::new();
+    projects.insert(String::new(), Project::default());
+    for entry in filter.walk_under(directory, Some(MAX_DEPTH)) {
+        if let Some(path) = project_path(directory, entry.path()) {
+            projects.entry(path.clone()).or_insert_with(|| Project {
+                js_root: path.clone(),
+                path,
+                ..Default::default()
+            });
+        }
+    }
+
+    // Workspaces can only add members, so each pass sees the projects the
+    // previous ones found
+    let roots: Vec<String> = projects.keys().cloned().collect();
+    for root in &roots {
+        let base = directory.join(root);
+        let members = js_workspace_members(&base);
+        for member in expand(directory, filter, root, &members) {
+            projects
+                .entry(member.clone())
+                .or_insert_with(|| Project {
+                    path: member.clone(),
+                    ..Default::default()
+                })
+                .js_root = root.clone();
+        }
+        for member in expand(directory, filter, root, &composer_path_repositories(&base)) {
+            projects.entry(member.clone()).or_insert_with(|| Project {
+                js_root: member.clone(),
+                path: member,
+                ..Default::default()
+            });
+        }
+    }
+
+    let roots: Vec<String> = projects.keys().cloned().collect();
+    for root in roots {
+        let Some((members, excluded)) = cargo_workspace(&directory.join(&root)) else {
+            continue;
+        };
+        for (path, project) in projects.iter_mut() {
+            let Some(relative) = relative_to(&root, path) else {
+                continue;
+            };
+            if !relative.is_empty()
+                && directory.join(path).join("Cargo.toml").is_file()
+                && members.is_match(relative)
+                && !excluded.is_match(relative)
+            {
+                project.in_cargo_workspace = true;
+            }
+        }
+    }
+
+    projects.into_values().collect()
+}
+
+/// `path` as a project path if it is a directory holding a manifest outside
+/// any dependency directory
+fn project_path(directory: &Path, path: &Path) -> Option<String> {
+    let relative = path.strip_prefix(directory).ok()?;
+    let in_dependencies = relative.components().any(|component| {
+        matches!(component, Component::Normal(name) if DEPENDENCY_DIRS.iter().any(|d| name == *d))
+    });
+    if in_dependencies || !path.is_dir() || !MANIFESTS.iter().any(|m| path.join(m).is_file()) {
+        return None;
+    }
+    Some(relative.to_string_lossy().to_string())
+}
+
+/// Project paths below `root` (a project path) matching the member globs
+/// `patterns`, at any depth
+fn expand(directory: &Path, filter: &PathFilter, root: &str, patterns: &[String]) -> Vec<String> {
+    let Some(globs) = glob_set(patterns) else {
+        return Vec::new();
+    };
+    filter
+        .walk_under(&directory.join(root), None)
+        .filter_map(|entry| project_path(directory, entry.path()))
+        .filter(|path| relative_to(root, path).is_some_and(|relative| globs.is_match(relative)))
+        .collect()
+}
+
+/// `path` relative to `root`, both project paths
+fn relative_to<'a>(root: &str, path: &'a str) -> Option<&'a str> {
+    if root.is_empty() {
+        return Some(path);
+    }
+    let rest = path.strip_prefix(root)?;
+    if rest.is_empty() {
+        Some(rest)
+    } else {
+        rest.strip_prefix('/')
+    }
+}
+
+fn glob_set(patterns: &[String]) -> Option<GlobSet> {
+    if patterns.is_empty() {
+        return None;
+    }
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        let pattern = pattern.trim_start_matches("./").trim_end_matches('/');
+        if let Ok(glob) = GlobBuilder::new(pattern).literal_separator(true).build() {
+            builder.add(glob);
+        }
+    }
+    builder.build().ok()
+}
+
+/// Member globs of an npm or yarn (`workspaces` in package.json) or pnpm
+/// (`pnpm-workspace.yaml`) workspace rooted at `directory`
+fn js_workspace_members(directory: &Path) -> Vec<String> {
+    let mut members = Vec::new();
+    if let Some(package) = read_json(&directory.join("package.json")) {
+        // Either a list or, with yarn, `{ "packages": [...] }`
+        let workspaces = package.get("workspaces");
+        let list = workspaces
+            .and_then(|w| w.get("packages"))
+            .or(workspaces)
+            .and_then(|w| w.as_array());
+        members.extend(list.into_iter().flatten().filter_map(|m| m.as_str()).map(str::to_string));
+    }
+    if let Some(workspace) = std::fs::read_to_string(directory.join("pnpm-workspace.yaml"))
+        .ok()
+        .and_then(|contents| serde_yaml::from_str::<serde_yaml::Value>(&contents).ok())
+    {
+        let list = workspace.get("packages").and_then(|p| p.as_sequence());
+        members.extend(
+            list.into_iter()
+                .flatten()
+                .filter_map(|m| m.as_str())
+                // Negated patterns exclude; they only narrow the globs
+                .filter(|m| !m.starts_with('!'))
+                .map(str::to_string),
+        );
+    }
+    members
+}
+
+/// Globs of the local packages in a composer.json's `path` repositories
+fn composer_path_repositories(directory: &Path) -> Vec<String> {
+    let Some(composer) = read_json(&directory.join("composer.json")) else {
+        return Vec::new();
+    };
+    // Repositories are a list, or an object keyed by name
+    let repositories: Vec<&serde_json::Value> = match composer.get("repositories") {
+        Some(serde_json::Value::Array(list)) => list.iter().collect(),
+        Some(serde_json::Value::Object(map)) => map.values().collect(),
+        _ => Vec::new(),
+    };
+    repositories
+        .into_iter()
+        .filter(|repository| repository.get("type").and_then(|t| t.as_str()) == Some("path"))
+        .filter_map(|repository| repository.get("url").and_then(|u| u.as_str()))
+        // Packages outside the analyzed tree are not ours to check
+        .filter(|url| !url.starts_with("..") && !url.starts_with('/'))
+        .map(str::to_string)
+        .collect()
+}
+
+/// Member and exclude globs of the Cargo workspace rooted at `directory`
+fn cargo_workspace(directory: &Path) -> Option<(GlobSet, GlobSet)> {
+    let manifest: toml::Value = std::fs::read_to_string(directory.join("Cargo.toml"))
+        .ok()?
+        .parse()
+        .ok()?;
+    let workspace = manifest.get("workspace")?;
+    let globs = |key: &str| -> Vec<String> {
+        workspace
+            .get(key)
+            .and_then(|list| list.as_array())
+            .into_iter()
+            .flatten()
+            .filter_map(|m| m.as_str())
+            .map(str::to_string)
+            .collect()
+    };
+    let members = glob_set(&globs("members"))?;
+    let excluded = glob_set(&globs("exclude")).unwrap_or_else(GlobSet::empty);
+    Some((members, excluded))
+}
+
+fn read_json(path: &Path) -> Option<serde_json::Value> {
+    serde_json::from_str(&std::fs::read_to_string(path).ok()?).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn write(root: &Path, path: &str, contents: &str) {
+        let path = root.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+    }
+
+    fn project(path: &str, js_root: &str, in_cargo_workspace: bool) -> Project {
+        Project {
+            path: path.to_string(),
+            js_root: js_root.to_string(),
+            in_cargo_workspace,
+        }
+    }
+
+    #[test]
+    fn finds_manifests_and_workspace_members() {
+        let root = tempfile::tempdir().unwrap();
+        let root = root.path();
+        write(root, ".gitignore", "generated/\n");
+        write(root, "Cargo.toml", "[workspace]\nmembers = [\"crates/*\"]\nexclude = [\"crates/old\"]\n");
+        write(root, "crates/core/Cargo.toml", "[package]\nname = \"core\"\n");
+        write(root, "crates/old/Cargo.toml", "[package]\nname = \"old\"\n");
+        write(root, "package.json", r#"{"workspaces": {"packages": ["packages/*"]}}"#);
+        write(root, "packages/ui/package.json", "{}");
+        write(root, "web/package.json", "{}");
+        write(root, "web/pnpm-workspace.yaml", "packages:\n  - 'libs/**'\n  - '!libs/skip'\n");
+        // Deeper than discovery looks, but a workspace member
+        write(root, "web/libs/a/b/c/package.json", "{}");
+        write(root, "php/composer.json", r#"{"repositories": [{"type": "path", "url": "./packages/*"}, {"type": "path", "url": "../shared"}]}"#);
+        write(root, "php/packages/lib/composer.json", "{}");
+        write(root, "tools/scripts/requirements.txt", "");
+        write(root, "node_modules/left-pad/package.json", "{}");
+        write(root, "generated/package.json", "{}");
+        write(root, "docs/README.md", "");
+
+        let filter = PathFilter::new(root, &[]);
+        assert_eq!(
+            discover_projects(root, &filter),
+            [
+                project("", "", false),
+                project("crates/core", "crates/core", true),
+                project("crates/old", "crates/old", false),
+                project("packages/ui", "", false),
+                project("php", "php", false),
+                project("php/packages/lib", "php/packages/lib", false),
+                project("tools/scripts", "tools/scripts", false),
+                project("web", "web", false),
+                project("web/libs/a/b/c", "web", false),
+            ]
+        );
+    }
+
+    #[test]
+    fn skips_ignored_projects() {
+        let root = tempfile::tempdir().unwrap();
+        let root = root.path();
+        write(root, "api/pyproject.toml", "");
+        write(root, "legacy/setup.py", "");
+        let filter = PathFilter::new(root, &["legacy".to_string()]);
+        assert_eq!(
+            discover_projects(root, &filter),
+            [project("", "", false), project("api", "api", false)]
+        );
+    }
+}
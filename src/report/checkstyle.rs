@@ -0,0 +1,69 @@
+use super::RunReport;
+use crate::findings::{Finding, Severity};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+/// Render all findings as a Checkstyle XML report, grouped by file
+pub fn render(report: &RunReport) -> String {
+    let mut files: BTreeMap<&str, Vec<&Finding>> = BTreeMap::new();
+    for finding in report.tools.iter().flat_map(|t| &t.findings) {
+        files.entry(&finding.file).or_default().push(finding);
+    }
+
+    let mut out = String::new();
+    let _ = writeln!(out, r#"<?xml version="1.0" encoding="UTF-8"?>"#);
+    let _ = writeln!(out, r#"<checkstyle version="4.3">"#);
+    // Checkstyle has no place for the versions of the tools behind it
+    for tool in report.tools.iter().filter(|tool| tool.version.is_some()) {
+        let _ = writeln!(out, "  <!-- {} -->", tool.title().replace("--", "- -"));
+    }
+    for (file, findings) in files {
+        let _ = writeln!(out, r#"  <file name="{}">"#, escape(file));
+        for finding in findings {
+            let _ = write!(out, r#"    <error line="{}""#, finding.line.unwrap_or(0));
+            if let Some(column) = finding.column {
+                let _ = write!(out, r#" column="{}""#, column);
+            }
+            let source = match &finding.rule {
+                Some(rule) => format!("{}.{}", finding.tool, rule),
+                None => finding.tool.clone(),
+            };
+            let _ = writeln!(
+                out,
+                r#" severity="{}" message="{}" source="{}"/>"#,
+                severity(finding.severity),
+                escape(&finding.message),
+                escape(&source),
+            );
+        }
+        let _ = writeln!(out, "  </file>");
+    }
+    let _ = writeln!(out, "</checkstyle>");
+    out
+}
+
+fn severity(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Error => "error",
+        Severity::Warning => "warning",
+        Severity::Info => "info",
+    }
+}
+
+/// Escape text for use inside a double-quoted XML attribute
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            '\n' => escaped.push_str("&#10;"),
+            c if c.is_control() => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
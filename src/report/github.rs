@@ -0,0 +1,100 @@
+use super::{RunReport, ToolStatus};
+use crate::findings::{Finding, Severity};
+use std::fmt::Write;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Render GitHub Actions workflow commands: each tool's raw output in a
+/// collapsible group, followed by one annotation per finding
+pub fn render(report: &RunReport) -> String {
+    let mut out = String::new();
+    // Raw output may itself contain `::` lines; stop GitHub from executing
+    // them as workflow commands while it is printed
+    let token = format!(
+        "codeguard-{}",
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos())
+            .unwrap_or_default()
+    );
+
+    for tool in &report.tools {
+        let _ = writeln!(
+            out,
+            "::group::{} ({})",
+            escape_data(&tool.title()),
+            tool.status.label()
+        );
+        let _ = writeln!(out, "::stop-commands::{}", token);
+        let _ = writeln!(out, "{}", tool.output.trim_end());
+        let _ = writeln!(out, "::{}::", token);
+        let _ = writeln!(out, "::endgroup::");
+
+        for finding in &tool.findings {
+            let _ = writeln!(out, "{}", annotation(finding));
+        }
+        if tool.status == ToolStatus::Failed && tool.findings.is_empty() {
+            let _ = writeln!(
+                out,
+                "::error title={}::{} failed with exit code {}",
+                escape_property(&tool.tool.name),
+                escape_data(&tool.tool.name),
+                tool.exit_code
+                    .map(|code| code.to_string())
+                    .unwrap_or_else(|| "unknown".to_string()),
+            );
+        }
+    }
+
+    let summary = report.summary();
+    let _ = writeln!(
+        out,
+        "{} passed, {} failed, {} skipped",
+        summary.passed, summary.failed, summary.skipped
+    );
+    if let Some(gate) = report.gate.as_ref().filter(|gate| !gate.passed) {
+        let _ = writeln!(
+            out,
+            "::error title=Quality gate::{}",
+            escape_data(&gate.violations.join("; "))
+        );
+    }
+    out
+}
+
+fn annotation(finding: &Finding) -> String {
+    let command = match finding.severity {
+        Severity::Error => "error",
+        Severity::Warning => "warning",
+        Severity::Info => "notice",
+    };
+    let mut properties = vec![format!("file={}", escape_property(&finding.file))];
+    if let Some(line) = finding.line {
+        properties.push(format!("line={}", line));
+    }
+    if let Some(column) = finding.column {
+        properties.push(format!("col={}", column));
+    }
+    let title = match &finding.rule {
+        Some(rule) => format!("{} {}", finding.tool, rule),
+        None => finding.tool.clone(),
+    };
+    properties.push(format!("title={}", escape_property(&title)));
+    format!(
+        "::{} {}::{}",
+        command,
+        properties.join(","),
+        escape_data(&finding.message)
+    )
+}
+
+/// Escape a workflow command message
+fn escape_data(text: &str) -> String {
+    text.replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+/// Escape a workflow command property value
+fn escape_property(text: &str) -> String {
+    escape_data(text).replace(':', "%3A").replace(',', "%2C")
+}
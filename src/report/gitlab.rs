@@ -0,0 +1,73 @@
+use super::RunReport;
+use crate::findings::{unique_fingerprints, Finding, Severity};
+use serde::Serialize;
+use std::collections::HashMap;
+
+#[derive(Serialize)]
+struct Issue<'a> {
+    description: String,
+    check_name: String,
+    /// The tool that reported the issue, with its version
+    engine_name: String,
+    fingerprint: String,
+    severity: &'static str,
+    location: Location<'a>,
+}
+
+#[derive(Serialize)]
+struct Location<'a> {
+    path: &'a str,
+    lines: Lines,
+}
+
+#[derive(Serialize)]
+struct Lines {
+    begin: u32,
+}
+
+/// Render all findings as a GitLab Code Quality report (a JSON array)
+pub fn render(report: &RunReport) -> String {
+    let findings: Vec<&Finding> = report.tools.iter().flat_map(|t| &t.findings).collect();
+    let fingerprints = unique_fingerprints(findings.iter().copied());
+    let engines: HashMap<&str, String> = report
+        .tools
+        .iter()
+        .map(|tool| (tool.tool.name.as_str(), tool.title()))
+        .collect();
+
+    let issues: Vec<Issue> = findings
+        .into_iter()
+        .zip(fingerprints)
+        .map(|(finding, fingerprint)| Issue {
+            description: format!("{}: {}", finding.tool, finding.message),
+            check_name: match &finding.rule {
+                Some(rule) => format!("{}/{}", finding.tool, rule),
+                None => finding.tool.clone(),
+            },
+            engine_name: engines
+                .get(finding.tool.as_str())
+                .cloned()
+                .unwrap_or_else(|| finding.tool.clone()),
+            fingerprint,
+            severity: severity(finding.severity),
+            location: Location {
+                path: &finding.file,
+                lines: Lines {
+                    begin: finding.line.unwrap_or(1),
+                },
+            },
+        })
+        .collect();
+
+    let mut out = serde_json::to_string_pretty(&issues).unwrap_or_default();
+    out.push('\n');
+    out
+}
+
+fn severity(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Error => "major",
+        Severity::Warning => "minor",
+        Severity::Info => "info",
+    }
+}
@@ -0,0 +1,20 @@
+use super::{RunReport, Summary};
+use serde::Serialize;
+
+#[derive(Serialize)]
+struct JsonReport<'a> {
+    #[serde(flatten)]
+    report: &'a RunReport,
+    summary: Summary,
+}
+
+/// Render the final report as a pretty-printed JSON document
+pub fn render(report: &RunReport) -> String {
+    let document = JsonReport {
+        report,
+        summary: report.summary(),
+    };
+    let mut out = serde_json::to_string_pretty(&document).unwrap_or_default();
+    out.push('\n');
+    out
+}
@@ -0,0 +1,24 @@
+use super::RunReport;
+use crate::runner::RunEvent;
+
+/// Render the events that need the whole run: a `diagnostic` per finding
+/// left after the baseline and the change filter, the `summary` of those, and
+/// the `gate` verdict. The events streamed before them describe each tool as
+/// it ran, so their counts include findings dropped here.
+pub fn render(report: &RunReport) -> String {
+    let mut events: Vec<RunEvent> = report
+        .tools
+        .iter()
+        .flat_map(|tool| &tool.findings)
+        .map(|finding| RunEvent::Diagnostic(finding.clone()))
+        .collect();
+    events.push(RunEvent::Summary(report.summary()));
+    if let Some(gate) = &report.gate {
+        events.push(RunEvent::Gate(gate.clone()));
+    }
+    events
+        .iter()
+        .filter_map(|event| serde_json::to_string(event).ok())
+        .map(|line| line + "\n")
+        .collect()
+}
@@ -0,0 +1,194 @@
+use super::{RenderOptions, RunReport, ToolReport, ToolStatus};
+use crate::coverage::CoverageReport;
+use crate::findings::{Finding, Severity};
+use std::fmt::Write;
+
+/// Outputs longer than this many lines are collapsed into a `<details>` block
+const COLLAPSE_AFTER_LINES: usize = 20;
+
+/// Render a compact Markdown summary for pull request comments
+pub fn render(report: &RunReport, options: &RenderOptions) -> String {
+    let mut out = String::new();
+
+    let _ = writeln!(out, "## CodeGuard report\n");
+    let _ = writeln!(
+        out,
+        "**{}** passed, **{}** failed, **{}** skipped\n",
+        report.count(ToolStatus::Passed),
+        report.count(ToolStatus::Failed),
+        report.count(ToolStatus::Skipped),
+    );
+    if let Some(base) = &report.changed_since {
+        let _ = writeln!(out, "Only findings in changes since `{}`\n", base.replace('`', "'"));
+    }
+    if let Some(baseline) = &report.baseline {
+        let _ = writeln!(
+            out,
+            "Baseline: **{}** new, **{}** known, **{}** fixed\n",
+            baseline.new, baseline.baselined, baseline.fixed
+        );
+    }
+    if let Some(gate) = &report.gate {
+        if gate.passed {
+            let _ = writeln!(out, "Quality gate: **passed**\n");
+        } else {
+            let _ = writeln!(out, "Quality gate: **failed**\n");
+            for violation in &gate.violations {
+                let _ = writeln!(out, "- {}", escape(violation));
+            }
+            let _ = writeln!(out);
+        }
+    }
+
+    let _ = writeln!(
+        out,
+        "| Tool | Category | Status | Errors | Warnings | Duration |"
+    );
+    let _ = writeln!(out, "| --- | --- | --- | ---: | ---: | ---: |");
+    for tool in &report.tools {
+        let _ = writeln!(
+            out,
+            "| {} | {} | {} | {} | {} | {:.1}s{} |",
+            escape(&tool.title()),
+            escape(&tool.tool.category),
+            tool.status.label(),
+            tool.count(Severity::Error),
+            tool.count(Severity::Warning),
+            tool.duration.as_secs_f64(),
+            if tool.cached { " (cached)" } else { "" },
+        );
+    }
+
+    if let Some(coverage) = &report.coverage {
+        render_coverage(&mut out, coverage, options);
+    }
+
+    for tool in report
+        .tools
+        .iter()
+        .filter(|t| t.status != ToolStatus::Passed || !t.findings.is_empty())
+    {
+        render_tool(&mut out, tool, options);
+    }
+
+    out
+}
+
+fn render_coverage(out: &mut String, coverage: &CoverageReport, options: &RenderOptions) {
+    let _ = writeln!(
+        out,
+        "\n### Coverage\n\n**{:.1}%** of {} lines{}",
+        coverage.lines.percent().unwrap_or(0.0),
+        coverage.lines.total,
+        coverage
+            .changed_lines
+            .map(|changed| format!(
+                ", **{:.1}%** of {} changed lines",
+                changed.percent().unwrap_or(100.0),
+                changed.total
+            ))
+            .unwrap_or_default(),
+    );
+    let files = coverage.least_covered();
+    if files.is_empty() {
+        return;
+    }
+    let _ = writeln!(out, "\n| File | Coverage | Uncovered lines |");
+    let _ = writeln!(out, "| --- | ---: | --- |");
+    for file in files.iter().take(options.max_findings) {
+        let _ = writeln!(
+            out,
+            "| {} | {:.1}% | {} |",
+            escape(&file.file),
+            file.lines.percent().unwrap_or(0.0),
+            file.ranges()
+        );
+    }
+    if files.len() > options.max_findings {
+        let _ = writeln!(out, "\n...and {} more files", files.len() - options.max_findings);
+    }
+}
+
+fn render_tool(out: &mut String, tool: &ToolReport, options: &RenderOptions) {
+    let _ = writeln!(
+        out,
+        "\n### {} ({})\n",
+        escape(&tool.title()),
+        tool.status.label()
+    );
+
+    if !tool.findings.is_empty() {
+        for finding in tool.findings.iter().take(options.max_findings) {
+            let _ = writeln!(out, "- {}", finding_line(finding, options));
+        }
+        let hidden = tool.findings.len().saturating_sub(options.max_findings);
+        if hidden > 0 {
+            let _ = writeln!(out, "- ...and {} more", hidden);
+        }
+    }
+
+    let output = tool.output.trim_end();
+    if output.is_empty() || tool.status == ToolStatus::Passed {
+        return;
+    }
+    if !tool.findings.is_empty() {
+        let _ = writeln!(out);
+    }
+    let lines = output.lines().count();
+    if lines > COLLAPSE_AFTER_LINES {
+        let _ = writeln!(
+            out,
+            "<details>\n<summary>Output ({} lines)</summary>\n",
+            lines
+        );
+        let _ = writeln!(out, "```\n{}\n```\n", fence_safe(output));
+        let _ = writeln!(out, "</details>");
+    } else {
+        let _ = writeln!(out, "```\n{}\n```", fence_safe(output));
+    }
+}
+
+fn finding_line(finding: &Finding, options: &RenderOptions) -> String {
+    let location = match finding.line {
+        Some(line) => format!("{}:{}", finding.file, line),
+        None => finding.file.clone(),
+    };
+    let target = match (&options.link_base, finding.line) {
+        (Some(base), Some(line)) => {
+            format!("{}/{}#L{}", base.trim_end_matches('/'), finding.file, line)
+        }
+        (Some(base), None) => format!("{}/{}", base.trim_end_matches('/'), finding.file),
+        (None, Some(line)) => format!("{}#L{}", finding.file, line),
+        (None, None) => finding.file.clone(),
+    };
+    let rule = finding
+        .rule
+        .as_ref()
+        .map(|rule| format!(" `{}`", rule.replace('`', "'")))
+        .unwrap_or_default();
+    format!(
+        "**{}** [{}]({}){} {}",
+        finding.severity,
+        escape(&location),
+        target.replace(' ', "%20"),
+        rule,
+        escape(&finding.message),
+    )
+}
+
+/// Escape characters that would break table cells or inline formatting
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '|' | '*' | '_' | '[' | ']' | '<' | '>' | '`') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Keep tool output from closing the surrounding code fence early
+fn fence_safe(output: &str) -> String {
+    output.replace("```", "` ` `")
+}
@@ -0,0 +1,209 @@
+pub mod checkstyle;
+pub mod github;
+pub mod gitlab;
+pub mod json;
+pub mod jsonl;
+pub mod markdown;
+pub mod text;
+
+use crate::baseline::BaselineSummary;
+use crate::coverage::CoverageReport;
+use crate::findings::{Finding, Severity};
+use crate::gates::GateOutcome;
+use crate::tools::Tool;
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize, Serializer};
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// The outcome of a single tool in a headless run
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ToolStatus {
+    /// The tool ran and exited with status zero
+    Passed,
+    /// The tool ran and exited with a non-zero status
+    Failed,
+    /// The tool could not be started
+    Skipped,
+}
+
+impl ToolStatus {
+    pub fn label(&self) -> &'static str {
+        match self {
+            ToolStatus::Passed => "PASS",
+            ToolStatus::Failed => "FAIL",
+            ToolStatus::Skipped => "SKIP",
+        }
+    }
+}
+
+/// Number of findings per severity
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct FindingCounts {
+    pub errors: usize,
+    pub warnings: usize,
+    pub infos: usize,
+}
+
+impl FindingCounts {
+    pub fn of<'a>(findings: impl IntoIterator<Item = &'a Finding>) -> Self {
+        let mut counts = Self::default();
+        for finding in findings {
+            match finding.severity {
+                Severity::Error => counts.errors += 1,
+                Severity::Warning => counts.warnings += 1,
+                Severity::Info => counts.infos += 1,
+            }
+        }
+        counts
+    }
+}
+
+/// Totals over a whole run
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct Summary {
+    pub passed: usize,
+    pub failed: usize,
+    pub skipped: usize,
+    #[serde(flatten)]
+    pub findings: FindingCounts,
+}
+
+/// Everything recorded about one tool in a headless run
+#[derive(Debug, Clone, Serialize)]
+pub struct ToolReport {
+    pub tool: Tool,
+    pub status: ToolStatus,
+    pub exit_code: Option<i32>,
+    #[serde(rename = "duration_ms", serialize_with = "serialize_millis")]
+    pub duration: Duration,
+    /// Combined stdout and stderr, or the spawn error for skipped tools
+    pub output: String,
+    pub findings: Vec<Finding>,
+    /// Whether the outcome was replayed from the result cache
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub cached: bool,
+    /// The version of the tool's program, if it reported one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+}
+
+impl ToolReport {
+    pub fn count(&self, severity: Severity) -> usize {
+        self.findings
+            .iter()
+            .filter(|f| f.severity == severity)
+            .count()
+    }
+
+    /// The tool's name with its version, e.g. "black 24.2.0"
+    pub fn title(&self) -> String {
+        match &self.version {
+            Some(version) => format!("{} {}", self.tool.name, version),
+            None => self.tool.name.clone(),
+        }
+    }
+}
+
+/// The result of running every detected tool once
+#[derive(Debug, Clone, Serialize)]
+pub struct RunReport {
+    pub directory: PathBuf,
+    pub tools: Vec<ToolReport>,
+    /// The quality gate verdict, once evaluated
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gate: Option<GateOutcome>,
+    /// Comparison with the baseline, when one was applied
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub baseline: Option<BaselineSummary>,
+    /// The git ref findings were restricted to changes since, if any
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub changed_since: Option<String>,
+    /// Line coverage of the test tools, when collected
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub coverage: Option<CoverageReport>,
+}
+
+impl RunReport {
+    pub fn count(&self, status: ToolStatus) -> usize {
+        self.tools.iter().filter(|t| t.status == status).count()
+    }
+
+    /// Whether the run passed: the quality gate verdict when it has been
+    /// evaluated, otherwise whether every tool that ran succeeded
+    pub fn passed(&self) -> bool {
+        match &self.gate {
+            Some(gate) => gate.passed,
+            None => self.count(ToolStatus::Failed) == 0,
+        }
+    }
+
+    pub fn summary(&self) -> Summary {
+        Summary {
+            passed: self.count(ToolStatus::Passed),
+            failed: self.count(ToolStatus::Failed),
+            skipped: self.count(ToolStatus::Skipped),
+            findings: FindingCounts::of(self.tools.iter().flat_map(|t| &t.findings)),
+        }
+    }
+}
+
+fn serialize_millis<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_u64(duration.as_millis() as u64)
+}
+
+/// Output formats for `codeguard run`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// Plain text summary for terminals
+    Text,
+    /// Markdown suitable for pull request comments
+    Markdown,
+    /// The final report as a single JSON document
+    Json,
+    /// A live stream of JSON events, one per line, ending with the
+    /// findings, summary and gate of the finished run
+    Jsonl,
+    /// GitLab Code Quality report (JSON array of issues)
+    GitlabCodequality,
+    /// Checkstyle XML report
+    Checkstyle,
+    /// GitHub Actions workflow commands (annotations and log groups)
+    Github,
+}
+
+impl OutputFormat {
+    /// The format used when none is requested: GitHub annotations inside
+    /// GitHub Actions, plain text everywhere else
+    pub fn detect() -> Self {
+        if std::env::var("GITHUB_ACTIONS").is_ok_and(|value| value == "true") {
+            OutputFormat::Github
+        } else {
+            OutputFormat::Text
+        }
+    }
+}
+
+/// Options shared by the report renderers
+#[derive(Debug, Clone)]
+pub struct RenderOptions {
+    /// Maximum number of findings listed per tool
+    pub max_findings: usize,
+    /// Prefix for file links (e.g. a repository blob URL); links stay
+    /// relative to the repository when unset
+    pub link_base: Option<String>,
+}
+
+/// Render a finished run in the requested format
+pub fn render(report: &RunReport, format: OutputFormat, options: &RenderOptions) -> String {
+    match format {
+        OutputFormat::Text => text::render(report, options),
+        OutputFormat::Markdown => markdown::render(report, options),
+        OutputFormat::Json => json::render(report),
+        OutputFormat::GitlabCodequality => gitlab::render(report),
+        OutputFormat::Checkstyle => checkstyle::render(report),
+        OutputFormat::Github => github::render(report),
+        OutputFormat::Jsonl => jsonl::render(report),
+    }
+}
@@ -0,0 +1,102 @@
+use super::{RenderOptions, RunReport, ToolStatus};
+use crate::findings::Severity;
+use std::fmt::Write;
+
+/// Render a plain text summary for terminal use
+pub fn render(report: &RunReport, options: &RenderOptions) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "Results for {}\n", report.directory.display());
+
+    for tool in &report.tools {
+        let _ = writeln!(
+            out,
+            "[{}] {} ({}{}{} errors, {} warnings, {:.1}s)",
+            tool.status.label(),
+            tool.title(),
+            if tool.cached { "cached, " } else { "" },
+            tool.exit_code
+                .filter(|code| *code != 0)
+                .map(|code| format!("exit {}, ", code))
+                .unwrap_or_default(),
+            tool.count(Severity::Error),
+            tool.count(Severity::Warning),
+            tool.duration.as_secs_f64(),
+        );
+        for finding in tool.findings.iter().take(options.max_findings) {
+            let location = match (finding.line, finding.column) {
+                (Some(line), Some(column)) => format!("{}:{}:{}", finding.file, line, column),
+                (Some(line), None) => format!("{}:{}", finding.file, line),
+                _ => finding.file.clone(),
+            };
+            let _ = writeln!(
+                out,
+                "    {}: {}: {}",
+                location, finding.severity, finding.message
+            );
+        }
+        if tool.status == ToolStatus::Skipped {
+            let _ = writeln!(out, "    {}", tool.output.trim());
+        }
+    }
+
+    if let Some(coverage) = &report.coverage {
+        let _ = writeln!(
+            out,
+            "\nCoverage: {:.1}% of {} lines{}",
+            coverage.lines.percent().unwrap_or(0.0),
+            coverage.lines.total,
+            coverage
+                .changed_lines
+                .map(|changed| format!(
+                    " ({:.1}% of {} changed lines)",
+                    changed.percent().unwrap_or(100.0),
+                    changed.total
+                ))
+                .unwrap_or_default(),
+        );
+        for file in coverage.least_covered().into_iter().take(options.max_findings) {
+            let _ = writeln!(
+                out,
+                "    {}: {:.1}%, uncovered {}",
+                file.file,
+                file.lines.percent().unwrap_or(0.0),
+                file.ranges()
+            );
+        }
+    }
+
+    let _ = writeln!(
+        out,
+        "\n{} passed, {} failed, {} skipped",
+        report.count(ToolStatus::Passed),
+        report.count(ToolStatus::Failed),
+        report.count(ToolStatus::Skipped),
+    );
+    if let Some(base) = &report.changed_since {
+        let _ = writeln!(out, "Only changes since {}", base);
+    }
+    if let Some(baseline) = &report.baseline {
+        let _ = writeln!(
+            out,
+            "Baseline: {} new, {} known, {} fixed",
+            baseline.new, baseline.baselined, baseline.fixed
+        );
+    }
+    if let Some(gate) = &report.gate {
+        if gate.passed {
+            let _ = writeln!(out, "Quality gate: passed");
+        } else {
+            let _ = writeln!(out, "Quality gate: failed");
+            for violation in &gate.violations {
+                let _ = writeln!(out, "    {}", violation);
+            }
+        }
+        for tool in &gate.advisory_failures {
+            let _ = writeln!(out, "    {} failed (advisory)", tool);
+        }
+        for violation in &gate.advisory_violations {
+            let _ = writeln!(out, "    {} (advisory)", violation);
+        }
+    }
+    out
+}
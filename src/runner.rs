@@ -0,0 +1,250 @@
+use crate::cache::{CachedResult, ResultCache};
+use crate::findings::{parse_findings, Finding};
+use crate::gates::GateOutcome;
+use crate::report::{FindingCounts, RunReport, Summary, ToolReport, ToolStatus};
+use crate::tools::availability::{self, Availability};
+use crate::tools::{OutputStream, Tool, ToolRunner};
+use serde::Serialize;
+use std::path::Path;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+/// Progress of a run, consumed by the TUI and by `--format jsonl`
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum RunEvent {
+    ToolStarted {
+        tool: String,
+        category: String,
+        command: String,
+        args: Vec<String>,
+    },
+    OutputLine {
+        tool: String,
+        stream: OutputStream,
+        line: String,
+    },
+    /// A finding; `--format jsonl` sends these once the run is filtered
+    Diagnostic(Finding),
+    ToolFinished {
+        tool: String,
+        status: ToolStatus,
+        exit_code: Option<i32>,
+        duration_ms: u64,
+        #[serde(flatten)]
+        findings: FindingCounts,
+        #[serde(skip_serializing_if = "std::ops::Not::not")]
+        cached: bool,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        version: Option<String>,
+    },
+    Summary(Summary),
+    /// The quality gate's verdict, last in `--format jsonl`
+    Gate(GateOutcome),
+}
+
+/// Run a single tool, reporting progress on `events`, and classify its
+/// outcome. A tool whose program is not installed is skipped with a hint on
+/// how to install it. With a `cache`, an outcome stored for the tool's
+/// current inputs is replayed instead of running the tool, and a fresh
+/// outcome is stored.
+pub async fn run_tool(
+    tool: &Tool,
+    directory: &Path,
+    cache: Option<&ResultCache>,
+    events: &mpsc::Sender<RunEvent>,
+) -> ToolReport {
+    let _ = events
+        .send(RunEvent::ToolStarted {
+            tool: tool.name.clone(),
+            category: tool.category.clone(),
+            command: tool.command.clone(),
+            args: tool.args.clone(),
+        })
+        .await;
+
+    let availability = availability::check(tool, directory).await;
+    let key = match (cache, &availability) {
+        (Some(cache), Availability::Installed { banner, .. }) => cache.key(tool, banner),
+        _ => None,
+    };
+    let cached = cache.zip(key.as_deref()).and_then(|(cache, key)| cache.load(key));
+    let (mut report, lines) = match (cached, availability.problem()) {
+        (_, Some(problem)) => {
+            let _ = events
+                .send(RunEvent::OutputLine {
+                    tool: tool.name.clone(),
+                    stream: OutputStream::Stderr,
+                    line: problem.clone(),
+                })
+                .await;
+            let report = ToolReport {
+                tool: tool.clone(),
+                status: ToolStatus::Skipped,
+                exit_code: None,
+                duration: Duration::ZERO,
+                output: problem,
+                findings: Vec::new(),
+                cached: false,
+                version: None,
+            };
+            (report, Vec::new())
+        }
+        (Some(cached), None) => {
+            for (stream, line) in &cached.lines {
+                let _ = events
+                    .send(RunEvent::OutputLine {
+                        tool: tool.name.clone(),
+                        stream: *stream,
+                        line: line.clone(),
+                    })
+                    .await;
+            }
+            let report = ToolReport {
+                tool: tool.clone(),
+                status: cached.status,
+                exit_code: cached.exit_code,
+                duration: Duration::from_millis(cached.duration_ms),
+                output: cached.output,
+                findings: cached.findings,
+                cached: true,
+                version: None,
+            };
+            (report, Vec::new())
+        }
+        (None, None) => execute(tool, directory, events).await,
+    };
+    report.version = availability.version().map(str::to_string);
+
+    if let (Some(cache), Some(key), false) = (cache, &key, report.cached) {
+        // A tool that could not start says nothing about its inputs
+        if report.status != ToolStatus::Skipped {
+            cache.store(
+                key,
+                &CachedResult {
+                    status: report.status,
+                    exit_code: report.exit_code,
+                    duration_ms: report.duration.as_millis() as u64,
+                    lines,
+                    output: report.output.clone(),
+                    findings: report.findings.clone(),
+                },
+            );
+        }
+    }
+
+    for finding in &report.findings {
+        let _ = events.send(RunEvent::Diagnostic(finding.clone())).await;
+    }
+    let _ = events
+        .send(RunEvent::ToolFinished {
+            tool: tool.name.clone(),
+            status: report.status,
+            exit_code: report.exit_code,
+            duration_ms: report.duration.as_millis() as u64,
+            findings: FindingCounts::of(&report.findings),
+            cached: report.cached,
+            version: report.version.clone(),
+        })
+        .await;
+
+    report
+}
+
+/// Run `tool`, forwarding its output on `events`; returns the report and the
+/// output lines as read
+async fn execute(
+    tool: &Tool,
+    directory: &Path,
+    events: &mpsc::Sender<RunEvent>,
+) -> (ToolReport, Vec<(OutputStream, String)>) {
+    let (lines, mut line_receiver) = mpsc::channel::<(OutputStream, String)>(100);
+    let forward = {
+        let events = events.clone();
+        let name = tool.name.clone();
+        tokio::spawn(async move {
+            let mut read = Vec::new();
+            while let Some((stream, line)) = line_receiver.recv().await {
+                let _ = events
+                    .send(RunEvent::OutputLine {
+                        tool: name.clone(),
+                        stream,
+                        line: line.clone(),
+                    })
+                    .await;
+                read.push((stream, line));
+            }
+            read
+        })
+    };
+    let working_dir = tool.working_dir(directory);
+    let result = tool.run_streaming(&working_dir, lines).await;
+    let read = forward.await.unwrap_or_default();
+
+    let report = match result {
+        Ok(output) => {
+            let text = output.combined();
+            ToolReport {
+                tool: tool.clone(),
+                status: if output.success() {
+                    ToolStatus::Passed
+                } else {
+                    ToolStatus::Failed
+                },
+                exit_code: output.exit_code,
+                duration: output.duration,
+                findings: project_findings(tool, parse_findings(&tool.name, &text, &working_dir)),
+                output: text,
+                cached: false,
+                version: None,
+            }
+        }
+        Err(err) => ToolReport {
+            tool: tool.clone(),
+            status: ToolStatus::Skipped,
+            exit_code: None,
+            duration: Duration::ZERO,
+            output: format!("Could not start `{}`: {}", tool.command, err),
+            findings: Vec::new(),
+            cached: false,
+            version: None,
+        },
+    };
+    (report, read)
+}
+
+/// Express the paths of `findings`, which tools report relative to their
+/// project, relative to the analyzed directory
+fn project_findings(tool: &Tool, mut findings: Vec<Finding>) -> Vec<Finding> {
+    if !tool.project.is_empty() {
+        for finding in &mut findings {
+            if Path::new(&finding.file).is_relative() {
+                finding.file = format!("{}/{}", tool.project, finding.file);
+            }
+        }
+    }
+    findings
+}
+
+/// Run every tool in turn, reporting progress on `events`, and collect a report
+pub async fn run_tools(
+    tools: &[Tool],
+    directory: &Path,
+    cache: Option<&ResultCache>,
+    events: &mpsc::Sender<RunEvent>,
+) -> RunReport {
+    let mut reports = Vec::with_capacity(tools.len());
+    for tool in tools {
+        reports.push(run_tool(tool, directory, cache, events).await);
+    }
+    let report = RunReport {
+        directory: directory.to_path_buf(),
+        tools: reports,
+        gate: None,
+        baseline: None,
+        changed_since: None,
+        coverage: None,
+    };
+    let _ = events.send(RunEvent::Summary(report.summary())).await;
+    report
+}
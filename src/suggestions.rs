@@ -0,0 +1,298 @@
+use crate::findings::Finding;
+use crate::fix::{FileChange, FixPreview};
+use crate::tools::Tool;
+use anyhow::{Context, Result};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::process::Stdio;
+
+/// A replacement of a byte range in one file
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Replacement {
+    /// File relative to the analyzed directory
+    file: String,
+    start: usize,
+    end: usize,
+    text: String,
+}
+
+/// A compiler suggestion rustc or clippy marked `MachineApplicable`, i.e.
+/// safe to apply without review of its meaning
+#[derive(Debug, Clone)]
+pub struct Suggestion {
+    /// The lint or error code, e.g. "clippy::needless_return"
+    pub code: Option<String>,
+    /// File of the diagnostic, relative to the analyzed directory
+    pub file: String,
+    pub line: u32,
+    pub message: String,
+    replacements: Vec<Replacement>,
+}
+
+/// Whether `tool` is a Cargo invocation whose suggestions can be collected
+pub fn supports(tool: &Tool) -> bool {
+    tool.command == "cargo" && tool.args.first().is_some_and(|arg| arg == "clippy")
+}
+
+/// Run `tool` (see [`supports`]) with JSON output and collect its
+/// machine-applicable suggestions
+pub async fn collect(tool: &Tool, directory: &Path) -> Result<Vec<Suggestion>> {
+    let mut args = tool.args.clone();
+    args.insert(1, "--message-format=json".to_string());
+    let output = tokio::process::Command::new(&tool.command)
+        .args(&args)
+        .current_dir(tool.working_dir(directory))
+        .stdin(Stdio::null())
+        .output()
+        .await
+        .with_context(|| format!("Could not start `{}`", tool.command))?;
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    Ok(stdout
+        .lines()
+        .filter_map(|line| serde_json::from_str::<Value>(line).ok())
+        .filter(|message| message["reason"] == "compiler-message")
+        .filter_map(|message| parse_diagnostic(&message["message"], &tool.project))
+        .collect())
+}
+
+/// A suggestion from one diagnostic, if any of its spans or those of its
+/// children carry a machine-applicable replacement
+fn parse_diagnostic(diagnostic: &Value, project: &str) -> Option<Suggestion> {
+    let relative = |file: &str| match project {
+        "" => file.to_string(),
+        project => format!("{}/{}", project, file),
+    };
+    let spans = |value: &Value| value["spans"].as_array().cloned().unwrap_or_default();
+
+    let primary = spans(diagnostic)
+        .into_iter()
+        .find(|span| span["is_primary"] == true)?;
+    let children = diagnostic["children"].as_array().cloned().unwrap_or_default();
+    let replacements: Vec<Replacement> = spans(diagnostic)
+        .iter()
+        .chain(children.iter().flat_map(spans).collect::<Vec<_>>().iter())
+        .filter(|span| span["suggestion_applicability"] == "MachineApplicable")
+        .filter_map(|span| {
+            let file = span["file_name"].as_str()?;
+            // Suggestions for code outside the project (e.g. in macros from
+            // dependencies) are not ours to apply
+            if Path::new(file).is_absolute() {
+                return None;
+            }
+            Some(Replacement {
+                file: relative(file),
+                start: span["byte_start"].as_u64()? as usize,
+                end: span["byte_end"].as_u64()? as usize,
+                text: span["suggested_replacement"].as_str()?.to_string(),
+            })
+        })
+        .collect();
+    if replacements.is_empty() {
+        return None;
+    }
+
+    Some(Suggestion {
+        code: diagnostic["code"]["code"].as_str().map(str::to_string),
+        file: relative(primary["file_name"].as_str()?),
+        line: primary["line_start"].as_u64()? as u32,
+        message: diagnostic["message"].as_str()?.to_string(),
+        replacements,
+    })
+}
+
+impl Suggestion {
+    /// Whether this suggestion fixes `finding`
+    pub fn matches(&self, finding: &Finding) -> bool {
+        self.file == finding.file
+            && Some(self.line) == finding.line
+            && (finding.rule.is_none() || finding.rule == self.code)
+    }
+}
+
+/// The changes `suggestions` make to the files in `directory`, for review
+/// before they are applied.
+///
+/// Suggestions are taken in order; one whose edits overlap an edit already
+/// taken is skipped and counted in the preview's note. Duplicates (the same
+/// diagnostic reported for several targets) are dropped silently.
+pub fn preview(tool: &Tool, directory: &Path, suggestions: &[Suggestion]) -> Result<FixPreview> {
+    let mut accepted: Vec<&Suggestion> = Vec::new();
+    let mut overlapping = 0;
+    for suggestion in suggestions {
+        if accepted
+            .iter()
+            .any(|taken| taken.replacements == suggestion.replacements)
+        {
+            continue;
+        }
+        let overlaps = suggestion.replacements.iter().any(|new| {
+            accepted.iter().flat_map(|taken| &taken.replacements).any(|old| {
+                old.file == new.file
+                    && old.start < new.end.max(new.start + 1)
+                    && new.start < old.end.max(old.start + 1)
+            })
+        });
+        if overlaps {
+            overlapping += 1;
+        } else {
+            accepted.push(suggestion);
+        }
+    }
+
+    let mut by_file: BTreeMap<&str, Vec<&Replacement>> = BTreeMap::new();
+    for replacement in accepted.iter().flat_map(|s| &s.replacements) {
+        by_file.entry(&replacement.file).or_default().push(replacement);
+    }
+    let mut changes = Vec::new();
+    for (file, mut replacements) in by_file {
+        let before = std::fs::read(directory.join(file))
+            .with_context(|| format!("Could not read {}", file))?;
+        let mut after = before.clone();
+        // Back to front, so earlier offsets stay valid
+        replacements.sort_by_key(|replacement| std::cmp::Reverse(replacement.start));
+        for replacement in replacements {
+            if replacement.end > after.len() || replacement.start > replacement.end {
+                anyhow::bail!("{} changed since the suggestions were made", file);
+            }
+            after.splice(replacement.start..replacement.end, replacement.text.bytes());
+        }
+        changes.push(FileChange {
+            path: file.to_string(),
+            before: Some(before),
+            after: Some(after),
+        });
+    }
+
+    let note = (overlapping > 0).then(|| {
+        format!(
+            "{} suggestions skipped because they overlap another edit; apply again after rerunning",
+            overlapping
+        )
+    });
+    Ok(FixPreview {
+        tool: tool.name.clone(),
+        changes,
+        note,
+        resolves: accepted
+            .iter()
+            .map(|suggestion| (suggestion.file.clone(), suggestion.message.clone()))
+            .collect(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const SOURCE: &str = "fn f() -> u32 {\n    return 1;\n}\n";
+
+    /// A clippy diagnostic whose child suggests `text` for `start..end` of
+    /// src/lib.rs
+    fn diagnostic(code: &str, start: usize, end: usize, text: &str) -> Value {
+        json!({
+            "message": format!("{} lint", code),
+            "code": { "code": code },
+            "spans": [{
+                "file_name": "src/lib.rs",
+                "byte_start": start,
+                "byte_end": end,
+                "line_start": 2,
+                "is_primary": true,
+                "suggestion_applicability": null,
+                "suggested_replacement": null,
+            }],
+            "children": [{
+                "spans": [{
+                    "file_name": "src/lib.rs",
+                    "byte_start": start,
+                    "byte_end": end,
+                    "line_start": 2,
+                    "is_primary": true,
+                    "suggestion_applicability": "MachineApplicable",
+                    "suggested_replacement": text,
+                }],
+            }],
+        })
+    }
+
+    fn suggestion(code: &str, start: usize, end: usize, text: &str) -> Suggestion {
+        parse_diagnostic(&diagnostic(code, start, end, text), "crate").unwrap()
+    }
+
+    fn preview_of(suggestions: &[Suggestion]) -> FixPreview {
+        let directory = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(directory.path().join("crate/src")).unwrap();
+        std::fs::write(directory.path().join("crate/src/lib.rs"), SOURCE).unwrap();
+        let tool = Tool {
+            name: "clippy".to_string(),
+            command: "cargo".to_string(),
+            ..Default::default()
+        };
+        preview(&tool, directory.path(), suggestions).unwrap()
+    }
+
+    fn after(preview: &FixPreview) -> String {
+        String::from_utf8(preview.changes[0].after.clone().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn parses_machine_applicable_suggestions() {
+        let parsed = suggestion("clippy::needless_return", 20, 29, "1");
+        assert_eq!(parsed.code.as_deref(), Some("clippy::needless_return"));
+        assert_eq!((parsed.file.as_str(), parsed.line), ("crate/src/lib.rs", 2));
+        assert_eq!(parsed.replacements.len(), 1);
+        assert_eq!(parsed.replacements[0].file, "crate/src/lib.rs");
+
+        let mut maybe = diagnostic("clippy::x", 20, 29, "1");
+        maybe["children"][0]["spans"][0]["suggestion_applicability"] = json!("MaybeIncorrect");
+        assert!(parse_diagnostic(&maybe, "").is_none());
+    }
+
+    #[test]
+    fn applies_disjoint_suggestions() {
+        let preview = preview_of(&[
+            suggestion("clippy::needless_return", 20, 29, "1"),
+            suggestion("clippy::rename", 3, 4, "g"),
+        ]);
+        assert_eq!(after(&preview), "fn g() -> u32 {\n    1\n}\n");
+        assert_eq!(preview.note, None);
+        assert_eq!(preview.resolves.len(), 2);
+    }
+
+    #[test]
+    fn skips_overlapping_and_duplicate_suggestions() {
+        let preview = preview_of(&[
+            suggestion("clippy::needless_return", 20, 29, "1"),
+            // Reported again for another target
+            suggestion("clippy::needless_return", 20, 29, "1"),
+            suggestion("clippy::other", 27, 28, "2"),
+            // Insertions at the same point overlap too
+            suggestion("clippy::insert", 15, 15, " // a"),
+            suggestion("clippy::insert_again", 15, 15, " // b"),
+        ]);
+        assert_eq!(after(&preview), "fn f() -> u32 { // a\n    1\n}\n");
+        assert_eq!(
+            preview.note.as_deref(),
+            Some("2 suggestions skipped because they overlap another edit; apply again after rerunning")
+        );
+        assert_eq!(
+            preview.resolves,
+            [
+                ("crate/src/lib.rs".to_string(), "clippy::needless_return lint".to_string()),
+                ("crate/src/lib.rs".to_string(), "clippy::insert lint".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn refuses_suggestions_past_the_end_of_the_file() {
+        let directory = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(directory.path().join("crate/src")).unwrap();
+        std::fs::write(directory.path().join("crate/src/lib.rs"), "fn f() {}\n").unwrap();
+        let result = preview(&Tool::default(), directory.path(), &[suggestion("x", 20, 29, "1")]);
+        assert!(result.is_err());
+    }
+}
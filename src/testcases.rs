@@ -0,0 +1,803 @@
+use crate::tools::Tool;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// The test runners whose output lists individual tests
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TestRunner {
+    Pytest,
+    Jest,
+    PhpUnit,
+    Cargo,
+}
+
+impl TestRunner {
+    /// The runner of a detected test tool, recognized by its name without
+    /// the project prefix
+    pub fn of(tool: &Tool) -> Option<Self> {
+        match tool.name.rsplit('/').next()? {
+            "pytest" => Some(TestRunner::Pytest),
+            "Jest" => Some(TestRunner::Jest),
+            "phpunit" => Some(TestRunner::PhpUnit),
+            "cargo-test" => Some(TestRunner::Cargo),
+            _ => None,
+        }
+    }
+}
+
+/// The outcome of one test in one run
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TestOutcome {
+    Passed,
+    Failed,
+    Skipped,
+}
+
+impl TestOutcome {
+    pub fn label(&self) -> &'static str {
+        match self {
+            TestOutcome::Passed => "passed",
+            TestOutcome::Failed => "failed",
+            TestOutcome::Skipped => "skipped",
+        }
+    }
+
+    pub fn parse(label: &str) -> Option<Self> {
+        match label {
+            "passed" => Some(TestOutcome::Passed),
+            "failed" => Some(TestOutcome::Failed),
+            "skipped" => Some(TestOutcome::Skipped),
+            _ => None,
+        }
+    }
+}
+
+/// A test and its outcome, named the way its runner names it (e.g.
+/// "tests/test_api.py::test_login", "api::tests::login")
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TestCase {
+    pub name: String,
+    pub outcome: TestOutcome,
+}
+
+/// The tests `runner` reported in `output`, in the order they appeared. A
+/// test reported more than once keeps its worst outcome.
+pub fn parse_tests(runner: TestRunner, output: &str) -> Vec<TestCase> {
+    let cases = match runner {
+        TestRunner::Pytest => parse_pytest(output),
+        TestRunner::Jest => parse_jest(output),
+        TestRunner::PhpUnit => parse_phpunit(output),
+        TestRunner::Cargo => parse_cargo(output),
+    };
+    let mut order = Vec::new();
+    let mut outcomes: BTreeMap<String, TestOutcome> = BTreeMap::new();
+    for (name, outcome) in cases {
+        match outcomes.get_mut(&name) {
+            Some(existing) => {
+                if outcome == TestOutcome::Failed {
+                    *existing = outcome;
+                }
+            }
+            None => {
+                outcomes.insert(name.clone(), outcome);
+                order.push(name);
+            }
+        }
+    }
+    order
+        .into_iter()
+        .map(|name| TestCase {
+            outcome: outcomes[&name],
+            name,
+        })
+        .collect()
+}
+
+/// `pytest -v` lines such as "tests/test_a.py::test_b PASSED [ 50%]"
+fn parse_pytest(output: &str) -> Vec<(String, TestOutcome)> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut words = line.split_whitespace();
+            let name = words.next().filter(|name| name.contains("::"))?;
+            let outcome = match words.next()? {
+                "PASSED" | "XFAIL" => TestOutcome::Passed,
+                "FAILED" | "ERROR" | "XPASS" => TestOutcome::Failed,
+                "SKIPPED" => TestOutcome::Skipped,
+                _ => return None,
+            };
+            Some((name.to_string(), outcome))
+        })
+        .collect()
+}
+
+/// `cargo test` lines such as "test api::tests::login ... ok"
+fn parse_cargo(output: &str) -> Vec<(String, TestOutcome)> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let (name, result) = line.strip_prefix("test ")?.rsplit_once(" ... ")?;
+            // Ignored tests may give a reason, e.g. "ignored, needs a database"
+            let outcome = match result.split([' ', ',']).next()? {
+                "ok" => TestOutcome::Passed,
+                "FAILED" => TestOutcome::Failed,
+                "ignored" => TestOutcome::Skipped,
+                _ => return None,
+            };
+            Some((name.to_string(), outcome))
+        })
+        .collect()
+}
+
+/// Jest's per-file blocks: a "PASS"/"FAIL" line with the file, then (when
+/// verbose) one "✓"/"✕"/"○" line per test under indented describe names,
+/// and a "●" heading per failure. Tests are named "file › describe › test".
+fn parse_jest(output: &str) -> Vec<(String, TestOutcome)> {
+    let mut cases = Vec::new();
+    let mut file: Option<String> = None;
+    let mut describes: Vec<(usize, String)> = Vec::new();
+    let mut in_failures = false;
+    for line in output.lines() {
+        let trimmed = line.trim_start();
+        if let Some(path) = trimmed.strip_prefix("PASS ").or_else(|| trimmed.strip_prefix("FAIL ")) {
+            file = path.split_whitespace().next().map(str::to_string);
+            describes.clear();
+            in_failures = false;
+            continue;
+        }
+        let Some(file) = &file else {
+            continue;
+        };
+        if let Some(title) = trimmed.strip_prefix("● ") {
+            in_failures = true;
+            // Not tests: a file that did not load, and its logged output
+            if !["Test suite failed to run", "Console"].contains(&title.trim()) {
+                cases.push((format!("{} › {}", file, title.trim()), TestOutcome::Failed));
+            }
+            continue;
+        }
+        if in_failures || trimmed.is_empty() {
+            continue;
+        }
+        let indent = line.len() - trimmed.len();
+        describes.retain(|(level, _)| *level < indent);
+        let outcome = match trimmed.chars().next() {
+            Some('✓') | Some('√') => TestOutcome::Passed,
+            Some('✕') | Some('×') => TestOutcome::Failed,
+            Some('○') => TestOutcome::Skipped,
+            _ => {
+                // Summary lines ("Tests: 1 failed") end the block
+                if indent == 0 {
+                    in_failures = true;
+                } else {
+                    describes.push((indent, trimmed.to_string()));
+                }
+                continue;
+            }
+        };
+        let title = trimmed.chars().skip(1).collect::<String>();
+        let title = title.trim().trim_start_matches("skipped ");
+        // Drop the timing, e.g. "adds numbers (3 ms)"
+        let title = match title.rsplit_once(" (") {
+            Some((title, timing)) if timing.ends_with("ms)") || timing.ends_with(" s)") => title,
+            _ => title,
+        };
+        let mut name = file.clone();
+        for (_, describe) in &describes {
+            name.push_str(" › ");
+            name.push_str(describe);
+        }
+        name.push_str(" › ");
+        name.push_str(title);
+        cases.push((name, outcome));
+    }
+    cases
+}
+
+/// PHPUnit's numbered entries ("1) Tests\FooTest::testBar") under the
+/// "There was 1 failure:" style section headings. Passing tests are not
+/// listed.
+fn parse_phpunit(output: &str) -> Vec<(String, TestOutcome)> {
+    let mut cases = Vec::new();
+    let mut section = None;
+    for line in output.lines() {
+        if let Some(heading) = line.strip_prefix("There w") {
+            section = if heading.contains("failure") || heading.contains("error") {
+                Some(TestOutcome::Failed)
+            } else if heading.contains("skipped") || heading.contains("incomplete") {
+                Some(TestOutcome::Skipped)
+            } else {
+                None
+            };
+            continue;
+        }
+        let Some(outcome) = section else {
+            continue;
+        };
+        let Some((number, name)) = line.split_once(") ") else {
+            continue;
+        };
+        if !number.is_empty() && number.chars().all(|c| c.is_ascii_digit()) && name.contains("::") {
+            cases.push((name.trim().to_string(), outcome));
+        }
+    }
+    cases
+}
+
+/// `tool` restricted to the tests named `failed` (as [`parse_tests`] names
+/// them), or `None` if its runner cannot be restricted that way. pytest
+/// falls back to `--lf` (its own record of the last failures) when no names
+/// are known.
+pub fn rerun_failed(tool: &Tool, failed: &[String]) -> Option<Tool> {
+    if failed.is_empty() && TestRunner::of(tool)? == TestRunner::Pytest {
+        let mut args = tool.args.clone();
+        args.push("--lf".to_string());
+        return Some(Tool {
+            args,
+            file_scope: None,
+            ..tool.clone()
+        });
+    }
+    restricted_to(tool, failed)
+}
+
+/// `tool` restricted to the tests named `tests`, as [`parse_tests`] and
+/// [`parse_listing`] name them. Jest tests may be named by file alone, which
+/// runs the whole file. `None` if there are no tests or `tool` is not a
+/// test runner.
+pub fn restricted_to(tool: &Tool, tests: &[String]) -> Option<Tool> {
+    let runner = TestRunner::of(tool)?;
+    if tests.is_empty() {
+        return None;
+    }
+    let mut args = tool.args.clone();
+    match runner {
+        TestRunner::Pytest => args.extend(tests.iter().cloned()),
+        TestRunner::Jest => {
+            // npm only passes arguments after `--` on to the script
+            if tool.command == "npm" {
+                args.push("--".to_string());
+            }
+            let mut files: Vec<&str> = Vec::new();
+            let mut titles: Vec<String> = Vec::new();
+            let mut whole_files = false;
+            for name in tests {
+                let mut parts = name.split(" › ");
+                if let Some(file) = parts.next() {
+                    if !files.contains(&file) {
+                        files.push(file);
+                    }
+                }
+                // Jest matches the describe names and title joined by spaces
+                let title = parts.collect::<Vec<_>>().join(" ");
+                if title.is_empty() {
+                    whole_files = true;
+                } else {
+                    titles.push(escape_regex(&title));
+                }
+            }
+            if !whole_files {
+                args.push("-t".to_string());
+                args.push(format!("^({})$", titles.join("|")));
+            }
+            args.extend(files.iter().map(|file| escape_regex(file)));
+        }
+        TestRunner::PhpUnit => {
+            let methods: Vec<String> = tests
+                .iter()
+                .map(|name| {
+                    // "Tests\FooTest::testBar with data set #1" runs all data sets
+                    let method = name.split(" with data set").next().unwrap_or(name);
+                    escape_regex(method)
+                })
+                .collect();
+            args.push("--filter".to_string());
+            args.push(format!("/^({})( with data set .*)?$/", methods.join("|")));
+        }
+        TestRunner::Cargo => {
+            if !args.iter().any(|arg| arg == "--") {
+                args.push("--".to_string());
+            }
+            args.push("--exact".to_string());
+            args.extend(tests.iter().cloned());
+        }
+    }
+    Some(Tool {
+        args,
+        file_scope: None,
+        ..tool.clone()
+    })
+}
+
+/// `tool` changed to list its tests without running them: `pytest
+/// --collect-only -q`, `jest --listTests` (which lists files only),
+/// `phpunit --list-tests` and `cargo test -- --list`
+pub fn listing(tool: &Tool) -> Option<Tool> {
+    let runner = TestRunner::of(tool)?;
+    let mut args = tool.args.clone();
+    match runner {
+        TestRunner::Pytest => {
+            args.retain(|arg| arg != "-v");
+            args.extend(["--collect-only".to_string(), "-q".to_string()]);
+        }
+        TestRunner::Jest => {
+            if tool.command == "npm" {
+                args.push("--".to_string());
+            }
+            args.push("--listTests".to_string());
+        }
+        TestRunner::PhpUnit => args.push("--list-tests".to_string()),
+        TestRunner::Cargo => {
+            if !args.iter().any(|arg| arg == "--") {
+                args.push("--".to_string());
+            }
+            args.push("--list".to_string());
+        }
+    }
+    Some(Tool {
+        args,
+        file_scope: None,
+        ..tool.clone()
+    })
+}
+
+/// The test names in the output of a [`listing`] run in `working_dir`,
+/// named as [`parse_tests`] names them (Jest tests by file)
+pub fn parse_listing(runner: TestRunner, output: &str, working_dir: &Path) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    for line in output.lines() {
+        let name = match runner {
+            // Node IDs, followed by a "3 tests collected" summary
+            TestRunner::Pytest if line.contains("::") && !line.starts_with(char::is_whitespace) => {
+                Some(line.trim_end().to_string())
+            }
+            TestRunner::Pytest => None,
+            // Absolute paths, after the package manager's own output
+            TestRunner::Jest => Path::new(line.trim())
+                .strip_prefix(working_dir)
+                .ok()
+                .filter(|_| Path::new(line.trim()).is_absolute())
+                .map(|path| path.to_string_lossy().to_string()),
+            TestRunner::PhpUnit => line
+                .trim_start()
+                .strip_prefix("- ")
+                .filter(|name| name.contains("::"))
+                .map(|name| name.trim().to_string()),
+            TestRunner::Cargo => line.strip_suffix(": test").map(str::to_string),
+        };
+        // Cargo lists the tests of every target binary separately
+        if let Some(name) = name.filter(|name| !names.contains(name)) {
+            names.push(name);
+        }
+    }
+    names
+}
+
+fn escape_regex(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if "\\.+*?()|[]{}^$/".contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cases(runner: TestRunner, output: &str) -> Vec<(String, TestOutcome)> {
+        parse_tests(runner, output)
+            .into_iter()
+            .map(|case| (case.name, case.outcome))
+            .collect()
+    }
+
+    fn tool(name: &str, command: &str, args: &[&str]) -> Tool {
+        Tool {
+            name: name.to_string(),
+            command: command.to_string(),
+            args: args.iter().map(|arg| arg.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    fn case(name: &str, outcome: TestOutcome) -> (String, TestOutcome) {
+        (name.to_string(), outcome)
+    }
+
+    const PYTEST: &str = "\
+============================= test session starts ==============================
+platform linux -- Python 3.12.3, pytest-8.3.2, pluggy-1.5.0 -- /home/dev/app/.venv/bin/python
+cachedir: .pytest_cache
+rootdir: /home/dev/app
+configfile: pyproject.toml
+collecting ... collected 6 items
+
+tests/test_api.py::test_login PASSED                                     [ 16%]
+tests/test_api.py::test_logout FAILED                                    [ 33%]
+tests/test_api.py::test_refresh SKIPPED (needs network)                  [ 50%]
+tests/test_db.py::test_query PASSED                                      [ 66%]
+tests/test_db.py::test_query ERROR                                       [ 66%]
+tests/test_models.py::TestUser::test_name[alice] PASSED                  [ 83%]
+tests/test_models.py::TestUser::test_name[bob] XFAIL (unicode)           [100%]
+
+==================================== ERRORS ====================================
+_________________________ ERROR at teardown of test_query _________________________
+
+    @pytest.fixture
+    def db():
+        yield connect()
+>       drop_tables()
+E       RuntimeError: tables in use
+
+tests/conftest.py:9: RuntimeError
+=================================== FAILURES ===================================
+_________________________________ test_logout __________________________________
+
+    def test_logout():
+>       assert logout() is None
+E       AssertionError: assert False is None
+E        +  where False = logout()
+
+tests/test_api.py:12: AssertionError
+=========================== short test summary info ============================
+FAILED tests/test_api.py::test_logout - AssertionError: assert False is None
+ERROR tests/test_db.py::test_query - RuntimeError: tables in use
+=========== 1 failed, 3 passed, 1 skipped, 1 xfailed, 1 error in 0.12s ===========
+";
+
+    #[test]
+    fn parses_pytest_verbose_output() {
+        assert_eq!(
+            cases(TestRunner::Pytest, PYTEST),
+            vec![
+                case("tests/test_api.py::test_login", TestOutcome::Passed),
+                case("tests/test_api.py::test_logout", TestOutcome::Failed),
+                case("tests/test_api.py::test_refresh", TestOutcome::Skipped),
+                // Passed, then errored in teardown
+                case("tests/test_db.py::test_query", TestOutcome::Failed),
+                case("tests/test_models.py::TestUser::test_name[alice]", TestOutcome::Passed),
+                case("tests/test_models.py::TestUser::test_name[bob]", TestOutcome::Passed),
+            ]
+        );
+    }
+
+    const JEST_VERBOSE: &str = "\
+ PASS  src/math.test.js
+  math
+    add
+      ✓ adds numbers (2 ms)
+      ✓ handles negatives
+    ○ skipped divides
+ FAIL  src/api.test.js
+  api
+    ✕ fetches users (5 ms)
+    ✓ handles errors (1 ms)
+
+  ● api › fetches users
+
+    expect(received).toBe(expected) // Object.is equality
+
+    Expected: 2
+    Received: 1
+
+      4 |   test('fetches users', async () => {
+      5 |     const users = await fetchUsers();
+    > 6 |     expect(users.length).toBe(2);
+        |                          ^
+      7 |   });
+
+      at Object.toBe (src/api.test.js:6:26)
+
+Test Suites: 1 failed, 1 passed, 2 total
+Tests:       1 failed, 1 skipped, 3 passed, 5 total
+Snapshots:   0 total
+Time:        0.512 s, estimated 1 s
+Ran all test suites.
+";
+
+    #[test]
+    fn parses_verbose_jest_output() {
+        assert_eq!(
+            cases(TestRunner::Jest, JEST_VERBOSE),
+            vec![
+                case("src/math.test.js › math › add › adds numbers", TestOutcome::Passed),
+                case("src/math.test.js › math › add › handles negatives", TestOutcome::Passed),
+                case("src/math.test.js › math › divides", TestOutcome::Skipped),
+                case("src/api.test.js › api › fetches users", TestOutcome::Failed),
+                case("src/api.test.js › api › handles errors", TestOutcome::Passed),
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_jest_output_with_windows_symbols() {
+        let output = "\
+ PASS  src\\math.test.js
+  math
+    √ adds numbers (2 ms)
+    × subtracts numbers (1 ms)
+";
+        assert_eq!(
+            cases(TestRunner::Jest, output),
+            vec![
+                case("src\\math.test.js › math › adds numbers", TestOutcome::Passed),
+                case("src\\math.test.js › math › subtracts numbers", TestOutcome::Failed),
+            ]
+        );
+    }
+
+    // Jest is only verbose for a single test file, unless configured to be
+    const JEST: &str = "\
+ PASS  src/math.test.js
+ FAIL  src/api.test.js
+  ● api › fetches users
+
+    expect(received).toBe(expected) // Object.is equality
+
+    Expected: 2
+    Received: 1
+
+      at Object.toBe (src/api.test.js:6:26)
+
+  ● Console
+
+    console.log
+      fetching /users
+
+      at log (src/api.js:3:11)
+
+ FAIL  src/broken.test.js
+  ● Test suite failed to run
+
+    Cannot find module './missing' from 'src/broken.test.js'
+
+Test Suites: 2 failed, 1 passed, 3 total
+Tests:       1 failed, 4 passed, 5 total
+Snapshots:   0 total
+Time:        0.734 s
+Ran all test suites.
+";
+
+    #[test]
+    fn parses_only_failures_from_jest_output_without_verbose() {
+        assert_eq!(
+            cases(TestRunner::Jest, JEST),
+            vec![
+                case("src/api.test.js › api › fetches users", TestOutcome::Failed),
+            ]
+        );
+    }
+
+    const PHPUNIT: &str = "\
+PHPUnit 10.5.20 by Sebastian Bergmann and contributors.
+
+Runtime:       PHP 8.3.6
+Configuration: /home/dev/app/phpunit.xml
+
+..FF.S                                                              6 / 6 (100%)
+
+Time: 00:00.012, Memory: 8.00 MB
+
+There were 2 failures:
+
+1) Tests\\CartTest::testTotal
+Failed asserting that 10 matches expected 12.
+
+/home/dev/app/tests/CartTest.php:21
+
+2) Tests\\CartTest::testTax with data set #1 (100, 8)
+Failed asserting that 7 matches expected 8.
+
+/home/dev/app/tests/CartTest.php:34
+
+--
+
+There was 1 skipped test:
+
+1) Tests\\CartTest::testDiscount
+Needs the pricing service
+
+/home/dev/app/tests/CartTest.php:40
+
+FAILURES!
+Tests: 6, Assertions: 5, Failures: 2, Skipped: 1.
+";
+
+    #[test]
+    fn parses_phpunit_failures_and_skips() {
+        assert_eq!(
+            cases(TestRunner::PhpUnit, PHPUNIT),
+            vec![
+                case("Tests\\CartTest::testTotal", TestOutcome::Failed),
+                case("Tests\\CartTest::testTax with data set #1 (100, 8)", TestOutcome::Failed),
+                case("Tests\\CartTest::testDiscount", TestOutcome::Skipped),
+            ]
+        );
+    }
+
+    const CARGO: &str = "\
+   Compiling ct v0.1.0 (/tmp/ct)
+    Finished `test` profile [unoptimized + debuginfo] target(s) in 0.26s
+     Running unittests src/lib.rs (target/debug/deps/ct-c4066ebb79ee0019)
+
+running 4 tests
+test tests::adds ... ok
+test tests::database ... ignored, needs a database
+test tests::overflows ... FAILED
+test tests::slow ... ignored
+
+failures:
+
+---- tests::overflows stdout ----
+
+thread 'tests::overflows' (20975) panicked at src/lib.rs:6:30:
+assertion `left == right` failed
+  left: 2
+ right: 3
+note: run with `RUST_BACKTRACE=1` environment variable to display a backtrace
+
+
+failures:
+    tests::overflows
+
+test result: FAILED. 1 passed; 1 failed; 2 ignored; 0 measured; 0 filtered out; finished in 0.01s
+
+error: test failed, to rerun pass `--lib`
+";
+
+    #[test]
+    fn parses_cargo_test_output() {
+        assert_eq!(
+            cases(TestRunner::Cargo, CARGO),
+            vec![
+                case("tests::adds", TestOutcome::Passed),
+                case("tests::database", TestOutcome::Skipped),
+                case("tests::overflows", TestOutcome::Failed),
+                case("tests::slow", TestOutcome::Skipped),
+            ]
+        );
+    }
+
+    #[test]
+    fn reruns_pytest_last_failures_without_names() {
+        let pytest = tool("pytest", ".venv/bin/pytest", &["-v"]);
+        let rerun = rerun_failed(&pytest, &[]).unwrap();
+        assert_eq!(rerun.args, ["-v", "--lf"]);
+
+        let failed = ["tests/test_api.py::test_logout".to_string()];
+        let rerun = rerun_failed(&pytest, &failed).unwrap();
+        assert_eq!(rerun.args, ["-v", "tests/test_api.py::test_logout"]);
+    }
+
+    #[test]
+    fn reruns_jest_tests_by_title_and_file() {
+        let jest = tool("web/Jest", "npm", &["test"]);
+        let failed = [
+            "src/api.test.js › api › fetches users".to_string(),
+            "src/math.test.js › math › add › adds (numbers)".to_string(),
+        ];
+        let rerun = rerun_failed(&jest, &failed).unwrap();
+        assert_eq!(
+            rerun.args,
+            [
+                "test",
+                "--",
+                "-t",
+                "^(api fetches users|math add adds \\(numbers\\))$",
+                "src\\/api\\.test\\.js",
+                "src\\/math\\.test\\.js",
+            ]
+        );
+
+        // A file alone runs the whole file
+        let listed = ["src/api.test.js".to_string(), "src/api.test.js › api › fetches users".to_string()];
+        let rerun = restricted_to(&tool("Jest", "node_modules/.bin/jest", &[]), &listed).unwrap();
+        assert_eq!(rerun.args, ["src\\/api\\.test\\.js"]);
+    }
+
+    #[test]
+    fn reruns_phpunit_tests_with_every_data_set() {
+        let phpunit = tool("phpunit", "vendor/bin/phpunit", &[]);
+        let failed = [
+            "Tests\\CartTest::testTotal".to_string(),
+            "Tests\\CartTest::testTax with data set #1 (100, 8)".to_string(),
+        ];
+        let rerun = rerun_failed(&phpunit, &failed).unwrap();
+        assert_eq!(
+            rerun.args,
+            [
+                "--filter",
+                "/^(Tests\\\\CartTest::testTotal|Tests\\\\CartTest::testTax)( with data set .*)?$/",
+            ]
+        );
+    }
+
+    #[test]
+    fn reruns_cargo_tests_exactly() {
+        let cargo = tool("cargo-test", "cargo", &["test"]);
+        let rerun = rerun_failed(&cargo, &["tests::overflows".to_string()]).unwrap();
+        assert_eq!(rerun.args, ["test", "--", "--exact", "tests::overflows"]);
+
+        assert!(rerun_failed(&cargo, &[]).is_none());
+        assert!(rerun_failed(&tool("flake8", "flake8", &[]), &["a".to_string()]).is_none());
+    }
+
+    #[test]
+    fn parses_pytest_listing() {
+        let output = "\
+tests/test_api.py::test_login
+tests/test_api.py::test_logout
+tests/test_models.py::TestUser::test_name[alice]
+
+3 tests collected in 0.01s
+";
+        assert_eq!(
+            parse_listing(TestRunner::Pytest, output, Path::new("/home/dev/app")),
+            [
+                "tests/test_api.py::test_login",
+                "tests/test_api.py::test_logout",
+                "tests/test_models.py::TestUser::test_name[alice]",
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_jest_listing_after_npm_output() {
+        let output = "
+> app@1.0.0 test
+> jest --listTests
+
+/home/dev/app/src/api.test.js
+/home/dev/app/src/math.test.js
+";
+        assert_eq!(
+            parse_listing(TestRunner::Jest, output, Path::new("/home/dev/app")),
+            ["src/api.test.js", "src/math.test.js"]
+        );
+    }
+
+    #[test]
+    fn parses_phpunit_listing() {
+        let output = "\
+PHPUnit 10.5.20 by Sebastian Bergmann and contributors.
+
+Available tests:
+ - Tests\\CartTest::testTotal
+ - Tests\\CartTest::testDiscount
+";
+        assert_eq!(
+            parse_listing(TestRunner::PhpUnit, output, Path::new("/home/dev/app")),
+            ["Tests\\CartTest::testTotal", "Tests\\CartTest::testDiscount"]
+        );
+    }
+
+    #[test]
+    fn parses_cargo_listing_once_per_test() {
+        let output = "\
+    Finished `test` profile [unoptimized + debuginfo] target(s) in 0.01s
+     Running unittests src/lib.rs (target/debug/deps/ct-c4066ebb79ee0019)
+tests::adds: test
+tests::database: test
+tests::overflows: test
+tests::slow: test
+
+4 tests, 0 benchmarks
+     Running unittests src/main.rs (target/debug/deps/ct-5b1e2f0c9a8d7e6f)
+tests::adds: test
+
+1 test, 0 benchmarks
+   Doc-tests ct
+0 tests, 0 benchmarks
+";
+        assert_eq!(
+            parse_listing(TestRunner::Cargo, output, Path::new("/tmp/ct")),
+            ["tests::adds", "tests::database", "tests::overflows", "tests::slow"]
+        );
+    }
+}
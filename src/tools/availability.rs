@@ -0,0 +1,331 @@
+use super::base::Tool;
+use crate::git::HOOK_ENV;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+
+/// How environment and package managers add a development dependency, for
+/// tools run with `<manager> run <tool>` or, under Yarn Plug'n'Play and with
+/// Bun, `yarn <tool>` and `bunx <tool>`
+const DEV_INSTALLS: [(&str, &str); 7] = [
+    ("uv", "uv add --dev"),
+    ("poetry", "poetry add --group dev"),
+    ("pdm", "pdm add --dev"),
+    ("hatch", "add to the dependencies of the Hatch environment:"),
+    ("pipenv", "pipenv install --dev"),
+    ("yarn", "yarn add --dev"),
+    ("bunx", "bun add --dev"),
+];
+
+/// How long a version probe may take before the tool counts as missing
+const PROBE_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// How to install the programs tools depend on, by executable name (or
+/// `cargo <subcommand>`)
+const INSTALL_HINTS: [(&str, &str); 27] = [
+    ("pytest", "pip install pytest"),
+    ("flake8", "pip install flake8"),
+    ("pylint", "pip install pylint"),
+    ("mypy", "pip install mypy"),
+    ("black", "pip install black"),
+    ("uv", "pipx install uv"),
+    ("poetry", "pipx install poetry"),
+    ("pdm", "pipx install pdm"),
+    ("hatch", "pipx install hatch"),
+    ("pipenv", "pipx install pipenv"),
+    ("eslint", "npm install --save-dev eslint"),
+    ("jest", "npm install --save-dev jest"),
+    ("tsc", "npm install --save-dev typescript"),
+    ("prettier", "npm install --save-dev prettier"),
+    ("npm", "install Node.js from https://nodejs.org"),
+    ("yarn", "corepack enable"),
+    ("pnpm", "corepack enable"),
+    ("bun", "install Bun from https://bun.sh"),
+    ("phpunit", "composer require --dev phpunit/phpunit"),
+    ("phpcs", "composer require --dev squizlabs/php_codesniffer"),
+    ("phpstan", "composer require --dev phpstan/phpstan"),
+    ("php-cs-fixer", "composer require --dev friendsofphp/php-cs-fixer"),
+    ("composer", "install Composer from https://getcomposer.org"),
+    ("cargo", "install Rust from https://rustup.rs"),
+    ("cargo clippy", "rustup component add clippy"),
+    ("cargo fmt", "rustup component add rustfmt"),
+    ("cargo audit", "cargo install cargo-audit"),
+];
+
+/// Whether a tool can run, as found by [`check`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "state", rename_all = "lowercase")]
+pub enum Availability {
+    Installed {
+        /// The version number, if the probe printed one
+        #[serde(skip_serializing_if = "Option::is_none")]
+        version: Option<String>,
+        /// The first line the probe printed, e.g. `black, 24.2.0 (compiled: yes)`
+        banner: String,
+    },
+    Missing {
+        /// What is missing, e.g. "`pytest` is not installed"
+        reason: String,
+        /// How to install it
+        hint: String,
+    },
+}
+
+impl Availability {
+    pub fn version(&self) -> Option<&str> {
+        match self {
+            Availability::Installed { version, .. } => version.as_deref(),
+            Availability::Missing { .. } => None,
+        }
+    }
+
+    /// "`pytest` is not installed (pip install pytest)", for missing tools
+    pub fn problem(&self) -> Option<String> {
+        match self {
+            Availability::Installed { .. } => None,
+            Availability::Missing { reason, hint } => Some(format!("{} ({})", reason, hint)),
+        }
+    }
+}
+
+/// Find the program `tool` depends on, on PATH or in its project, and ask
+/// it for its version
+pub async fn check(tool: &Tool, directory: &Path) -> Availability {
+    let (program, args) = match &tool.probe {
+        Some(probe) => (probe.command.as_str(), probe.args.clone()),
+        None => (tool.command.as_str(), vec!["--version".to_string()]),
+    };
+    let working_dir = tool.working_dir(directory);
+    let Some(path) = resolve(program, &[&working_dir, directory]) else {
+        return Availability::Missing {
+            reason: format!("`{}` is not installed", program),
+            hint: install_hint(program, &args),
+        };
+    };
+    // Past this point the program runs, so what is missing is what it runs
+    let missing = |reason: String| Availability::Missing {
+        reason,
+        hint: dev_install_hint(program, &args).unwrap_or_else(|| install_hint(program, &args)),
+    };
+
+    let mut command = tokio::process::Command::new(&path);
+    for variable in HOOK_ENV {
+        command.env_remove(variable);
+    }
+    let output = command
+        .args(&args)
+        .current_dir(&working_dir)
+        .stdin(Stdio::null())
+        .kill_on_drop(true)
+        .output();
+    let output = match tokio::time::timeout(PROBE_TIMEOUT, output).await {
+        Ok(Ok(output)) => output,
+        Ok(Err(err)) => return missing(format!("`{}` could not be started: {}", program, err)),
+        Err(_) => return missing(format!("`{} {}` did not answer", program, args.join(" "))),
+    };
+    // A cargo subcommand that is not installed makes cargo fail
+    if !output.status.success() {
+        return missing(format!("`{} {}` failed", program, args.join(" ")));
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let banner = stdout
+        .lines()
+        .chain(stderr.lines())
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .unwrap_or_default()
+        .to_string();
+    Availability::Installed {
+        version: version_number(&banner),
+        banner,
+    }
+}
+
+/// Where `program` is: a path with a `/` is looked up in each of
+/// `directories` in turn, as dependencies may be hoisted to the analyzed
+/// directory; a bare name is looked up on PATH
+fn resolve(program: &str, directories: &[&Path]) -> Option<PathBuf> {
+    if program.contains('/') {
+        return directories
+            .iter()
+            .flat_map(|directory| executables(directory.join(program)))
+            .find(|path| is_executable(path));
+    }
+    std::env::split_paths(&std::env::var_os("PATH")?)
+        .flat_map(|directory| executables(directory.join(program)))
+        .find(|path| is_executable(path))
+}
+
+/// The files that run as `path`
+#[cfg(unix)]
+fn executables(path: PathBuf) -> Vec<PathBuf> {
+    vec![path]
+}
+
+/// The files that run as `path`: itself, or with any extension in PATHEXT
+/// (e.g. `eslint.cmd` in `node_modules/.bin`)
+#[cfg(not(unix))]
+fn executables(path: PathBuf) -> Vec<PathBuf> {
+    let extensions = std::env::var("PATHEXT").unwrap_or_else(|_| ".COM;.EXE;.BAT;.CMD".to_string());
+    // Without an extension, e.g. the shell script npm puts next to `.cmd`
+    // shims, a file does not run
+    let mut paths = if path.extension().is_some() { vec![path.clone()] } else { Vec::new() };
+    paths.extend(
+        extensions
+            .split(';')
+            .filter(|extension| !extension.is_empty())
+            .map(|extension| {
+                let mut name = path.clone().into_os_string();
+                name.push(extension.to_ascii_lowercase());
+                PathBuf::from(name)
+            }),
+    );
+    paths
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    path.metadata()
+        .is_ok_and(|metadata| metadata.is_file() && metadata.permissions().mode() & 0o111 != 0)
+}
+
+/// Windows has no executable bit; [`executables`] only offers files whose
+/// extension makes them run
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
+}
+
+/// The first dotted number in `banner`, e.g. `24.2.0` in
+/// `black, 24.2.0 (compiled: yes)` or `8.57.0` in `v8.57.0`
+fn version_number(banner: &str) -> Option<String> {
+    banner
+        .split(|c: char| c.is_whitespace() || c == ',' || c == '(' || c == ')')
+        .map(|word| word.strip_prefix('v').unwrap_or(word))
+        .find(|word| word.starts_with(|c: char| c.is_ascii_digit()) && word.contains('.'))
+        .map(|word| word.trim_end_matches(|c: char| !c.is_ascii_alphanumeric()).to_string())
+}
+
+/// How to install the tool an environment or package manager runs, e.g.
+/// `poetry add --group dev pytest` for `poetry run pytest --version` or
+/// `yarn add --dev typescript` for `yarn tsc --version`
+fn dev_install_hint(program: &str, args: &[String]) -> Option<String> {
+    let (_, add) = DEV_INSTALLS.iter().find(|(manager, _)| *manager == program)?;
+    let tool = match args {
+        [run, tool, ..] if run == "run" => tool,
+        [tool, ..] if !tool.starts_with('-') => tool,
+        _ => return None,
+    };
+    // The npm package, e.g. `typescript` for `tsc`
+    let package = INSTALL_HINTS
+        .iter()
+        .find(|(program, _)| program == tool)
+        .and_then(|(_, hint)| hint.strip_prefix("npm install --save-dev "))
+        .unwrap_or(tool);
+    Some(format!("{} {}", add, package))
+}
+
+/// How to install `program`, looked up by its file name (and subcommand,
+/// for cargo). pip installs into a virtualenv go through its own `pip`.
+fn install_hint(program: &str, args: &[String]) -> String {
+    let name = Path::new(program)
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| program.to_string());
+    let key = match args.first() {
+        Some(subcommand) if name == "cargo" && !subcommand.starts_with('-') => {
+            format!("cargo {}", subcommand)
+        }
+        _ => name.clone(),
+    };
+    let hint = INSTALL_HINTS
+        .iter()
+        .find(|(program, _)| *program == key)
+        .map(|(_, hint)| hint.to_string())
+        .unwrap_or_else(|| format!("install `{}`", name));
+    match (Path::new(program).parent(), hint.strip_prefix("pip ")) {
+        (Some(bin), Some(rest)) if !bin.as_os_str().is_empty() => {
+            format!("{} {}", bin.join("pip").display(), rest)
+        }
+        _ => hint,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn finds_the_version_in_the_banner() {
+        for (banner, version) in [
+            ("black, 24.2.0 (compiled: yes)", Some("24.2.0")),
+            ("v8.57.0", Some("8.57.0")),
+            ("pytest 8.3.2", Some("8.3.2")),
+            ("cargo 1.80.0 (376290515 2024-07-16)", Some("1.80.0")),
+            ("mypy 1.11.1 (compiled: yes)", Some("1.11.1")),
+            ("Version 3.", Some("3")),
+            ("composer", None),
+        ] {
+            assert_eq!(version_number(banner).as_deref(), version, "{}", banner);
+        }
+    }
+
+    #[test]
+    fn suggests_how_to_install() {
+        assert_eq!(install_hint("pytest", &[]), "pip install pytest");
+        assert_eq!(install_hint(".venv/bin/pytest", &[]), ".venv/bin/pip install pytest");
+        assert_eq!(install_hint("node_modules/.bin/tsc", &[]), "npm install --save-dev typescript");
+        assert_eq!(install_hint("cargo", &args(&["clippy", "--version"])), "rustup component add clippy");
+        assert_eq!(install_hint("cargo", &args(&["--version"])), "install Rust from https://rustup.rs");
+        assert_eq!(install_hint("golangci-lint", &[]), "install `golangci-lint`");
+
+        assert_eq!(
+            dev_install_hint("poetry", &args(&["run", "pytest", "--version"])).as_deref(),
+            Some("poetry add --group dev pytest")
+        );
+        assert_eq!(
+            dev_install_hint("yarn", &args(&["tsc", "--version"])).as_deref(),
+            Some("yarn add --dev typescript")
+        );
+        assert_eq!(dev_install_hint("yarn", &args(&["--version"])), None);
+        assert_eq!(dev_install_hint("npm", &args(&["run", "lint"])), None);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn probes_project_local_programs() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let directory = tempfile::tempdir().unwrap();
+        let bin = directory.path().join("node_modules/.bin");
+        std::fs::create_dir_all(&bin).unwrap();
+        std::fs::write(bin.join("eslint"), "#!/bin/sh\necho v9.1.0\n").unwrap();
+        std::fs::set_permissions(bin.join("eslint"), std::fs::Permissions::from_mode(0o755)).unwrap();
+        std::fs::write(bin.join("jest"), "#!/bin/sh\nexit 1\n").unwrap();
+
+        let tool = |command: &str| Tool {
+            name: command.to_string(),
+            command: command.to_string(),
+            ..Default::default()
+        };
+        assert_eq!(
+            check(&tool("node_modules/.bin/eslint"), directory.path()).await,
+            Availability::Installed {
+                version: Some("9.1.0".to_string()),
+                banner: "v9.1.0".to_string(),
+            }
+        );
+        // Not executable
+        assert_eq!(
+            check(&tool("node_modules/.bin/jest"), directory.path()).await.problem().as_deref(),
+            Some("`node_modules/.bin/jest` is not installed (npm install --save-dev jest)")
+        );
+    }
+}
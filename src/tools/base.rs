@@ -1,10 +1,16 @@
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::{Duration, Instant};
 use anyhow::Result;
 use async_trait::async_trait;
+use globset::{GlobBuilder, GlobSetBuilder};
 use serde::{Serialize, Deserialize};
+use crate::git::HOOK_ENV;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, BufReader};
+use tokio::sync::mpsc;
 
 /// Represents a code analysis or testing tool
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Tool {
     /// The name of the tool
     pub name: String,
@@ -16,31 +22,480 @@ pub struct Tool {
     pub category: String,
     /// A description of what the tool does
     pub description: String,
+    /// How to run the tool on an explicit list of files, if it supports that
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file_scope: Option<FileScope>,
+    /// Globs, relative to the tool's project, of the files the tool
+    /// reads; watch mode reruns the tool when one of them changes
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub inputs: Vec<String>,
+    /// How to tell the tool which paths to skip, if it supports that
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exclude: Option<ExcludeFlag>,
+    /// The project the tool belongs to, relative to the analyzed directory;
+    /// empty for the analyzed directory itself
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub project: String,
+    /// How to run the tool so that it fixes what it reports, if it can
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fix: Option<FixCommand>,
+    /// How to check that the tool is installed and find its version, when
+    /// `command --version` would not tell (e.g. a package manager script)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub probe: Option<VersionProbe>,
+    /// Whether the tool only runs when picked, never with every tool (e.g.
+    /// a package.json script, which may build, serve or deploy)
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub on_demand: bool,
+    /// Whether the outcome also depends on a remote service, e.g. an
+    /// advisory database or a registry, so unchanged inputs do not mean an
+    /// unchanged outcome
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub online: bool,
+    /// Exit codes with which the tool reports findings and nothing else
+    /// (e.g. not a crash or a configuration error), so that dropping all of
+    /// its findings also explains its failure away
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub findings_exit_codes: Vec<i32>,
+}
+
+/// An invocation of a tool restricted to specific files
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileScope {
+    /// Extensions (without the dot) of the files the tool checks
+    pub extensions: Vec<String>,
+    /// The command to run; file paths are appended to `args`
+    pub command: String,
+    pub args: Vec<String>,
+}
+
+/// An invocation of a tool that rewrites files to fix its findings, run in
+/// the tool's working directory
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FixCommand {
+    pub command: String,
+    pub args: Vec<String>,
+}
+
+/// A `--version` style invocation of the program a tool depends on. A
+/// command containing a `/` is project-local, e.g. `node_modules/.bin/eslint`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VersionProbe {
+    pub command: String,
+    pub args: Vec<String>,
+}
+
+/// A command-line flag through which a tool accepts paths to skip
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExcludeFlag {
+    /// The flag, e.g. `--extend-exclude`; values are passed as `flag=value`
+    pub flag: String,
+    /// Whether all patterns go into one comma-separated value instead of one
+    /// flag per pattern
+    pub joined: bool,
+    /// How the tool reads the patterns
+    #[serde(default)]
+    pub syntax: PatternSyntax,
+}
+
+/// The pattern languages of exclusion flags
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PatternSyntax {
+    /// gitignore patterns, passed as they are
+    #[default]
+    Gitignore,
+    /// fnmatch globs matched against file and directory names, or against
+    /// paths if they contain a slash (flake8 `--extend-exclude`)
+    Name,
+    /// fnmatch globs matched against whole paths, in which `*` also matches
+    /// `/` (pytest `--ignore-glob`)
+    Path,
+    /// Regular expressions searched for in absolute paths (phpcs `--ignore`)
+    Regex,
+}
+
+impl PatternSyntax {
+    /// The gitignore `pattern`, relative to the analyzed directory, in this
+    /// syntax for a tool running in `project`. Patterns that do not
+    /// translate faithfully (negations, `**`, globs with a directory part)
+    /// are left out, since skipping too little is safer than skipping the
+    /// wrong files.
+    fn translate(self, pattern: &str, project: &str) -> Vec<String> {
+        match (self, Excluded::from_gitignore(pattern, project)) {
+            (PatternSyntax::Gitignore, _) => vec![pattern.to_string()],
+            (_, None) => Vec::new(),
+            (PatternSyntax::Name, Some(Excluded::Name { name, .. })) => vec![name.to_string()],
+            (PatternSyntax::Path, Some(Excluded::Name { name, .. })) => {
+                vec![name.to_string(), format!("*/{}", name)]
+            }
+            (PatternSyntax::Regex, Some(Excluded::Name { glob: true, .. })) => Vec::new(),
+            (PatternSyntax::Regex, Some(Excluded::Name { name, .. })) => {
+                vec![format!("(^|/){}(/|$)", escape_regex(name))]
+            }
+            // flake8 only reads values with a slash as paths
+            (PatternSyntax::Name, Some(Excluded::Path(path))) => vec![format!("./{}", path)],
+            (PatternSyntax::Path, Some(Excluded::Path(path))) => vec![path.to_string()],
+            (PatternSyntax::Regex, Some(Excluded::Path(path))) => {
+                vec![format!("/{}(/|$)", escape_regex(path))]
+            }
+        }
+    }
+}
+
+/// What a gitignore pattern excludes, in terms the other syntaxes share
+enum Excluded<'a> {
+    /// Files and directories with this name, which may be a glob, anywhere
+    Name { name: &'a str, glob: bool },
+    /// A path relative to the tool's project
+    Path(&'a str),
+}
+
+impl<'a> Excluded<'a> {
+    /// What `pattern`, relative to the analyzed directory, excludes for a
+    /// tool running in `project`, if it can be told without gitignore rules
+    fn from_gitignore(pattern: &'a str, project: &str) -> Option<Self> {
+        // Commas would split the joined values
+        if pattern.starts_with(['#', '!']) || pattern.contains("**") || pattern.contains(['\\', ',']) {
+            return None;
+        }
+        // Tools cannot tell directory-only patterns apart
+        let pattern = pattern.trim().trim_end_matches('/');
+        let glob = pattern.contains(['*', '?', '[']);
+        if pattern.is_empty() || (pattern.contains('/') && glob) {
+            return None;
+        }
+        if !pattern.contains('/') {
+            return Some(Excluded::Name { name: pattern, glob });
+        }
+
+        // A path relative to the analyzed directory, which the tool takes
+        // relative to its project
+        let path = pattern.trim_start_matches('/');
+        match project.is_empty() {
+            true => Some(Excluded::Path(path)),
+            false => path
+                .strip_prefix(project)
+                .and_then(|path| path.strip_prefix('/'))
+                .map(Excluded::Path),
+        }
+    }
+}
+
+fn escape_regex(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if "\\.+*?()|[]{}^$".contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+impl ExcludeFlag {
+    fn args(&self, patterns: &[String]) -> Vec<String> {
+        if self.joined {
+            vec![format!("{}={}", self.flag, patterns.join(","))]
+        } else {
+            patterns
+                .iter()
+                .map(|pattern| format!("{}={}", self.flag, pattern))
+                .collect()
+        }
+    }
+}
+
+impl Tool {
+    /// Whether exiting with `exit_code` means the tool failed only because
+    /// of the findings it reported
+    pub fn failed_on_findings(&self, exit_code: Option<i32>) -> bool {
+        exit_code.is_some_and(|code| self.findings_exit_codes.contains(&code))
+    }
+
+    /// The directory the tool runs in, given the analyzed `directory`
+    pub fn working_dir(&self, directory: &Path) -> PathBuf {
+        if self.project.is_empty() {
+            directory.to_path_buf()
+        } else {
+            directory.join(&self.project)
+        }
+    }
+
+    /// Those of `files` (relative to the analyzed directory) that lie in the
+    /// tool's project, relative to the project
+    fn project_files(&self, files: &[String]) -> Vec<String> {
+        if self.project.is_empty() {
+            return files.to_vec();
+        }
+        files
+            .iter()
+            .filter_map(|file| file.strip_prefix(&self.project)?.strip_prefix('/'))
+            .map(str::to_string)
+            .collect()
+    }
+
+    /// This tool told to skip `patterns` (gitignore syntax, translated to the
+    /// flag's syntax), if it has a flag for that; otherwise the tool
+    /// unchanged
+    pub fn excluding(&self, patterns: &[String]) -> Tool {
+        let mut tool = self.clone();
+        let Some(exclude) = &self.exclude else {
+            return tool;
+        };
+        if patterns.is_empty() {
+            return tool;
+        }
+        let patterns: Vec<String> = patterns
+            .iter()
+            .flat_map(|pattern| exclude.syntax.translate(pattern, &self.project))
+            .collect();
+        if patterns.is_empty() {
+            return tool;
+        }
+        let args = exclude.args(&patterns);
+        tool.args.extend(args.iter().cloned());
+        if let Some(scope) = &mut tool.file_scope {
+            scope.args.extend(args);
+        }
+        tool
+    }
+
+    /// This tool restricted to those of `files` (relative to the analyzed
+    /// directory) it checks.
+    ///
+    /// Returns the tool unchanged if it cannot take a file list, and `None`
+    /// if it can but none of `files` concern it.
+    pub fn scoped_to(&self, files: &[String]) -> Option<Tool> {
+        let Some(scope) = &self.file_scope else {
+            return Some(self.clone());
+        };
+        let matching: Vec<String> = self
+            .project_files(files)
+            .into_iter()
+            .filter(|file| {
+                Path::new(file)
+                    .extension()
+                    .and_then(|extension| extension.to_str())
+                    .is_some_and(|extension| scope.extensions.iter().any(|e| e == extension))
+            })
+            .collect();
+        if matching.is_empty() {
+            return None;
+        }
+        let mut args = scope.args.clone();
+        args.extend(matching);
+        Some(Tool {
+            command: scope.command.clone(),
+            args,
+            ..self.clone()
+        })
+    }
+
+    /// Whether a change to any of `files` (relative to the analyzed
+    /// directory) can change this tool's outcome. Tools that declare no inputs are affected by anything.
+    pub fn is_affected_by(&self, files: &[String]) -> bool {
+        let files = self.project_files(files);
+        if self.inputs.is_empty() {
+            return !files.is_empty();
+        }
+        let mut builder = GlobSetBuilder::new();
+        for input in &self.inputs {
+            // `*` stays within one directory; `**/` crosses them
+            if let Ok(glob) = GlobBuilder::new(input).literal_separator(true).build() {
+                builder.add(glob);
+            }
+        }
+        let Ok(inputs) = builder.build() else {
+            return true;
+        };
+        files.iter().any(|file| inputs.is_match(file))
+    }
+}
+
+/// A fix invocation, for tool definitions
+pub fn fix(command: &str, args: &[&str]) -> Option<FixCommand> {
+    Some(FixCommand {
+        command: command.to_string(),
+        args: args.iter().map(|arg| arg.to_string()).collect(),
+    })
+}
+
+/// A version probe, for tool definitions
+pub fn probe(command: &str, args: &[&str]) -> Option<VersionProbe> {
+    Some(VersionProbe {
+        command: command.to_string(),
+        args: args.iter().map(|arg| arg.to_string()).collect(),
+    })
+}
+
+/// An exclusion flag, for tool definitions
+pub fn exclude(flag: &str, joined: bool, syntax: PatternSyntax) -> Option<ExcludeFlag> {
+    Some(ExcludeFlag {
+        flag: flag.to_string(),
+        joined,
+        syntax,
+    })
+}
+
+/// Input globs as owned strings, for tool definitions
+pub fn inputs(globs: &[&str]) -> Vec<String> {
+    globs.iter().map(|glob| glob.to_string()).collect()
+}
+
+/// The captured result of a single tool invocation
+#[derive(Debug, Clone)]
+pub struct ToolOutput {
+    /// The exit code of the process, if it exited normally
+    pub exit_code: Option<i32>,
+    /// Everything the tool wrote to stdout
+    pub stdout: String,
+    /// Everything the tool wrote to stderr
+    pub stderr: String,
+    /// How long the tool took to run
+    pub duration: Duration,
+}
+
+impl ToolOutput {
+    /// Whether the tool exited with status zero
+    pub fn success(&self) -> bool {
+        self.exit_code == Some(0)
+    }
+
+    /// Stdout followed by stderr, skipping whichever is empty
+    pub fn combined(&self) -> String {
+        match (self.stdout.trim_end().is_empty(), self.stderr.trim_end().is_empty()) {
+            (false, false) => format!("{}\n{}", self.stdout.trim_end(), self.stderr.trim_end()),
+            (false, true) => self.stdout.trim_end().to_string(),
+            (true, false) => self.stderr.trim_end().to_string(),
+            (true, true) => String::new(),
+        }
+    }
+}
+
+/// Which output stream a line was read from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputStream {
+    Stdout,
+    Stderr,
 }
 
 /// Trait for running tools
 #[async_trait]
 pub trait ToolRunner: Send + Sync {
-    /// Run the tool in the given directory
-    async fn run(&self, directory: &Path) -> Result<String>;
+    /// Run the tool in the given directory, forwarding each output line to
+    /// `lines` as soon as it is read.
+    ///
+    /// A non-zero exit status is not an error; the error case is reserved for
+    /// failing to start the process at all (e.g. the binary is not installed).
+    async fn run_streaming(
+        &self,
+        directory: &Path,
+        lines: mpsc::Sender<(OutputStream, String)>,
+    ) -> Result<ToolOutput>;
 }
 
 #[async_trait]
 impl ToolRunner for Tool {
-    async fn run(&self, directory: &Path) -> Result<String> {
-        let output = tokio::process::Command::new(&self.command)
+    async fn run_streaming(
+        &self,
+        directory: &Path,
+        lines: mpsc::Sender<(OutputStream, String)>,
+    ) -> Result<ToolOutput> {
+        let started = Instant::now();
+        let mut command = tokio::process::Command::new(&self.command);
+        for variable in HOOK_ENV {
+            command.env_remove(variable);
+        }
+        let mut child = command
             .current_dir(directory)
             .args(&self.args)
-            .output()
-            .await?;
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()?;
+
+        let stdout = child.stdout.take().map(BufReader::new);
+        let stderr = child.stderr.take().map(BufReader::new);
+        let (stdout, stderr, status) = tokio::join!(
+            collect_lines(stdout, OutputStream::Stdout, &lines),
+            collect_lines(stderr, OutputStream::Stderr, &lines),
+            child.wait(),
+        );
+
+        Ok(ToolOutput {
+            exit_code: status?.code(),
+            stdout,
+            stderr,
+            duration: started.elapsed(),
+        })
+    }
+}
 
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        let stderr = String::from_utf8_lossy(&output.stderr);
+/// Read `reader` to the end, forwarding every line and returning them joined
+async fn collect_lines<R: AsyncBufRead + Unpin>(
+    reader: Option<R>,
+    stream: OutputStream,
+    lines: &mpsc::Sender<(OutputStream, String)>,
+) -> String {
+    let mut collected = String::new();
+    let Some(reader) = reader else {
+        return collected;
+    };
+    let mut reader = reader.lines();
+    while let Ok(Some(line)) = reader.next_line().await {
+        collected.push_str(&line);
+        collected.push('\n');
+        // Nobody listening is fine; the output is still collected
+        let _ = lines.send((stream, line)).await;
+    }
+    collected
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        if !output.status.success() {
-            anyhow::bail!("Command failed: {}", stderr);
+    fn translate(syntax: PatternSyntax, pattern: &str, project: &str) -> Vec<String> {
+        syntax.translate(pattern, project)
+    }
+
+    #[test]
+    fn passes_gitignore_patterns_through() {
+        for pattern in ["build/", "!keep.py", "**/generated", "*.min.js"] {
+            assert_eq!(translate(PatternSyntax::Gitignore, pattern, "web"), [pattern]);
         }
+    }
 
-        Ok(stdout.to_string())
+    #[test]
+    fn translates_names() {
+        assert_eq!(translate(PatternSyntax::Name, "build/", ""), ["build"]);
+        assert_eq!(translate(PatternSyntax::Name, "*.pyc", ""), ["*.pyc"]);
+        assert_eq!(translate(PatternSyntax::Path, "build", ""), ["build", "*/build"]);
+        assert_eq!(translate(PatternSyntax::Path, "*.pyc", ""), ["*.pyc", "*/*.pyc"]);
+        assert_eq!(translate(PatternSyntax::Regex, "a.b", ""), [r"(^|/)a\.b(/|$)"]);
+        assert!(translate(PatternSyntax::Regex, "*.pyc", "").is_empty());
     }
-} 
\ No newline at end of file
+
+    #[test]
+    fn translates_paths_relative_to_the_project() {
+        assert_eq!(translate(PatternSyntax::Name, "/src/gen", ""), ["./src/gen"]);
+        assert_eq!(translate(PatternSyntax::Name, "api/src/gen/", "api"), ["./src/gen"]);
+        assert_eq!(translate(PatternSyntax::Path, "api/src/gen", "api"), ["src/gen"]);
+        assert_eq!(translate(PatternSyntax::Regex, "api/src/gen.d", "api"), [r"/src/gen\.d(/|$)"]);
+        // Outside the project, or only sharing a prefix with it
+        assert!(translate(PatternSyntax::Path, "web/src", "api").is_empty());
+        assert!(translate(PatternSyntax::Path, "apis/src", "api").is_empty());
+    }
+
+    #[test]
+    fn leaves_out_patterns_that_do_not_translate() {
+        for syntax in [PatternSyntax::Name, PatternSyntax::Path, PatternSyntax::Regex] {
+            for pattern in ["!keep.py", "# comment", "**/generated", "src/*.py", "a,b", "a\\ b", "/"] {
+                assert!(translate(syntax, pattern, "").is_empty(), "{:?} {}", syntax, pattern);
+            }
+        }
+    }
+}
@@ -0,0 +1,122 @@
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+/// One condition detection checked, e.g. that `package.json` exists
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Check {
+    /// What was found, or what was missing
+    pub description: String,
+    pub passed: bool,
+    /// What to change for the check to pass
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub suggestion: Option<String>,
+}
+
+impl Check {
+    pub fn new(description: impl Into<String>, passed: bool) -> Self {
+        Self {
+            description: description.into(),
+            passed,
+            suggestion: None,
+        }
+    }
+
+    /// The same check, suggesting `suggestion` when it failed
+    pub fn suggest(mut self, suggestion: impl Into<String>) -> Self {
+        if !self.passed {
+            self.suggestion = Some(suggestion.into());
+        }
+        self
+    }
+}
+
+/// Whether a known tool was detected, and the checks that decided it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Verdict {
+    /// The tool's name, prefixed with its project like detected tools
+    pub tool: String,
+    /// The project the tool belongs to; empty for the analyzed directory
+    pub project: String,
+    pub detected: bool,
+    pub checks: Vec<Check>,
+}
+
+impl Verdict {
+    /// The suggestion of the first failed check
+    pub fn suggestion(&self) -> Option<&str> {
+        self.checks
+            .iter()
+            .find(|check| !check.passed)
+            .and_then(|check| check.suggestion.as_deref())
+    }
+}
+
+/// The verdicts on one ecosystem's tools in one directory, as its detector
+/// checks them. Checks passed to [`Detection::require`] apply to every tool
+/// considered afterwards, so a detector records its preconditions once and
+/// still considers each of its tools.
+pub struct Detection {
+    directory: PathBuf,
+    required: Vec<Check>,
+    verdicts: Vec<Verdict>,
+}
+
+impl Detection {
+    pub fn new(directory: &Path) -> Self {
+        Self {
+            directory: directory.to_path_buf(),
+            required: Vec::new(),
+            verdicts: Vec::new(),
+        }
+    }
+
+    /// The directory whose tools are detected
+    pub fn directory(&self) -> &Path {
+        &self.directory
+    }
+
+    /// A check that one of `files` exists in the directory, suggesting to
+    /// add the first
+    pub fn files(&self, files: &[&str]) -> Check {
+        match files.iter().find(|file| self.directory.join(file).exists()) {
+            Some(found) => Check::new(format!("found {}", found), true),
+            None => Check::new(format!("no {}", either(files)), false)
+                .suggest(format!("add {}", files[0])),
+        }
+    }
+
+    /// Require `check` of every tool considered from now on; returns whether
+    /// it passed
+    pub fn require(&mut self, check: Check) -> bool {
+        let passed = check.passed;
+        self.required.push(check);
+        passed
+    }
+
+    /// Record the verdict on `tool`: detected when its own `checks` and
+    /// every required check passed
+    pub fn consider(&mut self, tool: &str, checks: Vec<Check>) -> bool {
+        let checks: Vec<Check> = self.required.iter().cloned().chain(checks).collect();
+        let detected = checks.iter().all(|check| check.passed);
+        self.verdicts.push(Verdict {
+            tool: tool.to_string(),
+            project: String::new(),
+            detected,
+            checks,
+        });
+        detected
+    }
+
+    pub fn into_verdicts(self) -> Vec<Verdict> {
+        self.verdicts
+    }
+}
+
+/// "a, b or c"
+fn either(items: &[&str]) -> String {
+    match items.split_last() {
+        Some((last, [])) => last.to_string(),
+        Some((last, rest)) => format!("{} or {}", rest.join(", "), last),
+        None => String::new(),
+    }
+}
@@ -1,170 +1,808 @@
-use crate::tools::base::Tool;
+use crate::tools::base::{inputs, probe, FileScope, FixCommand, Tool, VersionProbe};
+use crate::tools::detection::{Check, Detection};
+use std::collections::BTreeMap;
 use std::path::Path;
 
+/// Where a Node.js tool's configuration can live
+struct ConfigSources {
+    tool: &'static str,
+    /// Config files, the first being the one to suggest adding
+    files: &'static [&'static str],
+    /// The package.json key that can hold the configuration instead
+    package_key: Option<&'static str>,
+}
+
+/// The configuration that makes each Node.js tool detected
+const CONFIGS: [ConfigSources; 4] = [
+    ConfigSources {
+        tool: "ESLint",
+        files: &[
+            "eslint.config.js",
+            "eslint.config.mjs",
+            "eslint.config.cjs",
+            "eslint.config.ts",
+            "eslint.config.mts",
+            "eslint.config.cts",
+            ".eslintrc",
+            ".eslintrc.js",
+            ".eslintrc.cjs",
+            ".eslintrc.json",
+            ".eslintrc.yml",
+            ".eslintrc.yaml",
+        ],
+        package_key: Some("eslintConfig"),
+    },
+    ConfigSources {
+        tool: "Jest",
+        files: &[
+            "jest.config.js",
+            "jest.config.ts",
+            "jest.config.mjs",
+            "jest.config.cjs",
+            "jest.config.json",
+        ],
+        package_key: Some("jest"),
+    },
+    ConfigSources {
+        tool: "TypeScript",
+        files: &["tsconfig.json"],
+        package_key: None,
+    },
+    ConfigSources {
+        tool: "Prettier",
+        files: &[
+            ".prettierrc",
+            ".prettierrc.json",
+            ".prettierrc.yml",
+            ".prettierrc.yaml",
+            ".prettierrc.json5",
+            ".prettierrc.toml",
+            ".prettierrc.js",
+            ".prettierrc.mjs",
+            ".prettierrc.cjs",
+            ".prettierrc.ts",
+            ".prettierrc.mts",
+            ".prettierrc.cts",
+            "prettier.config.js",
+            "prettier.config.mjs",
+            "prettier.config.cjs",
+            "prettier.config.ts",
+            "prettier.config.mts",
+            "prettier.config.cts",
+        ],
+        package_key: Some("prettier"),
+    },
+];
+
 /// Package manager types for JavaScript projects
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum PackageManager {
     Npm,
-    Yarn,
+    /// Yarn 1
+    YarnClassic,
+    /// Yarn 2 and later, which keeps its settings in `.yarnrc.yml`
+    YarnBerry,
     Pnpm,
+    Bun,
+}
+
+/// How the package manager of a project was decided
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Selection {
+    /// The `packageManager` field of package.json, as Corepack reads it,
+    /// e.g. `yarn@4.1.0`
+    Corepack(String),
+    /// A lockfile, e.g. `yarn.lock`
+    Lockfile(&'static str),
 }
 
 impl PackageManager {
     fn detect(directory: &Path) -> Option<Self> {
-        if directory.join("package-lock.json").exists() {
-            Some(PackageManager::Npm)
+        Self::select(directory).map(|(pm, _)| pm)
+    }
+
+    /// The package manager of the project in `directory` and what decided
+    /// it. The `packageManager` field wins, as Corepack refuses to run any
+    /// other; otherwise the lockfile does.
+    fn select(directory: &Path) -> Option<(Self, Selection)> {
+        if let Some(pinned) = corepack_pin(directory) {
+            let (name, version) = pinned.split_once('@').unwrap_or((&pinned, ""));
+            let pm = match name {
+                "npm" => Some(PackageManager::Npm),
+                "yarn" if version.starts_with("1.") => Some(PackageManager::YarnClassic),
+                "yarn" => Some(PackageManager::YarnBerry),
+                "pnpm" => Some(PackageManager::Pnpm),
+                "bun" => Some(PackageManager::Bun),
+                _ => None,
+            };
+            if let Some(pm) = pm {
+                return Some((pm, Selection::Corepack(pinned)));
+            }
+        }
+        let pm = if directory.join("package-lock.json").exists() {
+            PackageManager::Npm
         } else if directory.join("yarn.lock").exists() {
-            Some(PackageManager::Yarn)
+            if is_yarn_berry(directory) {
+                PackageManager::YarnBerry
+            } else {
+                PackageManager::YarnClassic
+            }
         } else if directory.join("pnpm-lock.yaml").exists() {
-            Some(PackageManager::Pnpm)
+            PackageManager::Pnpm
+        } else if directory.join("bun.lock").exists() || directory.join("bun.lockb").exists() {
+            PackageManager::Bun
         } else {
-            None
+            return None;
+        };
+        Some((pm, Selection::Lockfile(pm.lockfile(directory))))
+    }
+
+    /// The lockfile this package manager writes in `directory`
+    fn lockfile(&self, directory: &Path) -> &'static str {
+        match self {
+            PackageManager::Npm => "package-lock.json",
+            PackageManager::YarnClassic | PackageManager::YarnBerry => "yarn.lock",
+            PackageManager::Pnpm => "pnpm-lock.yaml",
+            // Bun 1.2 replaced the binary lockfile with a text one
+            PackageManager::Bun if directory.join("bun.lockb").exists() => "bun.lockb",
+            PackageManager::Bun => "bun.lock",
+        }
+    }
+
+    /// "Yarn Berry", for detection checks
+    fn describe(&self) -> &'static str {
+        match self {
+            PackageManager::Npm => "npm",
+            PackageManager::YarnClassic => "Yarn classic",
+            PackageManager::YarnBerry => "Yarn Berry",
+            PackageManager::Pnpm => "pnpm",
+            PackageManager::Bun => "Bun",
+        }
+    }
+
+    /// The files dependency checks read
+    fn dependency_inputs(&self) -> Vec<String> {
+        match self {
+            PackageManager::Npm => inputs(&["package.json", "package-lock.json", "npm-shrinkwrap.json"]),
+            PackageManager::YarnClassic => inputs(&["package.json", "yarn.lock"]),
+            PackageManager::YarnBerry => inputs(&["package.json", "yarn.lock", ".yarnrc.yml"]),
+            PackageManager::Pnpm => inputs(&["package.json", "pnpm-lock.yaml"]),
+            PackageManager::Bun => inputs(&["package.json", "bun.lock", "bun.lockb"]),
         }
     }
 
     fn get_command(&self) -> String {
         match self {
             PackageManager::Npm => "npm".to_string(),
-            PackageManager::Yarn => "yarn".to_string(),
+            PackageManager::YarnClassic | PackageManager::YarnBerry => "yarn".to_string(),
             PackageManager::Pnpm => "pnpm".to_string(),
+            PackageManager::Bun => "bun".to_string(),
+        }
+    }
+
+    /// Arguments that audit the dependencies for vulnerabilities; Yarn Berry
+    /// moved the audit under `yarn npm` and only checks direct dependencies
+    /// of the current workspace unless told otherwise
+    fn audit_args(&self) -> Vec<String> {
+        match self {
+            PackageManager::YarnBerry => ["npm", "audit", "--all", "--recursive"]
+                .iter()
+                .map(|arg| arg.to_string())
+                .collect(),
+            _ => vec!["audit".to_string()],
         }
     }
 
-    fn get_args(&self) -> Vec<String> {
+    /// Arguments that list outdated dependencies; Yarn Berry has no
+    /// non-interactive command for it
+    fn outdated_args(&self) -> Option<Vec<String>> {
         match self {
-            PackageManager::Npm => vec!["run".to_string()],
-            PackageManager::Yarn => vec![],
-            PackageManager::Pnpm => vec!["run".to_string()],
+            PackageManager::YarnBerry => None,
+            _ => Some(vec!["outdated".to_string()]),
         }
     }
+
+    /// Command and arguments that run the package.json script `script`;
+    /// `run` keeps e.g. a script named `install` from meaning the built-in
+    fn run_script(&self, script: &str) -> (String, Vec<String>) {
+        (self.get_command(), vec!["run".to_string(), script.to_string()])
+    }
 }
 
-/// Detect Node.js/JavaScript-specific tools in the given directory
-pub fn detect_js_tools(directory: &Path) -> Vec<Tool> {
-    let mut tools = Vec::new();
+/// The `packageManager` field of the package.json in `directory`, e.g.
+/// `pnpm@9.1.0+sha512.abc`, without the hash
+fn corepack_pin(directory: &Path) -> Option<String> {
+    let contents = std::fs::read_to_string(directory.join("package.json")).ok()?;
+    let package: serde_json::Value = serde_json::from_str(&contents).ok()?;
+    let pinned = package.get("packageManager")?.as_str()?;
+    Some(pinned.split('+').next().unwrap_or(pinned).to_string())
+}
 
-    // Check for package.json to identify Node.js projects
-    if !directory.join("package.json").exists() {
-        return tools;
+/// Whether Yarn 2 or later in `directory` installs with Plug'n'Play, its
+/// default, rather than into `node_modules` (`nodeLinker` in `.yarnrc.yml`)
+fn is_plug_n_play(directory: &Path) -> bool {
+    if directory.join(".pnp.cjs").exists() {
+        return true;
     }
+    let linker = std::fs::read_to_string(directory.join(".yarnrc.yml"))
+        .ok()
+        .and_then(|contents| serde_yaml::from_str::<serde_yaml::Value>(&contents).ok())
+        .and_then(|settings| settings.get("nodeLinker")?.as_str().map(str::to_string));
+    linker.is_none_or(|linker| linker == "pnp")
+}
 
-    if let Some(pm) = PackageManager::detect(directory) {
-        let pm_cmd = pm.get_command();
-        let pm_args = pm.get_args();
+/// Whether the `yarn.lock` in `directory` belongs to Yarn 2 or later: its
+/// settings live in `.yarnrc.yml`, and its lockfile starts with metadata
+fn is_yarn_berry(directory: &Path) -> bool {
+    directory.join(".yarnrc.yml").exists()
+        || std::fs::read_to_string(directory.join("yarn.lock"))
+            .is_ok_and(|lockfile| lockfile.contains("__metadata:"))
+}
 
-        // ESLint
-        if directory.join(".eslintrc").exists() || directory.join(".eslintrc.js").exists() {
-            let mut args = pm_args.clone();
-            args.push("lint".to_string());
-            tools.push(Tool {
-                name: "ESLint".to_string(),
-                command: pm_cmd.clone(),
-                args,
-                category: "linting".to_string(),
-                description: "Check JavaScript code style using ESLint".to_string(),
-            });
+/// The scripts of a package.json, by name
+struct Scripts(BTreeMap<String, String>);
+
+impl Scripts {
+    fn read(package: &serde_json::Value) -> Self {
+        let scripts = package
+            .get("scripts")
+            .cloned()
+            .and_then(|scripts| serde_json::from_value(scripts).ok())
+            .unwrap_or_default();
+        Scripts(scripts)
+    }
+
+    /// The first of the `preferred` scripts that runs `binary` as a check
+    /// would: nothing but the binary, after any `NAME=value` assignments,
+    /// with arguments that `accept` (e.g. no flag that rewrites files).
+    /// Scripts that chain or redirect commands, e.g. `jest && codecov`, may
+    /// do more than check, so they are never picked.
+    fn wrapping<'a>(
+        &self,
+        binary: &str,
+        preferred: &[&'a str],
+        accept: fn(&[&str]) -> bool,
+    ) -> Option<&'a str> {
+        let local = format!("/{}", binary);
+        preferred.iter().copied().find(|name| {
+            let Some(command) = self.0.get(*name) else {
+                return false;
+            };
+            if command.contains(['&', ';', '|', '<', '>', '`', '$', '(']) {
+                return false;
+            }
+            let mut words = command
+                .split_whitespace()
+                .skip_while(|word| is_assignment(word));
+            let Some(program) = words.next() else {
+                return false;
+            };
+            let args: Vec<&str> = words.collect();
+            (program == binary || program.ends_with(&local)) && accept(&args)
+        })
+    }
+}
+
+/// Whether `word` is an environment assignment, e.g. `NODE_ENV=test`
+fn is_assignment(word: &str) -> bool {
+    word.split_once('=').is_some_and(|(name, _)| {
+        !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+    })
+}
+
+/// How one of the Node.js tools runs: through the package.json script that
+/// wraps it, or else its binary
+struct Invocation {
+    command: String,
+    args: Vec<String>,
+    /// The command that runs the binary itself, e.g. `yarn` for `yarn eslint`
+    bin: String,
+    /// The arguments `bin` starts with, e.g. `eslint` for `yarn eslint`
+    bin_args: Vec<String>,
+    /// "script `lint`", or how the binary runs
+    via: String,
+    /// The package manager for a script, which may find the binary where
+    /// codeguard would not (e.g. installed globally); otherwise the binary
+    probe: Option<VersionProbe>,
+}
+
+impl Invocation {
+    /// A passed check recording the choice, for `codeguard doctor`
+    fn check(&self) -> Check {
+        Check::new(format!("runs {}", self.via), true)
+    }
+
+    /// Command and arguments that run the binary itself with `args`, for
+    /// file lists and fixes, which scripts do not take
+    fn direct(&self, args: &[&str]) -> (String, Vec<String>) {
+        prefixed(&self.bin, &self.bin_args, args)
+    }
+
+    fn fix(&self, args: &[&str]) -> Option<FixCommand> {
+        let (command, args) = self.direct(args);
+        Some(FixCommand { command, args })
+    }
+}
+
+/// `command` with `leading` arguments, then `args`
+fn prefixed(command: &str, leading: &[String], args: &[&str]) -> (String, Vec<String>) {
+    let args = leading.iter().cloned().chain(args.iter().map(|arg| arg.to_string()));
+    (command.to_string(), args.collect())
+}
+
+/// A Node.js project, as far as it decides how its tools run
+struct JsProject<'a> {
+    pm: PackageManager,
+    /// Whether Yarn installs with Plug'n'Play, so there is no `node_modules`
+    plug_n_play: bool,
+    scripts: Scripts,
+    directory: &'a Path,
+    lockfile_directory: &'a Path,
+}
+
+impl JsProject<'_> {
+    /// How `binary` runs: through a script among `preferred` whose arguments
+    /// `accept`, or else directly with `direct_args`
+    fn invocation(
+        &self,
+        binary: &str,
+        direct_args: &[&str],
+        preferred: &[&str],
+        accept: fn(&[&str]) -> bool,
+    ) -> Invocation {
+        let (bin, bin_args) = self.bin(binary);
+        match self.scripts.wrapping(binary, preferred, accept) {
+            Some(script) => {
+                let (command, args) = self.pm.run_script(script);
+                Invocation {
+                    probe: probe(&command, &["--version"]),
+                    command,
+                    args,
+                    bin,
+                    bin_args,
+                    via: format!("script `{}`", script),
+                }
+            }
+            None => {
+                let (command, args) = prefixed(&bin, &bin_args, direct_args);
+                let (probe_command, probe_args) = prefixed(&bin, &bin_args, &["--version"]);
+                Invocation {
+                    via: std::iter::once(&bin).chain(&bin_args).cloned().collect::<Vec<_>>().join(" "),
+                    command,
+                    args,
+                    probe: Some(VersionProbe {
+                        command: probe_command,
+                        args: probe_args,
+                    }),
+                    bin,
+                    bin_args,
+                }
+            }
         }
+    }
 
-        // Jest
-        if directory.join("jest.config.js").exists() {
-            let mut args = pm_args.clone();
-            args.push("test".to_string());
-            tools.push(Tool {
-                name: "Jest".to_string(),
-                command: pm_cmd.clone(),
-                args,
-                category: "testing".to_string(),
-                description: "Run JavaScript tests using Jest".to_string(),
-            });
+    /// Command and leading arguments that run `binary` itself: through Yarn
+    /// under Plug'n'Play, through `bunx` with Bun, and otherwise from
+    /// `node_modules/.bin`
+    fn bin(&self, binary: &str) -> (String, Vec<String>) {
+        match self.pm {
+            PackageManager::YarnBerry if self.plug_n_play => ("yarn".to_string(), vec![binary.to_string()]),
+            PackageManager::Bun => ("bunx".to_string(), vec![binary.to_string()]),
+            _ => (local_bin(self.directory, self.lockfile_directory, binary), Vec::new()),
         }
+    }
+}
 
-        // TypeScript
-        if directory.join("tsconfig.json").exists() {
-            let mut args = pm_args.clone();
-            args.push("tsc".to_string());
-            tools.push(Tool {
-                name: "TypeScript".to_string(),
-                command: pm_cmd.clone(),
+/// The package.json in `directory`, or null when it is missing or invalid
+fn read_package(directory: &Path) -> serde_json::Value {
+    std::fs::read_to_string(directory.join("package.json"))
+        .ok()
+        .and_then(|contents| serde_json::from_str(&contents).ok())
+        .unwrap_or_default()
+}
+
+/// A check that `tool` is configured, by one of its config files or its key
+/// in `package`
+fn configured(detection: &Detection, package: &serde_json::Value, tool: &str) -> Check {
+    let Some(sources) = CONFIGS.iter().find(|sources| sources.tool == tool) else {
+        return Check::new(format!("no known configuration for {}", tool), false);
+    };
+    let files = detection.files(sources.files);
+    match sources.package_key {
+        Some(key) if !files.passed && package.get(key).is_some() => {
+            Check::new(format!("found `{}` in package.json", key), true)
+        }
+        Some(key) if !files.passed => Check {
+            description: format!("no {} config file or `{}` in package.json", tool, key),
+            ..files
+        },
+        _ => files,
+    }
+}
+
+/// The path, relative to `directory`, of `binary` in `node_modules/.bin`:
+/// the project's own or, for workspace members whose dependencies are
+/// hoisted, the workspace root's
+fn local_bin(directory: &Path, lockfile_directory: &Path, binary: &str) -> String {
+    let own = format!("node_modules/.bin/{}", binary);
+    let depth = directory
+        .strip_prefix(lockfile_directory)
+        .map_or(0, |member| member.components().count());
+    let hoisted = format!("{}{}", "../".repeat(depth), own);
+    if depth > 0 && !directory.join(&own).exists() && directory.join(&hoisted).exists() {
+        hoisted
+    } else {
+        own
+    }
+}
+
+fn has_any(words: &[&str], flags: &[&str]) -> bool {
+    words.iter().any(|word| flags.iter().any(|flag| word.eq_ignore_ascii_case(flag)))
+}
+
+/// A tool per package.json script, run only on demand with the package
+/// manager whose lockfile is in `lockfile_directory`
+pub fn detect_js_scripts(directory: &Path, lockfile_directory: &Path) -> Vec<Tool> {
+    let Some(pm) = PackageManager::detect(lockfile_directory) else {
+        return Vec::new();
+    };
+    Scripts::read(&read_package(directory))
+        .0
+        .into_iter()
+        .map(|(name, script)| {
+            let (command, args) = pm.run_script(&name);
+            Tool {
+                name: format!("script:{}", name),
+                command,
                 args,
-                category: "type-checking".to_string(),
-                description: "Check TypeScript types".to_string(),
+                category: "script".to_string(),
+                description: script,
+                on_demand: true,
+                ..Default::default()
+            }
+        })
+        .collect()
+}
+
+/// Detect Node.js/JavaScript-specific tools in the given directory, using the
+/// package manager whose lockfile is in `lockfile_directory` (the workspace
+/// root for workspace members)
+pub fn detect_js_tools(
+    directory: &Path,
+    lockfile_directory: &Path,
+    detection: &mut Detection,
+) -> Vec<Tool> {
+    let mut tools = Vec::new();
+
+    // Check for package.json to identify Node.js projects
+    detection.require(detection.files(&["package.json"]));
+
+    let selected = PackageManager::select(lockfile_directory);
+    let detected = selected.as_ref().map(|(pm, _)| *pm);
+    let at_root = lockfile_directory == directory;
+    let workspace = if at_root { "" } else { " in the workspace root" };
+    detection.require(match &selected {
+        Some((pm, Selection::Corepack(pinned))) => Check::new(
+            format!("package.json{} pins {} for Corepack, so the package manager is {}", workspace, pinned, pm.describe()),
+            true,
+        ),
+        Some((pm, Selection::Lockfile(lockfile))) => Check::new(
+            format!("found {}{}, so the package manager is {}", lockfile, workspace, pm.describe()),
+            true,
+        ),
+        None => Check::new(
+            format!("no lockfile found{}, so package manager unknown", workspace),
+            false,
+        )
+        .suggest("install dependencies with npm, yarn, pnpm or bun to create a lockfile"),
+    });
+    // Tools are only detected once the package manager is known
+    let pm = detected.unwrap_or(PackageManager::Npm);
+    let package = read_package(directory);
+    let project = JsProject {
+        pm,
+        plug_n_play: pm == PackageManager::YarnBerry && is_plug_n_play(lockfile_directory),
+        scripts: Scripts::read(&package),
+        directory,
+        lockfile_directory,
+    };
+
+    // ESLint, unless the script fixes what it finds
+    let eslint = project.invocation("eslint", &["."], &["lint"], |words| !has_any(words, &["--fix"]));
+    if detection.consider(
+        "ESLint",
+        vec![configured(detection, &package, "ESLint"), eslint.check()],
+    ) {
+        let (scope_command, scope_args) = eslint.direct(&[]);
+        let fix = eslint.fix(&["--fix", "."]);
+        tools.push(Tool {
+            name: "ESLint".to_string(),
+            command: eslint.command,
+            args: eslint.args,
+            category: "linting".to_string(),
+            description: format!("Check JavaScript code style using ESLint ({})", eslint.via),
+            inputs: inputs(&[
+                "**/*.{js,jsx,mjs,cjs,ts,tsx,vue}",
+                ".eslintrc*",
+                "eslint.config.*",
+                "package.json",
+            ]),
+            file_scope: Some(FileScope {
+                extensions: ["js", "jsx", "mjs", "cjs", "ts", "tsx", "vue"]
+                    .iter()
+                    .map(|e| e.to_string())
+                    .collect(),
+                command: scope_command,
+                args: scope_args,
+            }),
+            fix,
+            probe: eslint.probe,
+            findings_exit_codes: vec![1],
+            ..Default::default()
+        });
+    }
+
+    // Jest, unless the script keeps watching or updates snapshots
+    let jest = project.invocation("jest", &[], &["test"], |words| {
+        !has_any(words, &["--watch", "--watchAll", "-u", "--updateSnapshot"])
+    });
+    if detection.consider("Jest", vec![configured(detection, &package, "Jest"), jest.check()]) {
+        tools.push(Tool {
+            name: "Jest".to_string(),
+            command: jest.command,
+            args: jest.args,
+            category: "testing".to_string(),
+            description: format!("Run JavaScript tests using Jest ({})", jest.via),
+            inputs: inputs(&[
+                "**/*.{js,jsx,mjs,cjs,ts,tsx,vue}",
+                "**/__snapshots__/*.snap",
+                "jest.config.*",
+                "package.json",
+            ]),
+            probe: jest.probe,
+            findings_exit_codes: vec![1],
+            ..Default::default()
+        });
+    }
+
+    // TypeScript, only through a script that does not emit (a `build`
+    // script running `tsc` writes the output)
+    let tsc = project.invocation("tsc", &["--noEmit"], &["typecheck", "type-check", "tsc"], |words| {
+        has_any(words, &["--noEmit"])
+    });
+    if detection.consider("TypeScript", vec![configured(detection, &package, "TypeScript"), tsc.check()]) {
+        tools.push(Tool {
+            name: "TypeScript".to_string(),
+            command: tsc.command,
+            args: tsc.args,
+            category: "type-checking".to_string(),
+            description: format!("Check TypeScript types ({})", tsc.via),
+            inputs: inputs(&["**/*.{ts,tsx,mts,cts}", "tsconfig*.json"]),
+            probe: tsc.probe,
+            findings_exit_codes: vec![1, 2],
+            ..Default::default()
+        });
+    }
+
+    // Prettier, only through a script that checks rather than writes
+    let prettier = project.invocation("prettier", &["--check", "."], &["format:check", "format"], |words| {
+        has_any(words, &["--check", "-c", "--list-different", "-l"])
+            && !has_any(words, &["--write", "-w"])
+    });
+    if detection.consider(
+        "Prettier",
+        vec![configured(detection, &package, "Prettier"), prettier.check()],
+    ) {
+        let fix = prettier.fix(&["--write", "."]);
+        tools.push(Tool {
+            name: "Prettier".to_string(),
+            command: prettier.command,
+            args: prettier.args,
+            category: "formatting".to_string(),
+            description: format!("Check code formatting using Prettier ({})", prettier.via),
+            inputs: inputs(&[
+                "**/*.{js,jsx,mjs,cjs,ts,tsx,vue,css,scss,less,html,json,md,yaml,yml}",
+                ".prettierrc*",
+                "prettier.config.*",
+                "package.json",
+            ]),
+            fix,
+            probe: prettier.probe,
+            findings_exit_codes: vec![1],
+            ..Default::default()
+        });
+    }
+
+    // Package manager specific tools; workspace members share the
+    // root's lockfile, so the root checks dependencies for them
+    let root = if at_root {
+        Check::new("not a workspace member", true)
+    } else {
+        Check::new("a workspace member, whose root checks its dependencies", false)
+    };
+    for candidate in [PackageManager::Npm, PackageManager::YarnClassic, PackageManager::Pnpm, PackageManager::Bun] {
+        let name = candidate.get_command();
+        // Both Yarns run as `yarn`; the detected one decides the subcommands
+        let candidate = match detected {
+            Some(pm) if pm.get_command() == name => pm,
+            _ => candidate,
+        };
+        let checks = vec![
+            root.clone(),
+            match detected {
+                Some(pm) if pm == candidate => {
+                    Check::new(format!("{} is the package manager", pm.describe()), true)
+                }
+                Some(pm) => Check::new(
+                    format!("the package manager is {}, not {}", pm.describe(), name),
+                    false,
+                ),
+                None => Check::new(format!("no {}", candidate.lockfile(lockfile_directory)), false),
+            },
+        ];
+
+        // audit
+        if detection.consider(&format!("{}-audit", name), checks.clone()) {
+            tools.push(Tool {
+                name: format!("{}-audit", name),
+                command: name.clone(),
+                args: candidate.audit_args(),
+                category: "security".to_string(),
+                description: "Check for security vulnerabilities in dependencies".to_string(),
+                inputs: candidate.dependency_inputs(),
+                online: true,
+                ..Default::default()
             });
         }
 
-        // Prettier
-        if directory.join(".prettierrc").exists() || directory.join(".prettierrc.js").exists() {
-            let mut args = pm_args.clone();
-            args.push("format".to_string());
+        // outdated
+        let outdated = candidate.outdated_args();
+        let mut checks = checks;
+        if outdated.is_none() {
+            checks.push(
+                Check::new(format!("{} has no non-interactive outdated command", candidate.describe()), false)
+                    .suggest("run `yarn upgrade-interactive` to review outdated dependencies"),
+            );
+        }
+        if let (true, Some(args)) = (detection.consider(&format!("{}-outdated", name), checks), outdated) {
             tools.push(Tool {
-                name: "Prettier".to_string(),
-                command: pm_cmd.clone(),
+                name: format!("{}-outdated", name),
+                command: name,
                 args,
-                category: "formatting".to_string(),
-                description: "Format code using Prettier".to_string(),
+                category: "dependency".to_string(),
+                description: "Check for outdated dependencies".to_string(),
+                inputs: candidate.dependency_inputs(),
+                online: true,
+                ..Default::default()
             });
         }
+    }
 
-        // Package manager specific tools
-        match pm {
-            PackageManager::Npm => {
-                // npm audit
-                tools.push(Tool {
-                    name: "npm-audit".to_string(),
-                    command: pm_cmd.clone(),
-                    args: vec!["audit".to_string()],
-                    category: "security".to_string(),
-                    description: "Check for security vulnerabilities in dependencies".to_string(),
-                });
-
-                // npm outdated
-                tools.push(Tool {
-                    name: "npm-outdated".to_string(),
-                    command: pm_cmd,
-                    args: vec!["outdated".to_string()],
-                    category: "dependency".to_string(),
-                    description: "Check for outdated dependencies".to_string(),
-                });
-            }
-            PackageManager::Yarn => {
-                // yarn audit
-                tools.push(Tool {
-                    name: "yarn-audit".to_string(),
-                    command: pm_cmd.clone(),
-                    args: vec!["audit".to_string()],
-                    category: "security".to_string(),
-                    description: "Check for security vulnerabilities in dependencies".to_string(),
-                });
-
-                // yarn outdated
-                tools.push(Tool {
-                    name: "yarn-outdated".to_string(),
-                    command: pm_cmd,
-                    args: vec!["outdated".to_string()],
-                    category: "dependency".to_string(),
-                    description: "Check for outdated dependencies".to_string(),
-                });
-            }
-            PackageManager::Pnpm => {
-                // pnpm audit
-                tools.push(Tool {
-                    name: "pnpm-audit".to_string(),
-                    command: pm_cmd.clone(),
-                    args: vec!["audit".to_string()],
-                    category: "security".to_string(),
-                    description: "Check for security vulnerabilities in dependencies".to_string(),
-                });
-
-                // pnpm outdated
-                tools.push(Tool {
-                    name: "pnpm-outdated".to_string(),
-                    command: pm_cmd,
-                    args: vec!["outdated".to_string()],
-                    category: "dependency".to_string(),
-                    description: "Check for outdated dependencies".to_string(),
-                });
-            }
+    tools
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scripts(scripts: &[(&str, &str)]) -> Scripts {
+        Scripts(
+            scripts
+                .iter()
+                .map(|(name, command)| (name.to_string(), command.to_string()))
+                .collect(),
+        )
+    }
+
+    fn no_fix(words: &[&str]) -> bool {
+        !has_any(words, &["--fix"])
+    }
+
+    #[test]
+    fn picks_scripts_that_plainly_run_the_binary() {
+        let wrapping = |command: &str| scripts(&[("lint", command)]).wrapping("eslint", &["lint"], no_fix);
+        assert_eq!(wrapping("eslint ."), Some("lint"));
+        assert_eq!(wrapping("NODE_ENV=test TIMING=1 eslint --max-warnings 0 src"), Some("lint"));
+        assert_eq!(wrapping("./node_modules/.bin/eslint ."), Some("lint"));
+        assert_eq!(wrapping("eslint --FIX ."), None);
+        assert_eq!(wrapping("eslint . && prettier --check ."), None);
+        assert_eq!(wrapping("eslint . > lint.log"), None);
+        assert_eq!(wrapping("eslint $(git ls-files '*.js')"), None);
+        assert_eq!(wrapping("npx eslint ."), None);
+        assert_eq!(wrapping("tsc-eslint ."), None);
+        assert_eq!(wrapping("NODE_ENV=test"), None);
+    }
+
+    #[test]
+    fn prefers_scripts_in_order() {
+        let scripts = scripts(&[("test", "jest --coverage && codecov"), ("test:unit", "jest"), ("ci", "jest --ci")]);
+        assert_eq!(scripts.wrapping("jest", &["test", "test:unit", "ci"], |_| true), Some("test:unit"));
+        assert_eq!(scripts.wrapping("jest", &["ci", "test:unit"], |_| true), Some("ci"));
+        assert_eq!(scripts.wrapping("jest", &["test"], |_| true), None);
+    }
+
+    fn project_with(files: &[(&str, &str)]) -> tempfile::TempDir {
+        let directory = tempfile::tempdir().unwrap();
+        for (file, contents) in files {
+            let path = directory.path().join(file);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, contents).unwrap();
         }
+        directory
     }
 
-    tools
-} 
\ No newline at end of file
+    fn selected(files: &[(&str, &str)]) -> Option<(PackageManager, Selection)> {
+        PackageManager::select(project_with(files).path())
+    }
+
+    #[test]
+    fn chooses_the_package_manager() {
+        use PackageManager::*;
+        assert_eq!(selected(&[("package-lock.json", "{}")]), Some((Npm, Selection::Lockfile("package-lock.json"))));
+        assert_eq!(selected(&[("yarn.lock", "# yarn lockfile v1\n")]), Some((YarnClassic, Selection::Lockfile("yarn.lock"))));
+        assert_eq!(selected(&[("yarn.lock", "__metadata:\n  version: 8\n")]).map(|(pm, _)| pm), Some(YarnBerry));
+        assert_eq!(selected(&[("yarn.lock", ""), (".yarnrc.yml", "")]).map(|(pm, _)| pm), Some(YarnBerry));
+        assert_eq!(selected(&[("pnpm-lock.yaml", "")]), Some((Pnpm, Selection::Lockfile("pnpm-lock.yaml"))));
+        assert_eq!(selected(&[("bun.lock", "")]), Some((Bun, Selection::Lockfile("bun.lock"))));
+        assert_eq!(selected(&[("bun.lockb", "")]), Some((Bun, Selection::Lockfile("bun.lockb"))));
+        assert_eq!(selected(&[("package.json", "{}")]), None);
+    }
+
+    #[test]
+    fn honours_the_corepack_pin_over_lockfiles() {
+        use PackageManager::*;
+        let pinned = |pin: &str| {
+            let package = format!(r#"{{"packageManager": "{}"}}"#, pin);
+            selected(&[("package.json", &package), ("package-lock.json", "{}")])
+        };
+        assert_eq!(
+            pinned("pnpm@9.1.0+sha512.abc"),
+            Some((Pnpm, Selection::Corepack("pnpm@9.1.0".to_string())))
+        );
+        assert_eq!(pinned("yarn@1.22.22").map(|(pm, _)| pm), Some(YarnClassic));
+        assert_eq!(pinned("yarn@4.1.0").map(|(pm, _)| pm), Some(YarnBerry));
+        assert_eq!(pinned("bun@1.1.0").map(|(pm, _)| pm), Some(Bun));
+        // Unknown managers fall back to the lockfile
+        assert_eq!(pinned("deno@1.0.0").map(|(pm, _)| pm), Some(Npm));
+    }
+
+    #[test]
+    fn uses_the_subcommands_of_each_package_manager() {
+        assert_eq!(PackageManager::Npm.audit_args(), ["audit"]);
+        assert_eq!(PackageManager::YarnBerry.audit_args(), ["npm", "audit", "--all", "--recursive"]);
+        assert_eq!(PackageManager::Pnpm.outdated_args(), Some(vec!["outdated".to_string()]));
+        assert_eq!(PackageManager::YarnBerry.outdated_args(), None);
+        assert_eq!(
+            PackageManager::Bun.run_script("install"),
+            ("bun".to_string(), vec!["run".to_string(), "install".to_string()])
+        );
+    }
+
+    #[test]
+    fn runs_binaries_where_the_package_manager_installs_them() {
+        let bin = |pm, plug_n_play, directory: &Path, lockfile_directory: &Path| {
+            JsProject {
+                pm,
+                plug_n_play,
+                scripts: scripts(&[]),
+                directory,
+                lockfile_directory,
+            }
+            .bin("eslint")
+        };
+        let workspace = project_with(&[("node_modules/.bin/eslint", ""), ("packages/ui/package.json", "{}")]);
+        let root = workspace.path();
+        let member = root.join("packages/ui");
+
+        assert_eq!(bin(PackageManager::YarnBerry, true, root, root), ("yarn".to_string(), vec!["eslint".to_string()]));
+        assert_eq!(bin(PackageManager::Bun, false, root, root), ("bunx".to_string(), vec!["eslint".to_string()]));
+        assert_eq!(bin(PackageManager::YarnBerry, false, root, root), ("node_modules/.bin/eslint".to_string(), Vec::new()));
+        // Hoisted to the workspace root
+        assert_eq!(bin(PackageManager::Npm, false, &member, root).0, "../../node_modules/.bin/eslint");
+        assert_eq!(bin(PackageManager::Npm, false, &member, &member).0, "node_modules/.bin/eslint");
+
+        assert!(is_plug_n_play(project_with(&[(".yarnrc.yml", "yarnPath: .yarn/releases/yarn.cjs\n")]).path()));
+        assert!(!is_plug_n_play(project_with(&[(".yarnrc.yml", "nodeLinker: node-modules\n")]).path()));
+        assert!(is_plug_n_play(project_with(&[(".yarnrc.yml", "nodeLinker: node-modules\n"), (".pnp.cjs", "")]).path()));
+    }
+}
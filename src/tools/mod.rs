@@ -1,26 +1,120 @@
+pub mod availability;
 pub mod base;
+pub mod detection;
 pub mod js_tools;
 pub mod php_tools;
 pub mod python_tools;
 pub mod rust_tools;
 
-pub use base::{Tool, ToolRunner};
+pub use base::{OutputStream, Tool, ToolRunner};
+use detection::{Check, Detection, Verdict};
+use crate::config::Config;
+use crate::paths::PathFilter;
+use crate::projects::{discover_projects, Project};
 use std::path::Path;
 
-pub fn detect_tools(directory: &Path) -> Vec<Tool> {
+/// Detect the tools of every project in `directory`, grouped by project
+pub fn detect_tools(directory: &Path, filter: &PathFilter) -> Vec<Tool> {
+    discover_projects(directory, filter)
+        .iter()
+        .flat_map(|project| detect_project_tools(directory, project).0)
+        .collect()
+}
+
+/// The verdict on every known tool of every project in `directory`: whether
+/// it was detected, and why
+pub fn diagnose_tools(directory: &Path, filter: &PathFilter) -> Vec<Verdict> {
+    discover_projects(directory, filter)
+        .iter()
+        .flat_map(|project| detect_project_tools(directory, project).1)
+        .collect()
+}
+
+/// Detect the tools of one project, with the verdicts on every known tool.
+/// Tools of nested projects are named after their project (e.g.
+/// "frontend/ESLint") so that every name stays unique.
+fn detect_project_tools(directory: &Path, project: &Project) -> (Vec<Tool>, Vec<Verdict>) {
+    let project_dir = directory.join(&project.path);
     let mut tools = Vec::new();
-    
+    let mut verdicts = Vec::new();
+
     // Detect Python tools
-    tools.extend(python_tools::detect_python_tools(directory));
-    
+    let mut detection = Detection::new(&project_dir);
+    tools.extend(python_tools::detect_python_tools(&mut detection));
+    verdicts.extend(detection.into_verdicts());
+
     // Detect Node.js tools
-    tools.extend(js_tools::detect_js_tools(directory));
-    
-    // Detect Rust tools
-    tools.extend(rust_tools::detect_rust_tools(directory));
-    
+    let mut detection = Detection::new(&project_dir);
+    tools.extend(js_tools::detect_js_tools(
+        &project_dir,
+        &directory.join(&project.js_root),
+        &mut detection,
+    ));
+    verdicts.extend(detection.into_verdicts());
+
+    // Detect Rust tools; the workspace root runs them for its members
+    let mut detection = Detection::new(&project_dir);
+    detection.require(if project.in_cargo_workspace {
+        Check::new("a Cargo workspace member, whose root runs its Cargo tools", false)
+    } else {
+        Check::new("not a Cargo workspace member", true)
+    });
+    tools.extend(rust_tools::detect_rust_tools(&mut detection));
+    verdicts.extend(detection.into_verdicts());
+
     // Detect PHP tools
-    tools.extend(php_tools::detect_php_tools(directory));
-    
+    let mut detection = Detection::new(&project_dir);
+    tools.extend(php_tools::detect_php_tools(&mut detection));
+    verdicts.extend(detection.into_verdicts());
+
+    name_after(project, &mut tools);
+    if !project.path.is_empty() {
+        for verdict in &mut verdicts {
+            verdict.tool = format!("{}/{}", project.path, verdict.tool);
+            verdict.project = project.path.clone();
+        }
+    }
+    (tools, verdicts)
+}
+
+/// Name `tools` after their nested `project`
+fn name_after(project: &Project, tools: &mut [Tool]) {
+    if project.path.is_empty() {
+        return;
+    }
+    for tool in tools {
+        tool.name = format!("{}/{}", project.path, tool.name);
+        tool.project = project.path.clone();
+    }
+}
+
+/// Detect tools and apply the configuration that concerns all of them
+pub fn detect_tools_with_config(directory: &Path, config: &Config) -> Vec<Tool> {
+    let filter = PathFilter::new(directory, &config.ignore_patterns);
+    let tools = detect_tools(directory, &filter);
+    if !config.pass_ignore_patterns {
+        return tools;
+    }
     tools
-} 
\ No newline at end of file
+        .iter()
+        .map(|tool| tool.excluding(&config.ignore_patterns))
+        .collect()
+}
+
+/// Detect tools as [`detect_tools_with_config`] does, each project's followed
+/// by a tool per package.json script, which only runs on demand
+pub fn detect_tools_and_scripts(directory: &Path, config: &Config) -> Vec<Tool> {
+    let tools = detect_tools_with_config(directory, config);
+    let filter = PathFilter::new(directory, &config.ignore_patterns);
+    let mut all = Vec::with_capacity(tools.len());
+    for project in discover_projects(directory, &filter) {
+        all.extend(tools.iter().filter(|tool| tool.project == project.path).cloned());
+        let mut scripts = js_tools::detect_js_scripts(
+            &directory.join(&project.path),
+            &directory.join(&project.js_root),
+        );
+        name_after(&project, &mut scripts);
+        all.extend(scripts);
+    }
+    all
+}
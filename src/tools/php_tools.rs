@@ -1,67 +1,112 @@
-use super::base::Tool;
-use std::path::Path;
+use super::base::{exclude, fix, inputs, FileScope, PatternSyntax, Tool};
+use super::detection::Detection;
 
-/// Detect PHP-specific tools in the given directory
-pub fn detect_php_tools(directory: &Path) -> Vec<Tool> {
+/// Detect PHP-specific tools in the directory of `detection`
+pub fn detect_php_tools(detection: &mut Detection) -> Vec<Tool> {
     let mut tools = Vec::new();
 
     // Check for composer.json to identify PHP projects
-    if !directory.join("composer.json").exists() {
-        return tools;
-    }
+    detection.require(detection.files(&["composer.json"]));
 
     // PHPUnit
-    if directory.join("vendor/bin/phpunit").exists() {
+    let phpunit = detection
+        .files(&["vendor/bin/phpunit"])
+        .suggest("composer require --dev phpunit/phpunit");
+    if detection.consider("phpunit", vec![phpunit]) {
         tools.push(Tool {
             name: "phpunit".to_string(),
             command: "vendor/bin/phpunit".to_string(),
             args: vec![],
             category: "testing".to_string(),
             description: "Run PHP tests using PHPUnit".to_string(),
+            inputs: inputs(&["**/*.php", "phpunit.xml*", "composer.lock"]),
+            findings_exit_codes: vec![1],
+            ..Default::default()
         });
     }
 
     // PHP_CodeSniffer
-    if directory.join("vendor/bin/phpcs").exists() {
+    let phpcs = detection
+        .files(&["vendor/bin/phpcs"])
+        .suggest("composer require --dev squizlabs/php_codesniffer");
+    if detection.consider("phpcs", vec![phpcs]) {
         tools.push(Tool {
             name: "phpcs".to_string(),
             command: "vendor/bin/phpcs".to_string(),
             args: vec![],
             category: "linting".to_string(),
             description: "Check PHP code style using PHP_CodeSniffer".to_string(),
+            inputs: inputs(&["**/*.php", "phpcs.xml*", ".phpcs.xml*"]),
+            file_scope: Some(FileScope {
+                extensions: vec!["php".to_string()],
+                command: "vendor/bin/phpcs".to_string(),
+                args: vec![],
+            }),
+            exclude: exclude("--ignore", true, PatternSyntax::Regex),
+            // 2 when some of the violations are fixable; 3 is an error
+            findings_exit_codes: vec![1, 2],
+            ..Default::default()
         });
     }
 
     // PHPStan
-    if directory.join("vendor/bin/phpstan").exists() {
+    let phpstan = detection
+        .files(&["vendor/bin/phpstan"])
+        .suggest("composer require --dev phpstan/phpstan");
+    if detection.consider("phpstan", vec![phpstan]) {
         tools.push(Tool {
             name: "phpstan".to_string(),
             command: "vendor/bin/phpstan".to_string(),
             args: vec!["analyse".to_string()],
             category: "static-analysis".to_string(),
             description: "Run static analysis using PHPStan".to_string(),
+            inputs: inputs(&["**/*.php", "phpstan.neon*", "composer.lock"]),
+            findings_exit_codes: vec![1],
+            ..Default::default()
         });
     }
 
     // PHP CS Fixer
-    if directory.join("vendor/bin/php-cs-fixer").exists() {
+    let php_cs_fixer = detection
+        .files(&["vendor/bin/php-cs-fixer"])
+        .suggest("composer require --dev friendsofphp/php-cs-fixer");
+    if detection.consider("php-cs-fixer", vec![php_cs_fixer]) {
         tools.push(Tool {
             name: "php-cs-fixer".to_string(),
             command: "vendor/bin/php-cs-fixer".to_string(),
             args: vec!["fix".to_string(), "--dry-run".to_string()],
             category: "formatting".to_string(),
             description: "Check PHP code formatting using PHP CS Fixer".to_string(),
+            inputs: inputs(&["**/*.php", ".php-cs-fixer*.php"]),
+            // Intersect with the configured finder so excluded paths stay excluded
+            file_scope: Some(FileScope {
+                extensions: vec!["php".to_string()],
+                command: "vendor/bin/php-cs-fixer".to_string(),
+                args: vec![
+                    "fix".to_string(),
+                    "--dry-run".to_string(),
+                    "--path-mode=intersection".to_string(),
+                ],
+            }),
+            fix: fix("vendor/bin/php-cs-fixer", &["fix"]),
+            // A bit per problem; 8 is "files need fixing"
+            findings_exit_codes: vec![8],
+            ..Default::default()
         });
     }
 
     // Composer
-    tools.push(Tool {
-        name: "composer-validate".to_string(),
-        command: "composer".to_string(),
-        args: vec!["validate".to_string()],
-        category: "dependency".to_string(),
-        description: "Validate composer.json file".to_string(),
-    });
+    if detection.consider("composer-validate", Vec::new()) {
+        tools.push(Tool {
+            name: "composer-validate".to_string(),
+            command: "composer".to_string(),
+            args: vec!["validate".to_string()],
+            category: "dependency".to_string(),
+            description: "Validate composer.json file".to_string(),
+            inputs: inputs(&["composer.json", "composer.lock"]),
+            ..Default::default()
+        });
+    }
 
     tools
 } 
\ No newline at end of file
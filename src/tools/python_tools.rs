@@ -1,64 +1,251 @@
-use super::base::Tool;
+use super::base::{exclude, inputs, FileScope, FixCommand, PatternSyntax, Tool, VersionProbe};
+use super::detection::{Check, Detection};
 use std::path::Path;
 
-/// Detect Python-specific tools in the given directory
-pub fn detect_python_tools(directory: &Path) -> Vec<Tool> {
+/// The environment Python tools run in
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PythonEnv {
+    /// A virtualenv inside the project, e.g. `.venv`
+    Venv {
+        path: String,
+        /// The directory holding the tools: `bin`, or `Scripts` on Windows
+        bin: &'static str,
+        /// The interpreter's version, from `pyvenv.cfg`
+        version: Option<String>,
+    },
+    Uv,
+    Poetry,
+    Pdm,
+    Hatch,
+    Pipenv,
+    /// No environment found; tools come from PATH
+    Global,
+}
+
+impl PythonEnv {
+    /// The environment of the project in `directory`. A virtualenv in the
+    /// project wins, as every manager can create one there; otherwise the
+    /// manager whose lockfile or configuration is present runs the tools.
+    pub fn detect(directory: &Path) -> Self {
+        for path in [".venv", "venv"] {
+            let venv = directory.join(path);
+            let bin = [("bin", "python"), ("Scripts", "python.exe")]
+                .into_iter()
+                .find(|(bin, python)| venv.join(bin).join(python).exists());
+            if let Some((bin, _)) = bin {
+                return PythonEnv::Venv {
+                    path: path.to_string(),
+                    bin,
+                    version: venv_version(&venv),
+                };
+            }
+        }
+        let pyproject: Option<toml::Value> = std::fs::read_to_string(directory.join("pyproject.toml"))
+            .ok()
+            .and_then(|contents| contents.parse().ok());
+        // `[tool.hatch]` and `[tool.pdm]` alone only configure builds
+        let configures = |path: &[&str]| {
+            let mut value = pyproject.as_ref().and_then(|p| p.get("tool"));
+            for key in path {
+                value = value.and_then(|v| v.get(key));
+            }
+            value.is_some()
+        };
+        if directory.join("uv.lock").exists() || configures(&["uv"]) {
+            PythonEnv::Uv
+        } else if directory.join("poetry.lock").exists() || configures(&["poetry"]) {
+            PythonEnv::Poetry
+        } else if directory.join("pdm.lock").exists() || directory.join(".pdm-python").exists() {
+            PythonEnv::Pdm
+        } else if directory.join("hatch.toml").exists() || configures(&["hatch", "envs"]) {
+            PythonEnv::Hatch
+        } else if directory.join("Pipfile").exists() {
+            PythonEnv::Pipenv
+        } else {
+            PythonEnv::Global
+        }
+    }
+
+    /// The manager that runs commands with `<manager> run`, if any
+    fn manager(&self) -> Option<&'static str> {
+        match self {
+            PythonEnv::Uv => Some("uv"),
+            PythonEnv::Poetry => Some("poetry"),
+            PythonEnv::Pdm => Some("pdm"),
+            PythonEnv::Hatch => Some("hatch"),
+            PythonEnv::Pipenv => Some("pipenv"),
+            PythonEnv::Venv { .. } | PythonEnv::Global => None,
+        }
+    }
+
+    /// The interpreter tools run with, e.g. ".venv (Python 3.12.1)" or
+    /// "the Poetry environment"
+    pub fn describe(&self) -> String {
+        match self {
+            PythonEnv::Venv { path, version: Some(version), .. } => {
+                format!("{} (Python {})", path, version)
+            }
+            PythonEnv::Venv { path, version: None, .. } => path.clone(),
+            PythonEnv::Uv => "the uv environment".to_string(),
+            PythonEnv::Poetry => "the Poetry environment".to_string(),
+            PythonEnv::Pdm => "the PDM environment".to_string(),
+            PythonEnv::Hatch => "the Hatch environment".to_string(),
+            PythonEnv::Pipenv => "the Pipenv environment".to_string(),
+            PythonEnv::Global => "the python on PATH".to_string(),
+        }
+    }
+
+    /// Command and arguments that run `binary` in the environment with `args`
+    fn exec(&self, binary: &str, args: &[&str]) -> (String, Vec<String>) {
+        let args = args.iter().map(|arg| arg.to_string());
+        if let PythonEnv::Venv { path, bin, .. } = self {
+            return (format!("{}/{}/{}", path, bin, binary), args.collect());
+        }
+        match self.manager() {
+            Some(manager) => (
+                manager.to_string(),
+                ["run".to_string(), binary.to_string()].into_iter().chain(args).collect(),
+            ),
+            None => (binary.to_string(), args.collect()),
+        }
+    }
+
+    /// A fix invocation running `binary` in the environment with `args`
+    fn fix(&self, binary: &str, args: &[&str]) -> FixCommand {
+        let (command, args) = self.exec(binary, args);
+        FixCommand { command, args }
+    }
+
+    /// Asks `binary` for its version from within the environment
+    fn probe(&self, binary: &str) -> Option<VersionProbe> {
+        let (command, args) = self.exec(binary, &["--version"]);
+        Some(VersionProbe { command, args })
+    }
+
+    /// " in .venv (Python 3.12.1)", for tool descriptions
+    fn suffix(&self) -> String {
+        match self {
+            PythonEnv::Global => String::new(),
+            _ => format!(" in {}", self.describe()),
+        }
+    }
+}
+
+/// The Python version a virtualenv was created with, as `pyvenv.cfg`
+/// records it (`version = 3.12.1`, or `version_info = 3.12.1.final.0`)
+fn venv_version(venv: &Path) -> Option<String> {
+    let config = std::fs::read_to_string(venv.join("pyvenv.cfg")).ok()?;
+    config.lines().find_map(|line| {
+        let (key, value) = line.split_once('=')?;
+        match key.trim() {
+            "version" | "version_info" => {
+                Some(value.trim().split('.').take(3).collect::<Vec<_>>().join("."))
+            }
+            _ => None,
+        }
+    })
+}
+
+/// Detect Python-specific tools in the directory of `detection`, run in the
+/// project's environment
+pub fn detect_python_tools(detection: &mut Detection) -> Vec<Tool> {
     let mut tools = Vec::new();
+    let env = PythonEnv::detect(detection.directory());
+    detection.require(Check::new(format!("runs with {}", env.describe()), true));
 
     // pytest
-    if directory.join("pytest.ini").exists() || directory.join("tests").exists() {
+    if detection.consider("pytest", vec![detection.files(&["pytest.ini", "tests"])]) {
+        let (command, args) = env.exec("pytest", &["-v"]);
         tools.push(Tool {
             name: "pytest".to_string(),
-            command: "pytest".to_string(),
-            args: vec!["-v".to_string()],
+            command,
+            args,
             category: "testing".to_string(),
-            description: "Run Python tests using pytest".to_string(),
+            description: format!("Run Python tests using pytest{}", env.suffix()),
+            inputs: inputs(&["**/*.py", "pytest.ini", "pyproject.toml", "setup.cfg", "tox.ini"]),
+            exclude: exclude("--ignore-glob", false, PatternSyntax::Path),
+            probe: env.probe("pytest"),
+            findings_exit_codes: vec![1],
+            ..Default::default()
         });
     }
 
     // flake8
-    if directory.join(".flake8").exists() {
+    if detection.consider("flake8", vec![detection.files(&[".flake8"])]) {
+        let (command, args) = env.exec("flake8", &[]);
         tools.push(Tool {
             name: "flake8".to_string(),
-            command: "flake8".to_string(),
-            args: vec![],
+            command: command.clone(),
+            args: args.clone(),
             category: "linting".to_string(),
-            description: "Check Python code style with flake8".to_string(),
+            description: format!("Check Python code style with flake8{}", env.suffix()),
+            inputs: inputs(&["**/*.py", ".flake8", "setup.cfg", "tox.ini"]),
+            file_scope: Some(FileScope {
+                extensions: vec!["py".to_string()],
+                command,
+                args,
+            }),
+            exclude: exclude("--extend-exclude", true, PatternSyntax::Name),
+            probe: env.probe("flake8"),
+            findings_exit_codes: vec![1],
+            ..Default::default()
         });
     }
 
     // pylint
-    if directory.join(".pylintrc").exists() {
+    if detection.consider("pylint", vec![detection.files(&[".pylintrc"])]) {
+        let (command, args) = env.exec("pylint", &[]);
         tools.push(Tool {
             name: "pylint".to_string(),
-            command: "pylint".to_string(),
-            args: vec![],
+            command,
+            args,
             category: "linting".to_string(),
-            description: "Analyze Python code with pylint".to_string(),
+            description: format!("Analyze Python code with pylint{}", env.suffix()),
+            inputs: inputs(&["**/*.py", ".pylintrc", "pyproject.toml"]),
+            probe: env.probe("pylint"),
+            // A bit per message category found; 1 is fatal, 32 a usage error
+            findings_exit_codes: (2..32).step_by(2).collect(),
+            ..Default::default()
         });
     }
 
     // mypy
-    if directory.join("mypy.ini").exists() {
+    if detection.consider("mypy", vec![detection.files(&["mypy.ini"])]) {
+        let (command, args) = env.exec("mypy", &[]);
         tools.push(Tool {
             name: "mypy".to_string(),
-            command: "mypy".to_string(),
-            args: vec![],
+            command,
+            args,
             category: "type-checking".to_string(),
-            description: "Check Python types with mypy".to_string(),
+            description: format!("Check Python types with mypy{}", env.suffix()),
+            inputs: inputs(&["**/*.{py,pyi}", "mypy.ini", "pyproject.toml", "setup.cfg"]),
+            probe: env.probe("mypy"),
+            findings_exit_codes: vec![1],
+            ..Default::default()
         });
     }
 
     // black
-    if directory.join("pyproject.toml").exists() {
+    if detection.consider("black", vec![detection.files(&["pyproject.toml"])]) {
+        let (command, args) = env.exec("black", &["--check"]);
         tools.push(Tool {
             name: "black".to_string(),
-            command: "black".to_string(),
-            args: vec!["--check".to_string()],
+            command: command.clone(),
+            args: args.clone(),
             category: "formatting".to_string(),
-            description: "Check Python code formatting with black".to_string(),
+            description: format!("Check Python code formatting with black{}", env.suffix()),
+            inputs: inputs(&["**/*.{py,pyi}", "pyproject.toml"]),
+            file_scope: Some(FileScope {
+                extensions: vec!["py".to_string(), "pyi".to_string()],
+                command,
+                args,
+            }),
+            fix: Some(env.fix("black", &["."])),
+            probe: env.probe("black"),
+            findings_exit_codes: vec![1],
+            ..Default::default()
         });
     }
 
     tools
-} 
\ No newline at end of file
+}
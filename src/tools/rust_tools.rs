@@ -1,50 +1,70 @@
-use super::base::Tool;
-use std::path::Path;
+use super::base::{fix, inputs, probe, Tool};
+use super::detection::Detection;
 
-/// Detect Rust-specific tools in the given directory
-pub fn detect_rust_tools(directory: &Path) -> Vec<Tool> {
+/// Detect Rust-specific tools in the directory of `detection`
+pub fn detect_rust_tools(detection: &mut Detection) -> Vec<Tool> {
     let mut tools = Vec::new();
 
     // Only add Rust tools if Cargo.toml exists
-    if !directory.join("Cargo.toml").exists() {
-        return tools;
-    }
+    detection.require(detection.files(&["Cargo.toml"]));
 
     // cargo test
-    tools.push(Tool {
-        name: "cargo-test".to_string(),
-        command: "cargo".to_string(),
-        args: vec!["test".to_string()],
-        category: "testing".to_string(),
-        description: "Run Rust tests using cargo test".to_string(),
-    });
+    if detection.consider("cargo-test", Vec::new()) {
+        tools.push(Tool {
+            name: "cargo-test".to_string(),
+            command: "cargo".to_string(),
+            args: vec!["test".to_string()],
+            category: "testing".to_string(),
+            description: "Run Rust tests using cargo test".to_string(),
+            inputs: inputs(&["**/*.rs", "**/Cargo.toml", "Cargo.lock"]),
+            ..Default::default()
+        });
+    }
 
     // cargo clippy
-    tools.push(Tool {
-        name: "cargo-clippy".to_string(),
-        command: "cargo".to_string(),
-        args: vec!["clippy".to_string()],
-        category: "linting".to_string(),
-        description: "Run Rust linter using clippy".to_string(),
-    });
+    if detection.consider("cargo-clippy", Vec::new()) {
+        tools.push(Tool {
+            name: "cargo-clippy".to_string(),
+            command: "cargo".to_string(),
+            args: vec!["clippy".to_string()],
+            category: "linting".to_string(),
+            description: "Run Rust linter using clippy".to_string(),
+            inputs: inputs(&["**/*.rs", "**/Cargo.toml", "Cargo.lock", "clippy.toml", ".clippy.toml"]),
+            probe: probe("cargo", &["clippy", "--version"]),
+            ..Default::default()
+        });
+    }
 
     // cargo fmt
-    tools.push(Tool {
-        name: "cargo-fmt".to_string(),
-        command: "cargo".to_string(),
-        args: vec!["fmt".to_string(), "--check".to_string()],
-        category: "formatting".to_string(),
-        description: "Check Rust code formatting".to_string(),
-    });
+    if detection.consider("cargo-fmt", Vec::new()) {
+        tools.push(Tool {
+            name: "cargo-fmt".to_string(),
+            command: "cargo".to_string(),
+            args: vec!["fmt".to_string(), "--check".to_string()],
+            category: "formatting".to_string(),
+            description: "Check Rust code formatting".to_string(),
+            inputs: inputs(&["**/*.rs", "rustfmt.toml", ".rustfmt.toml"]),
+            fix: fix("cargo", &["fmt"]),
+            probe: probe("cargo", &["fmt", "--version"]),
+            findings_exit_codes: vec![1],
+            ..Default::default()
+        });
+    }
 
     // cargo audit
-    tools.push(Tool {
-        name: "cargo-audit".to_string(),
-        command: "cargo".to_string(),
-        args: vec!["audit".to_string()],
-        category: "security".to_string(),
-        description: "Check for security vulnerabilities in dependencies".to_string(),
-    });
+    if detection.consider("cargo-audit", Vec::new()) {
+        tools.push(Tool {
+            name: "cargo-audit".to_string(),
+            command: "cargo".to_string(),
+            args: vec!["audit".to_string()],
+            category: "security".to_string(),
+            description: "Check for security vulnerabilities in dependencies".to_string(),
+            inputs: inputs(&["Cargo.lock"]),
+            probe: probe("cargo", &["audit", "--version"]),
+            online: true,
+            ..Default::default()
+        });
+    }
 
     tools
 } 
\ No newline at end of file
@@ -1,34 +1,198 @@
-use crate::tools::{Tool, ToolRunner};
+use crate::baseline::{baseline_path, Baseline};
+use crate::cache::ResultCache;
+use crate::config::{Config, GatesConfig};
+use crate::coverage::{self, Coverage, CoverageReport, InstrumentedTool};
+use crate::doctor;
+use crate::explorer;
+use crate::findings::Finding;
+use crate::fix::{self, AppliedFix, FixPreview};
+use crate::gates::{self, GateInput, GateOutcome};
+use crate::history::{self, FlakyTest, History, Record};
+use crate::git::{ChangeScope, ChangeSet};
+use crate::paths::PathFilter;
+use crate::report::{FindingCounts, ToolStatus};
+use crate::runner::{self, RunEvent};
+use crate::suggestions;
+use crate::testcases::{parse_tests, rerun_failed, restricted_to, TestCase, TestOutcome, TestRunner};
+use crate::tools::availability::{self, Availability};
+use crate::tools::detection::Verdict;
+use crate::tools::Tool;
+use crate::watch::ChangeWatcher;
 use anyhow::Result;
 use crossterm::event::{self, Event, KeyCode, KeyEventKind};
 use ratatui::{
     prelude::*,
-    widgets::{Block, Borders, List, ListItem, ListState, Paragraph},
+    widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph, Sparkline},
 };
+use std::collections::{BTreeMap, HashMap};
 use std::path::Path;
+use std::sync::Arc;
 use tokio::sync::mpsc;
 
+/// Output of one tool run as shown in the results pane
+#[derive(Debug, Clone)]
+pub struct ToolResult {
+    pub tool: String,
+    pub category: String,
+    /// The arguments the tool ran with
+    pub args: Vec<String>,
+    /// `None` while the tool is still running
+    pub status: Option<ToolStatus>,
+    pub output: Vec<String>,
+    pub findings: Vec<Finding>,
+    /// Findings dropped because they lie outside the active change set
+    pub hidden_findings: usize,
+    /// Findings dropped because the baseline accepts them
+    pub baselined: usize,
+    /// Whether the outcome was replayed from the result cache
+    pub cached: bool,
+    /// The version of the tool's program, once finished
+    pub version: Option<String>,
+    pub exit_code: Option<i32>,
+}
+
+/// The tests of one tool as the explorer lists them
+#[derive(Debug, Clone)]
+struct Explorer {
+    tool: String,
+    /// `None` while the tests are being listed
+    tests: Option<Vec<String>>,
+    filter: String,
+    /// Whether keys edit the filter
+    filtering: bool,
+}
+
+/// Findings an applied fix should have resolved
+#[derive(Debug, Clone)]
+struct Verification {
+    tool: String,
+    /// Per file and message, how many findings were resolved and how many
+    /// should be left
+    expected: Vec<(String, String, usize, usize)>,
+}
+
 pub struct App {
     pub tools: Vec<Tool>,
     pub selected_tool: usize,
-    pub results: Vec<(String, String)>, // (tool_name, result)
+    pub results: Vec<ToolResult>,
     pub should_quit: bool,
     pub directory: std::path::PathBuf,
     tools_state: ListState,
     results_state: ListState,
-    result_sender: mpsc::Sender<(String, String)>,
-    result_receiver: mpsc::Receiver<(String, String)>,
+    result_sender: mpsc::Sender<RunEvent>,
+    result_receiver: mpsc::Receiver<RunEvent>,
     show_help: bool,
+    /// The first help line shown, when the help is taller than the terminal
+    help_scroll: u16,
+    gates: GatesConfig,
+    draft: bool,
+    /// Git ref the "changed only" toggle compares against
+    changed_base: String,
+    /// The active change set while "changed only" is on
+    changes: Option<ChangeSet>,
+    /// A transient message shown in the status bar
+    status_message: Option<String>,
+    /// Paths left out of change sets and watching
+    paths: PathFilter,
+    /// The accepted findings, left out of results and the gate, if the
+    /// directory has a baseline file
+    baseline: Option<Arc<Baseline>>,
+    /// The file watcher while watch mode is on
+    watcher: Option<ChangeWatcher>,
+    /// Changed paths waiting for the tools they affect to finish running
+    watch_pending: Vec<String>,
+    fix_sender: mpsc::Sender<Result<FixPreview>>,
+    fix_receiver: mpsc::Receiver<Result<FixPreview>>,
+    /// The fix awaiting confirmation, shown as a diff
+    fix_preview: Option<FixPreview>,
+    fix_scroll: u16,
+    /// Applied fixes, most recent last, for undo
+    applied_fixes: Vec<AppliedFix>,
+    /// What the right-hand pane shows
+    pane: Pane,
+    /// The selected row of the findings or tests pane
+    selected_row: usize,
+    row_state: ListState,
+    /// The check of an applied fix, made when its tool's rerun finishes
+    verifying: Option<Verification>,
+    /// Results of earlier runs, unless disabled with `--no-cache`
+    cache: Option<Arc<ResultCache>>,
+    /// The run history, if it could be opened
+    history: Option<Arc<History>>,
+    /// Recorded outcomes of the tool the history pane last showed
+    history_records: Option<(String, Vec<Record>)>,
+    /// Tests that both passed and failed on the same code
+    flaky: Vec<FlakyTest>,
+    /// How often "rerun failing tests" runs a test tool
+    flaky_reruns: usize,
+    /// The failed tests each targeted test invocation launched so far runs,
+    /// by its arguments
+    targeted: HashMap<Vec<String>, Vec<String>>,
+    /// The test explorer's listing, kept while other panes are shown
+    explorer: Option<Explorer>,
+    explorer_sender: mpsc::Sender<(String, Result<Vec<String>>)>,
+    explorer_receiver: mpsc::Receiver<(String, Result<Vec<String>>)>,
+    /// Whether test tools run with coverage collection
+    coverage_mode: bool,
+    /// Where instrumented runs write their reports, created on first use
+    coverage_dir: Option<Arc<tempfile::TempDir>>,
+    /// Instrumented runs whose reports are read once they finish, by tool
+    coverage_runs: HashMap<String, InstrumentedTool>,
+    /// The coverage of each test tool's latest instrumented run
+    coverage: BTreeMap<String, Coverage>,
+    /// Whether each tool's program is installed, by tool, as probed so far
+    availability: HashMap<String, Availability>,
+    availability_receiver: mpsc::Receiver<(String, Availability)>,
+    /// Why each known tool was or was not detected
+    verdicts: Vec<Verdict>,
+}
+
+/// What the right-hand pane shows
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Pane {
+    /// The output of every run
+    Results,
+    /// The findings of the selected tool's latest run
+    Findings,
+    /// The selected tool's recorded trends
+    History,
+    /// The tests of the selected test tool's latest run
+    Tests,
+    /// Every test of the selected test tool, listed without running it
+    Explorer,
+    /// Line coverage per file
+    Coverage,
+    /// Why each known tool was or was not detected
+    Doctor,
 }
 
 impl App {
-    pub fn new(directory: &Path) -> Self {
-        let tools = crate::tools::detect_tools(directory);
+    pub fn new(
+        directory: &Path,
+        config: &Config,
+        draft: bool,
+        changed_since: Option<String>,
+        no_cache: bool,
+    ) -> Self {
+        let tools = crate::tools::detect_tools_and_scripts(directory, config);
+        let verdicts = crate::tools::diagnose_tools(
+            directory,
+            &PathFilter::new(directory, &config.ignore_patterns),
+        );
         let mut tools_state = ListState::default();
         tools_state.select(Some(0));
         
         let (result_sender, result_receiver) = mpsc::channel(100);
-        
+        let (fix_sender, fix_receiver) = mpsc::channel(1);
+        let (explorer_sender, explorer_receiver) = mpsc::channel(1);
+        let (availability_sender, availability_receiver) = mpsc::channel(100);
+        let history = History::open().ok().map(Arc::new);
+        probe_tools(&tools, directory, &availability_sender);
+        let (baseline, status_message) = match Baseline::load(&baseline_path(directory, None)) {
+            Ok(baseline) => (baseline.map(Arc::new), None),
+            Err(err) => (None, Some(format!("{:#}", err))),
+        };
+
         Self {
             tools,
             selected_tool: 0,
@@ -40,49 +204,185 @@ impl App {
             result_sender,
             result_receiver,
             show_help: false,
+            help_scroll: 0,
+            gates: config.gates.clone(),
+            draft,
+            changed_base: changed_since.unwrap_or_else(|| "HEAD".to_string()),
+            changes: None,
+            status_message,
+            paths: PathFilter::new(directory, &config.ignore_patterns),
+            baseline,
+            watcher: None,
+            watch_pending: Vec::new(),
+            fix_sender,
+            fix_receiver,
+            fix_preview: None,
+            fix_scroll: 0,
+            applied_fixes: Vec::new(),
+            pane: Pane::Results,
+            selected_row: 0,
+            row_state: ListState::default(),
+            verifying: None,
+            cache: match no_cache {
+                true => None,
+                false => ResultCache::new(PathFilter::new(directory, &config.ignore_patterns)).map(Arc::new),
+            },
+            flaky: history
+                .as_ref()
+                .and_then(|history| history.flaky_tests(directory).ok())
+                .unwrap_or_default(),
+            history,
+            history_records: None,
+            flaky_reruns: config.flaky_reruns,
+            targeted: HashMap::new(),
+            explorer: None,
+            explorer_sender,
+            explorer_receiver,
+            coverage_mode: false,
+            coverage_dir: None,
+            coverage_runs: HashMap::new(),
+            coverage: BTreeMap::new(),
+            availability: HashMap::new(),
+            availability_receiver,
+            verdicts,
         }
     }
 
     pub fn handle_events(&mut self) -> Result<()> {
         if event::poll(std::time::Duration::from_millis(50))? {
             if let Event::Key(key) = event::read()? {
-                if key.kind == KeyEventKind::Press {
+                let filtering = self.pane == Pane::Explorer
+                    && self.explorer.as_ref().is_some_and(|explorer| explorer.filtering);
+                if key.kind == KeyEventKind::Press && self.fix_preview.is_some() {
+                    self.handle_fix_preview_key(key.code);
+                } else if key.kind == KeyEventKind::Press
+                    && self.show_help
+                    && matches!(key.code, KeyCode::Up | KeyCode::Down | KeyCode::Esc)
+                {
+                    match key.code {
+                        KeyCode::Up => self.help_scroll = self.help_scroll.saturating_sub(1),
+                        KeyCode::Down => self.help_scroll = self.help_scroll.saturating_add(1),
+                        _ => self.show_help = false,
+                    }
+                } else if key.kind == KeyEventKind::Press && filtering {
+                    self.handle_filter_key(key.code);
+                } else if key.kind == KeyEventKind::Press {
                     match key.code {
                         KeyCode::Char('q') => self.should_quit = true,
-                        KeyCode::Char('?') => self.show_help = !self.show_help,
-                        KeyCode::Char('r') => {
-                            let sender = self.result_sender.clone();
-                            let tool = self.tools[self.selected_tool].clone();
-                            let directory = self.directory.clone();
-                            tokio::spawn(async move {
-                                if let Ok(result) = tool.run(&directory).await {
-                                    let _ = sender.send((tool.name, result)).await;
-                                }
+                        KeyCode::Char('?') => {
+                            self.show_help = !self.show_help;
+                            self.help_scroll = 0;
+                        }
+                        KeyCode::Char('d') => self.draft = !self.draft,
+                        KeyCode::Char('c') => self.toggle_changed_only(),
+                        KeyCode::Char('w') => self.toggle_watch(),
+                        KeyCode::Char('f') => self.preview_fix(),
+                        KeyCode::Char('u') => self.undo_fix(),
+                        KeyCode::Char('v') => self.toggle_pane(Pane::Findings),
+                        KeyCode::Char('h') => self.toggle_pane(Pane::History),
+                        KeyCode::Char('t') => self.toggle_pane(Pane::Tests),
+                        KeyCode::Char('e') => self.toggle_explorer(),
+                        KeyCode::Char('C') => {
+                            self.coverage_mode = !self.coverage_mode;
+                            self.status_message = Some(match self.coverage_mode {
+                                true => "Test tools run with coverage".to_string(),
+                                false => "Coverage off".to_string(),
                             });
                         }
+                        KeyCode::Char('o') => self.toggle_pane(Pane::Coverage),
+                        KeyCode::Char('D') => self.toggle_pane(Pane::Doctor),
+                        KeyCode::Char('/') if self.pane == Pane::Explorer => {
+                            if let Some(explorer) = &mut self.explorer {
+                                explorer.filtering = true;
+                            }
+                        }
+                        KeyCode::Enter if self.pane == Pane::Explorer => self.run_explored(),
+                        KeyCode::Char('s') if self.pane == Pane::Findings => {
+                            self.preview_suggestions(false)
+                        }
+                        KeyCode::Char('S') if self.pane == Pane::Findings => {
+                            self.preview_suggestions(true)
+                        }
+                        KeyCode::Char('R') if self.pane == Pane::Tests => self.rerun_failing_tests(),
+                        KeyCode::Char('F') => self.rerun_failures(1),
+                        KeyCode::Esc if self.pane != Pane::Results => self.pane = Pane::Results,
+                        KeyCode::Up
+                            if matches!(
+                                self.pane,
+                                Pane::Findings
+                                    | Pane::Tests
+                                    | Pane::Explorer
+                                    | Pane::Coverage
+                                    | Pane::Doctor
+                            ) =>
+                        {
+                            self.selected_row = self.selected_row.saturating_sub(1);
+                        }
+                        KeyCode::Down
+                            if matches!(
+                                self.pane,
+                                Pane::Findings
+                                    | Pane::Tests
+                                    | Pane::Explorer
+                                    | Pane::Coverage
+                                    | Pane::Doctor
+                            ) =>
+                        {
+                            let count = match self.pane {
+                                Pane::Doctor => self.doctor_lines().len(),
+                                Pane::Findings => self.selected_findings().len(),
+                                Pane::Tests => self.selected_tests().len(),
+                                Pane::Explorer => self.explorer_rows().len(),
+                                _ => self.coverage_report().map_or(0, |report| report.files.len()),
+                            };
+                            if self.selected_row + 1 < count {
+                                self.selected_row += 1;
+                            }
+                        }
+                        KeyCode::Char('r') => {
+                            if let Some((tool, instrumented)) = self
+                                .tools
+                                .get(self.selected_tool)
+                                .cloned()
+                                .and_then(|tool| self.prepare(&tool))
+                            {
+                                let sender = self.result_sender.clone();
+                                let directory = self.directory.clone();
+                                let cache = if instrumented { None } else { self.cache.clone() };
+                                tokio::spawn(async move {
+                                    runner::run_tool(&tool, &directory, cache.as_deref(), &sender).await;
+                                });
+                            }
+                        }
                         KeyCode::Char('a') => {
                             let sender = self.result_sender.clone();
-                            let tools = self.tools.clone();
+                            let tools: Vec<(Tool, bool)> = self
+                                .tools
+                                .clone()
+                                .iter()
+                                .filter(|tool| !tool.on_demand)
+                                .filter_map(|tool| self.prepare(tool))
+                                .collect();
                             let directory = self.directory.clone();
+                            let cache = self.cache.clone();
                             tokio::spawn(async move {
-                                for tool in tools {
-                                    if let Ok(result) = tool.run(&directory).await {
-                                        let _ = sender.send((tool.name, result)).await;
-                                    }
+                                for (tool, instrumented) in &tools {
+                                    // Replaying an instrumented run would not
+                                    // write its coverage report
+                                    let cache = if *instrumented { None } else { cache.as_deref() };
+                                    runner::run_tool(tool, &directory, cache, &sender).await;
                                 }
                             });
                         }
-                        KeyCode::Up => {
-                            if self.selected_tool > 0 {
-                                self.selected_tool -= 1;
-                                self.tools_state.select(Some(self.selected_tool));
-                            }
+                        KeyCode::Up if self.selected_tool > 0 => {
+                            self.selected_tool -= 1;
+                            self.tools_state.select(Some(self.selected_tool));
                         }
-                        KeyCode::Down => {
-                            if self.selected_tool < self.tools.len().saturating_sub(1) {
-                                self.selected_tool += 1;
-                                self.tools_state.select(Some(self.selected_tool));
-                            }
+                        KeyCode::Down
+                            if self.selected_tool < self.tools.len().saturating_sub(1) =>
+                        {
+                            self.selected_tool += 1;
+                            self.tools_state.select(Some(self.selected_tool));
                         }
                         _ => {}
                     }
@@ -91,13 +391,758 @@ impl App {
         }
 
         // Check for new results
-        while let Ok((tool_name, result)) = self.result_receiver.try_recv() {
-            self.results.push((tool_name, result));
+        while let Ok(event) = self.result_receiver.try_recv() {
+            self.apply_event(event);
+        }
+
+        if let Ok(preview) = self.fix_receiver.try_recv() {
+            match preview {
+                Ok(preview) if preview.changes.is_empty() => {
+                    self.status_message = Some(format!("{}: nothing to fix", preview.tool));
+                }
+                Ok(preview) => {
+                    self.status_message = preview.note.clone();
+                    self.fix_scroll = 0;
+                    self.fix_preview = Some(preview);
+                }
+                Err(err) => self.status_message = Some(format!("{:#}", err)),
+            }
+        }
+
+        while let Ok((tool, availability)) = self.availability_receiver.try_recv() {
+            self.availability.insert(tool, availability);
+        }
+
+        if let Ok((tool, tests)) = self.explorer_receiver.try_recv() {
+            match (tests, &mut self.explorer) {
+                (Ok(tests), Some(explorer)) if explorer.tool == tool => {
+                    self.status_message = Some(format!("{} tests of {}", tests.len(), tool));
+                    explorer.tests = Some(tests);
+                }
+                (Err(err), Some(explorer)) if explorer.tool == tool => {
+                    self.status_message = Some(format!("{:#}", err));
+                    explorer.tests = Some(Vec::new());
+                }
+                _ => {}
+            }
+        }
+
+        if let Some(changed) = self.watcher.as_mut().and_then(ChangeWatcher::poll) {
+            self.watch_pending.extend(changed);
+        }
+        if !self.watch_pending.is_empty() {
+            self.rerun_affected();
         }
 
         Ok(())
     }
 
+    /// Run the selected tool's fix in a scratch copy; the diff is shown once
+    /// it arrives on the fix channel
+    fn preview_fix(&mut self) {
+        let Some(tool) = self.tools.get(self.selected_tool).cloned() else {
+            return;
+        };
+        if tool.fix.is_none() {
+            self.status_message = Some(format!("{} has no fix", tool.name));
+            return;
+        }
+        self.status_message = Some(format!("Running the {} fix in a scratch copy...", tool.name));
+        let sender = self.fix_sender.clone();
+        let directory = self.directory.clone();
+        let paths = self.paths.clone();
+        tokio::spawn(async move {
+            let _ = sender.send(fix::preview(&tool, &directory, &paths).await).await;
+        });
+    }
+
+    fn handle_fix_preview_key(&mut self, key: KeyCode) {
+        match key {
+            KeyCode::Char('y') | KeyCode::Enter => {
+                let Some(preview) = self.fix_preview.take() else {
+                    return;
+                };
+                match preview.apply(&self.directory) {
+                    Ok(applied) => {
+                        self.status_message = Some(format!(
+                            "Applied {} fix to {} files (u: undo)",
+                            applied.tool,
+                            preview.changes.len()
+                        ));
+                        self.applied_fixes.push(applied);
+                        self.verifying = self.expected_findings(&preview);
+                        self.rerun(&preview.tool);
+                    }
+                    Err(err) => self.status_message = Some(format!("{:#}", err)),
+                }
+            }
+            KeyCode::Char('n') | KeyCode::Esc => {
+                self.fix_preview = None;
+                self.status_message = Some("Fix discarded".to_string());
+            }
+            KeyCode::Up => self.fix_scroll = self.fix_scroll.saturating_sub(1),
+            KeyCode::Down => self.fix_scroll = self.fix_scroll.saturating_add(1),
+            KeyCode::PageUp => self.fix_scroll = self.fix_scroll.saturating_sub(20),
+            KeyCode::PageDown => self.fix_scroll = self.fix_scroll.saturating_add(20),
+            _ => {}
+        }
+    }
+
+    /// The findings of the latest finished run of the selected tool
+    fn selected_findings(&self) -> &[Finding] {
+        match self.tools.get(self.selected_tool) {
+            Some(tool) => self.latest_findings(&tool.name),
+            None => &[],
+        }
+    }
+
+    /// The findings of the latest finished run of the tool named `name`
+    fn latest_findings(&self, name: &str) -> &[Finding] {
+        self.results
+            .iter()
+            .rev()
+            .find(|result| result.tool == name && result.status.is_some())
+            .map_or(&[], |result| &result.findings)
+    }
+
+    /// Collect the compiler's machine-applicable suggestions for the selected
+    /// finding, or with `whole_lint` for every finding of its lint, and show
+    /// the edits once they arrive on the fix channel
+    fn preview_suggestions(&mut self, whole_lint: bool) {
+        let Some(tool) = self.tools.get(self.selected_tool).cloned() else {
+            return;
+        };
+        let Some(finding) = self.selected_findings().get(self.selected_row).cloned() else {
+            self.status_message = Some("No finding selected".to_string());
+            return;
+        };
+        if !suggestions::supports(&tool) {
+            self.status_message = Some(format!("{} does not make suggestions", tool.name));
+            return;
+        }
+        if whole_lint && finding.rule.is_none() {
+            self.status_message = Some("The finding has no lint to apply all suggestions of".to_string());
+            return;
+        }
+        self.status_message = Some(format!("Collecting suggestions from {}...", tool.name));
+        let sender = self.fix_sender.clone();
+        let directory = self.directory.clone();
+        tokio::spawn(async move {
+            let preview = async {
+                let found: Vec<_> = suggestions::collect(&tool, &directory)
+                    .await?
+                    .into_iter()
+                    .filter(|suggestion| match whole_lint {
+                        true => suggestion.code == finding.rule,
+                        false => suggestion.matches(&finding),
+                    })
+                    .collect();
+                if found.is_empty() {
+                    anyhow::bail!("No machine-applicable suggestion for this finding");
+                }
+                suggestions::preview(&tool, &directory, &found)
+            };
+            let _ = sender.send(preview.await).await;
+        });
+    }
+
+    /// What to check after applying `preview`. Findings are counted per file
+    /// and message rather than located by line, since the fix moves lines and
+    /// the same message often appears several times in a file.
+    fn expected_findings(
+        &self,
+        preview: &FixPreview,
+    ) -> Option<Verification> {
+        if preview.resolves.is_empty() {
+            return None;
+        }
+        let findings = self.latest_findings(&preview.tool);
+        let mut expected: Vec<(String, String, usize, usize)> = Vec::new();
+        for (file, message) in &preview.resolves {
+            match expected.iter_mut().find(|(f, m, ..)| f == file && m == message) {
+                Some(entry) => entry.2 += 1,
+                None => expected.push((file.clone(), message.clone(), 1, 0)),
+            }
+        }
+        for (file, message, resolved, left) in &mut expected {
+            let count = findings
+                .iter()
+                .filter(|finding| &finding.file == file && &finding.message == message)
+                .count();
+            *left = count.saturating_sub(*resolved);
+        }
+        Some(Verification {
+            tool: preview.tool.clone(),
+            expected,
+        })
+    }
+
+    /// Report whether the rerun of `tool` still has the findings the applied
+    /// fix was meant to resolve
+    fn verify_fix(&mut self, tool: &str) {
+        if self.verifying.as_ref().is_none_or(|v| v.tool != tool) {
+            return;
+        }
+        let Some(Verification { expected, .. }) = self.verifying.take() else {
+            return;
+        };
+        let findings = self.latest_findings(tool);
+        let mut remaining = 0;
+        for (file, message, resolved, left) in &expected {
+            let count = findings
+                .iter()
+                .filter(|finding| &finding.file == file && &finding.message == message)
+                .count();
+            remaining += count.saturating_sub(*left).min(*resolved);
+        }
+        let total: usize = expected.iter().map(|(_, _, resolved, _)| resolved).sum();
+        self.status_message = Some(match remaining {
+            0 => format!("Verified: {} findings resolved", total),
+            remaining => format!("{} of {} findings remain after the fix", remaining, total),
+        });
+    }
+
+    /// Restore the files changed by the most recently applied fix
+    fn undo_fix(&mut self) {
+        let Some(applied) = self.applied_fixes.pop() else {
+            self.status_message = Some("No fix to undo".to_string());
+            return;
+        };
+        match applied.undo(&self.directory) {
+            Ok(()) => {
+                self.status_message = Some(format!("Undid {} fix", applied.tool));
+                self.rerun(&applied.tool);
+            }
+            Err(err) => {
+                self.status_message = Some(format!("{:#}", err));
+                self.applied_fixes.push(applied);
+            }
+        }
+    }
+
+    /// Run the tool named `name` again to refresh its result
+    fn rerun(&self, name: &str) {
+        let Some(tool) = self
+            .tools
+            .iter()
+            .find(|tool| tool.name == name)
+            .and_then(|tool| self.scope(tool))
+        else {
+            return;
+        };
+        let sender = self.result_sender.clone();
+        let directory = self.directory.clone();
+        let cache = self.cache.clone();
+        tokio::spawn(async move {
+            runner::run_tool(&tool, &directory, cache.as_deref(), &sender).await;
+        });
+    }
+
+    /// Turn watch mode on or off
+    fn toggle_watch(&mut self) {
+        if self.watcher.take().is_some() {
+            self.watch_pending.clear();
+            self.status_message = None;
+            return;
+        }
+        match ChangeWatcher::new(&self.directory, self.paths.patterns()) {
+            Ok(watcher) => {
+                self.watcher = Some(watcher);
+                self.status_message = Some(format!("Watching {}", self.directory.display()));
+            }
+            Err(err) => self.status_message = Some(format!("{:#}", err)),
+        }
+    }
+
+    /// Rerun the tools whose inputs are among the pending changed paths.
+    /// Waits while any of them is still running so that a run never overlaps
+    /// with another run of the same tool.
+    fn rerun_affected(&mut self) {
+        let affected: Vec<&Tool> = self
+            .tools
+            .iter()
+            .filter(|tool| !tool.on_demand && tool.is_affected_by(&self.watch_pending))
+            .collect();
+        let running = affected.iter().any(|tool| {
+            self.results
+                .iter()
+                .any(|result| result.tool == tool.name && result.status.is_none())
+        });
+        if running {
+            return;
+        }
+        self.status_message = Some(format!("Changed: {}", self.watch_pending.join(", ")));
+        self.watch_pending.clear();
+        if self.changes.is_some() {
+            // Edits change the change set too
+            self.changes = ChangeSet::since(&self.directory, &self.changed_base, &self.paths).ok();
+        }
+
+        let tools: Vec<Tool> = affected.into_iter().filter_map(|tool| self.scope(tool)).collect();
+        if tools.is_empty() {
+            return;
+        }
+        let sender = self.result_sender.clone();
+        let directory = self.directory.clone();
+        let cache = self.cache.clone();
+        tokio::spawn(async move {
+            runner::run_tools(&tools, &directory, cache.as_deref(), &sender).await;
+        });
+    }
+
+    fn apply_event(&mut self, event: RunEvent) {
+        match event {
+            RunEvent::ToolStarted {
+                tool,
+                category,
+                args,
+                ..
+            } => self.results.push(ToolResult {
+                tool,
+                category,
+                args,
+                status: None,
+                output: Vec::new(),
+                findings: Vec::new(),
+                hidden_findings: 0,
+                baselined: 0,
+                cached: false,
+                version: None,
+                exit_code: None,
+            }),
+            RunEvent::OutputLine { tool, line, .. } => {
+                if let Some(result) = self.running_result(&tool) {
+                    result.output.push(line);
+                }
+            }
+            RunEvent::ToolFinished {
+                tool,
+                status,
+                exit_code,
+                duration_ms,
+                findings,
+                cached,
+                version,
+            } => {
+                let directory = self.directory.clone();
+                let baseline = self.baseline.clone();
+                if let Some(result) = self.running_result(&tool) {
+                    result.status = Some(status);
+                    result.cached = cached;
+                    result.version = version.clone();
+                    result.exit_code = exit_code;
+                    if let Some(baseline) = &baseline {
+                        result.baselined = baseline.filter(&directory, &mut result.findings);
+                    }
+                }
+                // A tool that ran is installed, whatever the last probe said
+                if status != ToolStatus::Skipped
+                    && self.availability.get(&tool).is_some_and(|a| a.problem().is_some())
+                {
+                    self.availability.insert(
+                        tool.clone(),
+                        Availability::Installed {
+                            banner: version.clone().unwrap_or_default(),
+                            version: version.clone(),
+                        },
+                    );
+                }
+                if let Some(run) = self.coverage_runs.remove(&tool) {
+                    match Coverage::read(&run, &self.directory) {
+                        Ok(coverage) => {
+                            self.coverage.insert(tool.clone(), coverage);
+                        }
+                        Err(err) if status != ToolStatus::Skipped => {
+                            self.status_message = Some(format!("{:#}", err));
+                        }
+                        Err(_) => {}
+                    }
+                }
+                self.record_history(history::Entry {
+                    tool: tool.clone(),
+                    status,
+                    exit_code,
+                    duration_ms,
+                    findings,
+                    cached,
+                });
+                self.verify_fix(&tool);
+            }
+            RunEvent::Diagnostic(finding) => {
+                let visible = self
+                    .changes
+                    .as_ref()
+                    .is_none_or(|changes| changes.contains(&finding, ChangeScope::Lines));
+                if let Some(result) = self.running_result(&finding.tool) {
+                    if visible {
+                        result.findings.push(finding);
+                    } else {
+                        result.hidden_findings += 1;
+                    }
+                }
+            }
+            RunEvent::Summary(_) | RunEvent::Gate(_) => {}
+        }
+    }
+
+    /// Add a tool outcome, and the outcomes of its tests, to the run
+    /// history. Runs restricted to changed files are left out of the trends,
+    /// as their counts would distort them; their tests are still recorded.
+    fn record_history(&mut self, entry: history::Entry) {
+        let Some(history) = self.history.clone() else {
+            return;
+        };
+        let (Some(tool), Some(result)) = (
+            self.tools.iter().find(|tool| tool.name == entry.tool),
+            self.results.iter().rev().find(|result| result.tool == entry.tool),
+        ) else {
+            return;
+        };
+        let whole_suite = self.changes.is_none() && result.args == tool.args;
+        let mut recorded = match whole_suite {
+            true => history.record(&self.directory, std::slice::from_ref(&entry)),
+            false => Ok(()),
+        };
+        if !entry.cached {
+            let selected = self.targeted.get(&result.args).cloned().unwrap_or_default();
+            recorded = recorded.and_then(|()| {
+                history.record_test_output(
+                    &self.directory,
+                    tool,
+                    entry.status,
+                    &result.output.join("\n"),
+                    whole_suite,
+                    &selected,
+                )
+            });
+            self.flaky = history.flaky_tests(&self.directory).unwrap_or_default();
+        }
+        if let Err(err) = recorded {
+            self.status_message = Some(format!("Could not record run history: {:#}", err));
+        }
+        self.history_records = None;
+    }
+
+    /// Show `pane` on the right, or the results if it is already shown
+    fn toggle_pane(&mut self, pane: Pane) {
+        self.pane = if self.pane == pane { Pane::Results } else { pane };
+        self.selected_row = 0;
+    }
+
+    /// The tests of the latest finished run of the selected tool
+    fn selected_tests(&self) -> Vec<TestCase> {
+        let Some(tool) = self.tools.get(self.selected_tool) else {
+            return Vec::new();
+        };
+        let Some(runner) = TestRunner::of(tool) else {
+            return Vec::new();
+        };
+        self.results
+            .iter()
+            .rev()
+            .find(|result| result.tool == tool.name && result.status.is_some())
+            .map(|result| parse_tests(runner, &result.output.join("\n")))
+            .unwrap_or_default()
+    }
+
+    /// Run the failing tests of the selected test tool's latest run
+    /// `flaky_reruns` times. Tests that pass on some of the runs are then
+    /// flagged as flaky.
+    fn rerun_failing_tests(&mut self) {
+        self.rerun_failures(self.flaky_reruns);
+    }
+
+    /// Run only the tests that failed in the selected test tool's latest run,
+    /// `times` times in a row, bypassing the cache
+    fn rerun_failures(&mut self, times: usize) {
+        let Some(tool) = self.tools.get(self.selected_tool).cloned() else {
+            return;
+        };
+        let failed: Vec<String> = self
+            .selected_tests()
+            .into_iter()
+            .filter(|test| test.outcome == TestOutcome::Failed)
+            .map(|test| test.name)
+            .collect();
+        let failed_run = self
+            .results
+            .iter()
+            .rev()
+            .find(|result| result.tool == tool.name && result.status.is_some())
+            .is_some_and(|result| result.status == Some(ToolStatus::Failed));
+        if !failed_run {
+            self.status_message = Some(format!("{} has no failures to rerun", tool.name));
+            return;
+        }
+        let Some(tool) = rerun_failed(&tool, &failed) else {
+            self.status_message = Some(format!("No failed tests of {} to rerun", tool.name));
+            return;
+        };
+        self.targeted.insert(tool.args.clone(), failed.clone());
+        let repeat = match times {
+            1 => String::new(),
+            times => format!(" {} times", times),
+        };
+        self.status_message = Some(match failed.len() {
+            0 => format!("Rerunning the last failures of {}{}", tool.name, repeat),
+            count => format!("Rerunning {} failed tests of {}{}", count, tool.name, repeat),
+        });
+        let sender = self.result_sender.clone();
+        let directory = self.directory.clone();
+        tokio::spawn(async move {
+            for _ in 0..times {
+                runner::run_tool(&tool, &directory, None, &sender).await;
+            }
+        });
+    }
+
+    /// Show the test explorer for the selected tool, listing its tests if
+    /// they have not been listed yet, or hide it
+    fn toggle_explorer(&mut self) {
+        self.toggle_pane(Pane::Explorer);
+        if self.pane != Pane::Explorer {
+            return;
+        }
+        let Some(tool) = self.tools.get(self.selected_tool).cloned() else {
+            return;
+        };
+        if TestRunner::of(&tool).is_none() {
+            self.status_message = Some(format!("{} is not a test runner", tool.name));
+            self.pane = Pane::Results;
+            return;
+        }
+        // A listing that failed or found nothing is retried
+        let listed = self.explorer.as_ref().is_some_and(|explorer| {
+            explorer.tool == tool.name && explorer.tests.as_ref().is_none_or(|tests| !tests.is_empty())
+        });
+        if listed {
+            return;
+        }
+        self.explorer = Some(Explorer {
+            tool: tool.name.clone(),
+            tests: None,
+            filter: String::new(),
+            filtering: false,
+        });
+        self.status_message = Some(format!("Listing the tests of {}...", tool.name));
+        let sender = self.explorer_sender.clone();
+        let directory = self.directory.clone();
+        tokio::spawn(async move {
+            let tests = explorer::discover(&tool, &directory).await;
+            let _ = sender.send((tool.name, tests)).await;
+        });
+    }
+
+    fn handle_filter_key(&mut self, key: KeyCode) {
+        let Some(explorer) = &mut self.explorer else {
+            return;
+        };
+        match key {
+            KeyCode::Char(c) => explorer.filter.push(c),
+            KeyCode::Backspace => {
+                explorer.filter.pop();
+            }
+            KeyCode::Enter => explorer.filtering = false,
+            KeyCode::Esc => {
+                explorer.filter.clear();
+                explorer.filtering = false;
+            }
+            _ => return,
+        }
+        self.selected_row = 0;
+    }
+
+    /// The rows of the explorer tree that match its filter
+    fn explorer_rows(&self) -> Vec<explorer::Row> {
+        let Some(explorer) = &self.explorer else {
+            return Vec::new();
+        };
+        let Some(runner) = self.tools.iter().find(|tool| tool.name == explorer.tool).and_then(TestRunner::of)
+        else {
+            return Vec::new();
+        };
+        explorer::rows(runner, explorer.tests.as_deref().unwrap_or_default(), &explorer.filter)
+    }
+
+    /// The latest outcome of every test of `tool` across its finished runs,
+    /// so a run of one test updates only that test
+    fn test_outcomes(&self, tool: &str) -> HashMap<String, TestOutcome> {
+        let Some(runner) = self.tools.iter().find(|t| t.name == tool).and_then(TestRunner::of) else {
+            return HashMap::new();
+        };
+        let mut outcomes = HashMap::new();
+        for result in self.results.iter().filter(|r| r.tool == tool && r.status.is_some()) {
+            for test in parse_tests(runner, &result.output.join("\n")) {
+                outcomes.insert(test.name, test.outcome);
+            }
+        }
+        outcomes
+    }
+
+    /// Run the tests under the selected explorer row
+    fn run_explored(&mut self) {
+        let Some(row) = self.explorer_rows().into_iter().nth(self.selected_row) else {
+            return;
+        };
+        let Some(tool) = self
+            .explorer
+            .as_ref()
+            .and_then(|explorer| self.tools.iter().find(|tool| tool.name == explorer.tool))
+            .and_then(|tool| restricted_to(tool, &row.tests))
+        else {
+            return;
+        };
+        self.targeted.insert(tool.args.clone(), row.tests.clone());
+        self.status_message = Some(match row.tests.len() {
+            1 => format!("Running {}", row.tests[0]),
+            count => format!("Running {} tests under {}", count, row.label),
+        });
+        let sender = self.result_sender.clone();
+        let directory = self.directory.clone();
+        let cache = self.cache.clone();
+        tokio::spawn(async move {
+            runner::run_tool(&tool, &directory, cache.as_deref(), &sender).await;
+        });
+    }
+
+    /// The recorded outcomes of the selected tool, oldest first
+    fn selected_history(&mut self) -> &[Record] {
+        let Some(tool) = self.tools.get(self.selected_tool).map(|tool| tool.name.clone()) else {
+            return &[];
+        };
+        if self.history_records.as_ref().is_none_or(|(name, _)| *name != tool) {
+            let records = self
+                .history
+                .as_ref()
+                .and_then(|history| history.recent(&self.directory, 60).ok())
+                .and_then(|mut recent| recent.remove(&tool))
+                .unwrap_or_default();
+            self.history_records = Some((tool, records));
+        }
+        self.history_records.as_ref().map_or(&[], |(_, records)| records)
+    }
+
+    /// Turn "changed only" on or off, reading the change set from git
+    fn toggle_changed_only(&mut self) {
+        if self.changes.take().is_some() {
+            self.status_message = None;
+            return;
+        }
+        match ChangeSet::since(&self.directory, &self.changed_base, &self.paths) {
+            Ok(changes) => {
+                self.status_message = Some(format!(
+                    "{} files changed since {}",
+                    changes.files().len(),
+                    self.changed_base
+                ));
+                self.changes = Some(changes);
+            }
+            Err(err) => self.status_message = Some(format!("{:#}", err)),
+        }
+    }
+
+    /// `tool` as it should run on `r` or `a`: scoped (see `scope`) and, in
+    /// coverage mode, instrumented if it is a test tool, along with whether
+    /// it was instrumented
+    fn prepare(&mut self, tool: &Tool) -> Option<(Tool, bool)> {
+        let tool = self.scope(tool)?;
+        if !self.coverage_mode || TestRunner::of(&tool).is_none() {
+            return Some((tool, false));
+        }
+        if self.coverage_dir.is_none() {
+            match tempfile::tempdir() {
+                Ok(dir) => self.coverage_dir = Some(Arc::new(dir)),
+                Err(err) => {
+                    self.status_message = Some(format!("Could not create a coverage directory: {}", err));
+                    return Some((tool, false));
+                }
+            }
+        }
+        let report_dir = self.coverage_dir.as_ref()?.path().to_path_buf();
+        match coverage::instrument(&tool, &self.directory, &report_dir) {
+            Ok(run) => {
+                let instrumented = run.tool.clone();
+                self.coverage_runs.insert(tool.name.clone(), run);
+                Some((instrumented, true))
+            }
+            Err(err) => {
+                self.status_message = Some(format!("{:#}", err));
+                Some((tool, false))
+            }
+        }
+    }
+
+    /// The coverage of the latest instrumented run of every test tool,
+    /// restricted to the change set while "changed only" is on
+    fn coverage_report(&self) -> Option<CoverageReport> {
+        let mut merged = Coverage::default();
+        for coverage in self.coverage.values() {
+            merged.merge(coverage.clone());
+        }
+        (!merged.is_empty()).then(|| merged.report(self.changes.as_ref()))
+    }
+
+    /// `tool` as it should run now: restricted to changed files while
+    /// "changed only" is on, or `None` if no changed file concerns it
+    fn scope(&self, tool: &Tool) -> Option<Tool> {
+        match &self.changes {
+            Some(changes) => tool.scoped_to(&changes.files()),
+            None => Some(tool.clone()),
+        }
+    }
+
+    /// The quality gate over the latest finished result of each tool, or
+    /// `None` before anything has finished
+    fn gate(&self) -> Option<GateOutcome> {
+        let mut latest: Vec<&ToolResult> = Vec::new();
+        for result in self.results.iter().rev().filter(|r| r.status.is_some()) {
+            if !latest.iter().any(|r| r.tool == result.tool) {
+                latest.push(result);
+            }
+        }
+        if latest.is_empty() {
+            return None;
+        }
+        let mut outcome = gates::evaluate(
+            &self.gates,
+            self.draft,
+            latest.into_iter().filter_map(|result| {
+                // Failures caused only by findings outside the change set
+                // or in the baseline do not count, matching headless runs
+                let status = match result.status? {
+                    ToolStatus::Failed
+                        if result.hidden_findings + result.baselined > 0
+                            && result.findings.is_empty()
+                            && self.tools.iter().any(|tool| {
+                                tool.name == result.tool && tool.failed_on_findings(result.exit_code)
+                            }) =>
+                    {
+                        ToolStatus::Passed
+                    }
+                    status => status,
+                };
+                Some(GateInput {
+                    tool: &result.tool,
+                    category: &result.category,
+                    status,
+                    findings: &result.findings,
+                })
+            }),
+        );
+        if let Some(coverage) = self.coverage_report() {
+            gates::check_coverage(&self.gates, self.draft, &coverage, &mut outcome);
+        }
+        Some(outcome)
+    }
+
+    /// The most recent result of `tool` that has not finished yet
+    fn running_result(&mut self, tool: &str) -> Option<&mut ToolResult> {
+        self.results
+            .iter_mut()
+            .rev()
+            .find(|r| r.tool == tool && r.status.is_none())
+    }
+
     pub fn draw(&mut self, f: &mut Frame) {
         let chunks = Layout::default()
             .direction(Direction::Vertical)
@@ -115,20 +1160,44 @@ impl App {
             ])
             .split(chunks[0]);
 
-        // Tools list
-        let tools: Vec<ListItem> = self
-            .tools
-            .iter()
-            .enumerate()
-            .map(|(i, tool)| {
-                let style = if i == self.selected_tool {
-                    Style::default().add_modifier(Modifier::REVERSED)
-                } else {
-                    Style::default()
-                };
-                ListItem::new(tool.name.clone()).style(style)
-            })
-            .collect();
+        // Tools list, grouped under a header per project when there are
+        // nested projects
+        let grouped = self.tools.iter().any(|tool| !tool.project.is_empty());
+        let mut tools: Vec<ListItem> = Vec::new();
+        let mut selected_item = None;
+        let mut project = None;
+        for (i, tool) in self.tools.iter().enumerate() {
+            if grouped && project != Some(&tool.project) {
+                project = Some(&tool.project);
+                let header = if tool.project.is_empty() { "." } else { &tool.project };
+                tools.push(
+                    ListItem::new(header.to_string())
+                        .style(Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)),
+                );
+            }
+            // Tools whose program is missing are greyed out
+            let missing = self.availability.get(&tool.name).is_some_and(|a| a.problem().is_some());
+            let style = if missing {
+                Style::default().fg(Color::DarkGray)
+            } else {
+                Style::default()
+            };
+            let style = if i == self.selected_tool {
+                selected_item = Some(tools.len());
+                style.add_modifier(Modifier::REVERSED)
+            } else {
+                style
+            };
+            let label = match tool.name.strip_prefix(&format!("{}/", tool.project)) {
+                Some(name) if grouped => format!("  {}", name),
+                _ if grouped => format!("  {}", tool.name),
+                _ => tool.name.clone(),
+            };
+            let label = if missing { format!("{} (not installed)", label) } else { label };
+            tools.push(ListItem::new(label).style(style));
+        }
+        // Keep the selection in view, accounting for the headers
+        self.tools_state.select(selected_item);
 
         let tools_list = List::new(tools)
             .block(Block::default().title("Tools").borders(Borders::ALL));
@@ -138,24 +1207,99 @@ impl App {
         let results: Vec<ListItem> = self
             .results
             .iter()
-            .map(|(tool, result)| {
-                ListItem::new(format!("[{}]\n{}", tool, result))
-                    .style(Style::default().fg(Color::Green))
+            .map(|result| {
+                let (label, color) = match result.status {
+                    None => ("RUNNING", Color::Cyan),
+                    Some(ToolStatus::Passed) => (ToolStatus::Passed.label(), Color::Green),
+                    Some(ToolStatus::Failed) => (ToolStatus::Failed.label(), Color::Red),
+                    Some(ToolStatus::Skipped) => (ToolStatus::Skipped.label(), Color::Yellow),
+                };
+                ListItem::new(format!(
+                    "[{}{}] {}{}{}\n{}",
+                    result.tool,
+                    result.version.as_deref().map(|v| format!(" {}", v)).unwrap_or_default(),
+                    label,
+                    if result.cached { " (cached)" } else { "" },
+                    match result.baselined {
+                        0 => String::new(),
+                        baselined => format!(" ({} baselined)", baselined),
+                    },
+                    result.output.join("\n")
+                ))
+                .style(Style::default().fg(color))
             })
             .collect();
 
-        let results_list = List::new(results)
-            .block(Block::default().title("Results").borders(Borders::ALL));
-        f.render_stateful_widget(results_list, main_chunks[1], &mut self.results_state);
+        if self.pane == Pane::History {
+            self.draw_history(f, main_chunks[1]);
+        } else if self.pane == Pane::Tests {
+            self.draw_tests(f, main_chunks[1]);
+        } else if self.pane == Pane::Explorer {
+            self.draw_explorer(f, main_chunks[1]);
+        } else if self.pane == Pane::Coverage {
+            self.draw_coverage(f, main_chunks[1]);
+        } else if self.pane == Pane::Doctor {
+            self.draw_doctor(f, main_chunks[1]);
+        } else if self.pane == Pane::Findings {
+            let findings: Vec<ListItem> = self
+                .selected_findings()
+                .iter()
+                .enumerate()
+                .map(|(i, finding)| {
+                    let location = match (finding.line, finding.column) {
+                        (Some(line), Some(column)) => format!("{}:{}:{}", finding.file, line, column),
+                        (Some(line), None) => format!("{}:{}", finding.file, line),
+                        _ => finding.file.clone(),
+                    };
+                    let rule = finding.rule.as_deref().map(|r| format!(" [{}]", r)).unwrap_or_default();
+                    let style = if i == self.selected_row {
+                        Style::default().add_modifier(Modifier::REVERSED)
+                    } else {
+                        Style::default()
+                    };
+                    ListItem::new(format!(
+                        "{} {}{}: {}",
+                        location, finding.severity, rule, finding.message
+                    ))
+                    .style(style)
+                })
+                .collect();
+            self.row_state.select(Some(self.selected_row));
+            let findings_list = List::new(findings).block(
+                Block::default()
+                    .title("Findings | s: apply suggestion  S: apply all for lint  v: back")
+                    .borders(Borders::ALL),
+            );
+            f.render_stateful_widget(findings_list, main_chunks[1], &mut self.row_state);
+        } else {
+            let results_list = List::new(results)
+                .block(Block::default().title("Results").borders(Borders::ALL));
+            f.render_stateful_widget(results_list, main_chunks[1], &mut self.results_state);
+        }
 
         // Help menu
         if self.show_help {
             // Calculate the size of the help window
-            let help_text = vec![
+            let help_text = [
                 "Key Bindings:".to_string(),
                 "  ↑/↓    - Navigate tools".to_string(),
                 "  r      - Run selected tool".to_string(),
                 "  a      - Run all tools".to_string(),
+                "  d      - Toggle draft gate policies".to_string(),
+                "  c      - Toggle changed files/lines only".to_string(),
+                "  w      - Toggle watch mode".to_string(),
+                "  f      - Preview the selected tool's fix".to_string(),
+                "  u      - Undo the last applied fix".to_string(),
+                "  v      - Toggle the findings of the selected tool".to_string(),
+                "  h      - Toggle the run history of the selected tool".to_string(),
+                "  t      - Toggle the tests of the selected tool".to_string(),
+                "  F      - Rerun only the failed tests".to_string(),
+                "  R      - Rerun failing tests to detect flaky ones".to_string(),
+                "  e      - Toggle the test explorer (/: filter, Enter: run)".to_string(),
+                "  C      - Toggle coverage collection for test tools".to_string(),
+                "  o      - Toggle the per-file coverage".to_string(),
+                "  D      - Toggle why each tool was or was not detected".to_string(),
+                "  s/S    - Apply the finding's suggestion / all for its lint".to_string(),
                 "  ?      - Toggle this help menu".to_string(),
                 "  q      - Quit".to_string(),
             ];
@@ -164,6 +1308,9 @@ impl App {
 
             // Center the help window
             let help_area = centered_rect(help_width, help_height, f.size());
+            // On a short terminal the help scrolls instead
+            let hidden = help_height.saturating_sub(help_area.height);
+            self.help_scroll = self.help_scroll.min(hidden);
             
             // Create shadow effect by drawing a slightly offset dark rectangle
             let shadow_area = Rect {
@@ -171,16 +1318,21 @@ impl App {
                 y: help_area.y + 1,
                 width: help_area.width,
                 height: help_area.height,
-            };
+            }
+            .intersection(f.size());
             let shadow = Block::default()
                 .style(Style::default().bg(Color::Rgb(0, 0, 0)));
             f.render_widget(shadow, shadow_area);
             
             // Create the help window with a semi-transparent background
             let help = Paragraph::new(help_text.join("\n"))
+                .scroll((self.help_scroll, 0))
                 .block(
                     Block::default()
-                        .title("Help")
+                        .title(match hidden {
+                            0 => "Help",
+                            _ => "Help (↑/↓: scroll)",
+                        })
                         .borders(Borders::ALL)
                         .style(Style::default().bg(Color::Rgb(32, 32, 32)).fg(Color::White))
                 )
@@ -189,22 +1341,448 @@ impl App {
             f.render_widget(help, help_area);
         }
 
+        if let Some(preview) = &self.fix_preview {
+            let area = centered_rect(
+                f.size().width.saturating_sub(8),
+                f.size().height.saturating_sub(6),
+                f.size(),
+            );
+            let diff = preview.diff();
+            let lines: Vec<Line> = diff
+                .lines()
+                .map(|line| {
+                    let color = if line.starts_with("+++") || line.starts_with("---") {
+                        Color::White
+                    } else if line.starts_with('+') {
+                        Color::Green
+                    } else if line.starts_with('-') {
+                        Color::Red
+                    } else if line.starts_with("@@") {
+                        Color::Cyan
+                    } else {
+                        Color::Gray
+                    };
+                    Line::styled(line.to_string(), Style::default().fg(color))
+                })
+                .collect();
+            f.render_widget(Clear, area);
+            f.render_widget(
+                Paragraph::new(lines)
+                    .scroll((self.fix_scroll, 0))
+                    .block(
+                        Block::default()
+                            .title(format!(
+                                "Fix preview: {} ({} files) | y: apply  n: discard  ↑/↓: scroll",
+                                preview.tool,
+                                preview.changes.len()
+                            ))
+                            .borders(Borders::ALL),
+                    ),
+                area,
+            );
+        }
+
         // Status bar with key hints
         let status_text = if self.show_help {
             "Press ? to hide help"
         } else {
             "Press ? for help | ↑/↓: Navigate | r: Run | a: Run All | q: Quit"
         };
-        let status = Paragraph::new(status_text)
+        let mut status_spans = Vec::new();
+        if let Some(gate) = self.gate() {
+            let (label, color) = if gate.passed {
+                (" GATE PASS ", Color::Green)
+            } else {
+                (" GATE FAIL ", Color::Red)
+            };
+            status_spans.push(Span::styled(
+                label,
+                Style::default().fg(Color::Black).bg(color).add_modifier(Modifier::BOLD),
+            ));
+            status_spans.push(Span::raw(" "));
+        }
+        if self.draft {
+            status_spans.push(Span::styled("DRAFT ", Style::default().fg(Color::Yellow)));
+        }
+        if self.watcher.is_some() {
+            status_spans.push(Span::styled("WATCH ", Style::default().fg(Color::Magenta)));
+        }
+        if self.coverage_mode {
+            status_spans.push(Span::styled("COVERAGE ", Style::default().fg(Color::Green)));
+        }
+        // What the selected tool runs, or what keeps it from running
+        if let Some(tool) = self.tools.get(self.selected_tool) {
+            match self.availability.get(&tool.name).and_then(Availability::problem) {
+                Some(problem) => status_spans.push(Span::styled(
+                    format!("{} ", problem),
+                    Style::default().fg(Color::Yellow),
+                )),
+                None => status_spans.push(Span::styled(
+                    format!("{} | ", tool.description),
+                    Style::default().fg(Color::DarkGray),
+                )),
+            }
+        }
+        if let Some(changes) = &self.changes {
+            status_spans.push(Span::styled(
+                format!("CHANGED since {} ", changes.base),
+                Style::default().fg(Color::Cyan),
+            ));
+        }
+        match &self.status_message {
+            Some(message) => status_spans.push(Span::raw(format!("{} | {}", message, status_text))),
+            None => status_spans.push(Span::raw(status_text)),
+        }
+        let status = Paragraph::new(Line::from(status_spans))
             .block(Block::default().borders(Borders::TOP))
             .style(Style::default().fg(Color::Gray));
         f.render_widget(status, chunks[1]);
     }
 }
 
+impl App {
+    /// Sparklines of the selected tool's duration and findings over its
+    /// recorded runs
+    fn draw_history(&mut self, f: &mut Frame, area: Rect) {
+        let name = self.tools.get(self.selected_tool).map(|tool| tool.name.clone()).unwrap_or_default();
+        let records = self.selected_history().to_vec();
+        let block = Block::default()
+            .title(format!("History: {} | h: back", name))
+            .borders(Borders::ALL);
+        let inner = block.inner(area);
+        f.render_widget(block, area);
+        let Some(last) = records.last() else {
+            f.render_widget(Paragraph::new("No runs recorded yet"), inner);
+            return;
+        };
+
+        let rows = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(2),
+                Constraint::Length(1),
+                Constraint::Length(6),
+                Constraint::Length(1),
+                Constraint::Length(6),
+                Constraint::Min(0),
+            ])
+            .split(inner);
+        // The most recent runs that fit, one column each
+        let shown = &records[records.len().saturating_sub(inner.width as usize)..];
+        let durations: Vec<u64> = shown.iter().map(|r| r.entry.duration_ms).collect();
+        let findings: Vec<u64> = shown.iter().map(|r| history::finding_total(&r.entry)).collect();
+        let FindingCounts { errors, warnings, .. } = last.entry.findings;
+        f.render_widget(
+            Paragraph::new(format!(
+                "{} runs, last {} {}{}",
+                records.len(),
+                last.entry.status.label(),
+                history::ago(last.recorded_at),
+                last.commit
+                    .as_deref()
+                    .map(|commit| format!(" at {}", &commit[..commit.len().min(8)]))
+                    .unwrap_or_default()
+            )),
+            rows[0],
+        );
+        f.render_widget(
+            Paragraph::new(format!(
+                "Duration (last {:.1}s, max {:.1}s)",
+                last.entry.duration_ms as f64 / 1000.0,
+                durations.iter().max().copied().unwrap_or(0) as f64 / 1000.0
+            )),
+            rows[1],
+        );
+        f.render_widget(
+            Sparkline::default().data(&durations).style(Style::default().fg(Color::Cyan)),
+            rows[2],
+        );
+        f.render_widget(
+            Paragraph::new(format!("Findings (last {} errors, {} warnings)", errors, warnings)),
+            rows[3],
+        );
+        f.render_widget(
+            Sparkline::default().data(&findings).style(Style::default().fg(Color::Yellow)),
+            rows[4],
+        );
+    }
+}
+
+impl App {
+    /// The tests of the selected tool's latest run, flaky ones marked with
+    /// their flip rate
+    fn draw_tests(&mut self, f: &mut Frame, area: Rect) {
+        let name = self.tools.get(self.selected_tool).map(|tool| tool.name.clone()).unwrap_or_default();
+        let tests = self.selected_tests();
+        let items: Vec<ListItem> = tests
+            .iter()
+            .enumerate()
+            .map(|(i, test)| {
+                let (mark, color) = match test.outcome {
+                    TestOutcome::Passed => ("✓", Color::Green),
+                    TestOutcome::Failed => ("✗", Color::Red),
+                    TestOutcome::Skipped => ("○", Color::Yellow),
+                };
+                let mut spans = vec![
+                    Span::styled(format!("{} ", mark), Style::default().fg(color)),
+                    Span::raw(test.name.clone()),
+                ];
+                if let Some(flaky) = self
+                    .flaky
+                    .iter()
+                    .find(|flaky| flaky.tool == name && flaky.test == test.name)
+                {
+                    spans.push(Span::styled(
+                        format!(" FLAKY {:.0}%", flaky.flip_rate * 100.0),
+                        Style::default().fg(Color::Magenta).add_modifier(Modifier::BOLD),
+                    ));
+                }
+                let style = if i == self.selected_row {
+                    Style::default().add_modifier(Modifier::REVERSED)
+                } else {
+                    Style::default()
+                };
+                ListItem::new(Line::from(spans)).style(style)
+            })
+            .collect();
+        self.row_state.select(Some(self.selected_row));
+        let title = match tests.is_empty() {
+            true => format!("Tests: {} | no test results yet; t: back", name),
+            false => format!(
+                "Tests: {} | F: rerun failures  R: rerun failures {} times  t: back",
+                name, self.flaky_reruns
+            ),
+        };
+        f.render_stateful_widget(
+            List::new(items).block(Block::default().title(title).borders(Borders::ALL)),
+            area,
+            &mut self.row_state,
+        );
+    }
+}
+
+impl App {
+    /// Every test of the explorer's tool as a tree, with the latest outcome
+    /// of each test and group
+    fn draw_explorer(&mut self, f: &mut Frame, area: Rect) {
+        let Some(explorer) = self.explorer.clone() else {
+            return;
+        };
+        let rows = self.explorer_rows();
+        let outcomes = self.test_outcomes(&explorer.tool);
+        let items: Vec<ListItem> = rows
+            .iter()
+            .enumerate()
+            .map(|(i, row)| {
+                let (mark, color) = match explorer::outcome(&outcomes, &row.tests) {
+                    Some(TestOutcome::Passed) => ("✓", Color::Green),
+                    Some(TestOutcome::Failed) => ("✗", Color::Red),
+                    Some(TestOutcome::Skipped) => ("○", Color::Yellow),
+                    None => ("·", Color::DarkGray),
+                };
+                let label = match row.leaf {
+                    true => Span::raw(row.label.clone()),
+                    false => Span::styled(
+                        format!("{} ({})", row.label, row.tests.len()),
+                        Style::default().fg(Color::Cyan),
+                    ),
+                };
+                let style = if i == self.selected_row {
+                    Style::default().add_modifier(Modifier::REVERSED)
+                } else {
+                    Style::default()
+                };
+                ListItem::new(Line::from(vec![
+                    Span::raw("  ".repeat(row.depth)),
+                    Span::styled(format!("{} ", mark), Style::default().fg(color)),
+                    label,
+                ]))
+                .style(style)
+            })
+            .collect();
+        self.row_state.select(Some(self.selected_row));
+        let filter = match (explorer.filtering, explorer.filter.is_empty()) {
+            (true, _) => format!(" | filter: {}_", explorer.filter),
+            (false, false) => format!(" | filter: {}", explorer.filter),
+            (false, true) => String::new(),
+        };
+        let title = match &explorer.tests {
+            None => format!("Explorer: {} | listing tests...", explorer.tool),
+            Some(_) => format!(
+                "Explorer: {}{} | Enter: run  /: filter  e: back",
+                explorer.tool, filter
+            ),
+        };
+        f.render_stateful_widget(
+            List::new(items).block(Block::default().title(title).borders(Borders::ALL)),
+            area,
+            &mut self.row_state,
+        );
+    }
+}
+
+impl App {
+    /// Line coverage per file with the uncovered line ranges
+    fn draw_coverage(&mut self, f: &mut Frame, area: Rect) {
+        let Some(report) = self.coverage_report() else {
+            let hint = match self.coverage_mode {
+                true => "No coverage collected yet; run a test tool",
+                false => "No coverage collected yet; C turns on coverage, then run a test tool",
+            };
+            f.render_widget(
+                Paragraph::new(hint)
+                    .block(Block::default().title("Coverage | o: back").borders(Borders::ALL)),
+                area,
+            );
+            return;
+        };
+        let items: Vec<ListItem> = report
+            .files
+            .iter()
+            .enumerate()
+            .map(|(i, file)| {
+                let percent = file.lines.percent().unwrap_or(0.0);
+                let color = if percent >= 80.0 {
+                    Color::Green
+                } else if percent >= 50.0 {
+                    Color::Yellow
+                } else {
+                    Color::Red
+                };
+                let mut spans = vec![
+                    Span::styled(format!("{:>6.1}% ", percent), Style::default().fg(color)),
+                    Span::raw(format!(
+                        "{:>5}/{:<5} {}",
+                        file.lines.covered, file.lines.total, file.file
+                    )),
+                ];
+                if !file.uncovered.is_empty() {
+                    spans.push(Span::styled(
+                        format!("  uncovered {}", file.ranges()),
+                        Style::default().fg(Color::DarkGray),
+                    ));
+                }
+                let style = if i == self.selected_row {
+                    Style::default().add_modifier(Modifier::REVERSED)
+                } else {
+                    Style::default()
+                };
+                ListItem::new(Line::from(spans)).style(style)
+            })
+            .collect();
+        self.row_state.select(Some(self.selected_row));
+        let changed = report
+            .changed_lines
+            .map(|changed| {
+                format!(
+                    ", changed lines {:.1}% of {}",
+                    changed.percent().unwrap_or(100.0),
+                    changed.total
+                )
+            })
+            .unwrap_or_default();
+        let title = format!(
+            "Coverage: {:.1}% of {} lines{} | o: back",
+            report.lines.percent().unwrap_or(0.0),
+            report.lines.total,
+            changed
+        );
+        f.render_stateful_widget(
+            List::new(items).block(Block::default().title(title).borders(Borders::ALL)),
+            area,
+            &mut self.row_state,
+        );
+    }
+}
+
+impl App {
+    /// The lines of the doctor pane: each tool or group of undetected
+    /// tools, the checks that decided, and what to change
+    fn doctor_lines(&self) -> Vec<Line<'static>> {
+        let mark = |passed: bool| {
+            Span::styled(
+                format!("{} ", doctor::mark(passed)),
+                Style::default().fg(if passed { Color::Green } else { Color::Red }),
+            )
+        };
+        let mut lines = Vec::new();
+        let mut project = None;
+        for group in doctor::groups(&self.verdicts) {
+            if project != Some(group.project()) {
+                project = Some(group.project());
+                let header = if group.project().is_empty() { "." } else { group.project() };
+                lines.push(Line::styled(
+                    header.to_string(),
+                    Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD),
+                ));
+            }
+            let style = if group.detected() {
+                Style::default().add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().fg(Color::DarkGray)
+            };
+            lines.push(Line::from(vec![
+                Span::raw("  "),
+                mark(group.detected()),
+                Span::styled(group.tools(), style),
+            ]));
+            for check in group.checks {
+                lines.push(Line::from(vec![
+                    Span::raw("      "),
+                    mark(check.passed),
+                    Span::raw(check.description.clone()),
+                ]));
+            }
+            let problem = self
+                .availability
+                .get(&group.verdicts[0].tool)
+                .and_then(Availability::problem)
+                .filter(|_| group.detected());
+            if let Some(suggestion) = group.suggestion().map(str::to_string).or(problem) {
+                lines.push(Line::styled(
+                    format!("      → {}", suggestion),
+                    Style::default().fg(Color::Yellow),
+                ));
+            }
+        }
+        lines
+    }
+
+    fn draw_doctor(&mut self, f: &mut Frame, area: Rect) {
+        let items: Vec<ListItem> = self
+            .doctor_lines()
+            .into_iter()
+            .enumerate()
+            .map(|(i, line)| {
+                let style = if i == self.selected_row {
+                    Style::default().add_modifier(Modifier::REVERSED)
+                } else {
+                    Style::default()
+                };
+                ListItem::new(line).style(style)
+            })
+            .collect();
+        self.row_state.select(Some(self.selected_row));
+        let detected = self.verdicts.iter().filter(|verdict| verdict.detected).count();
+        let title = format!(
+            "Detection: {} of {} known tools | D: back",
+            detected,
+            self.verdicts.len()
+        );
+        f.render_stateful_widget(
+            List::new(items).block(Block::default().title(title).borders(Borders::ALL)),
+            area,
+            &mut self.row_state,
+        );
+    }
+}
+
 impl Clone for App {
     fn clone(&self) -> Self {
         let (result_sender, result_receiver) = mpsc::channel(100);
+        let (fix_sender, fix_receiver) = mpsc::channel(1);
+        let (explorer_sender, explorer_receiver) = mpsc::channel(1);
+        let (_, availability_receiver) = mpsc::channel(1);
         Self {
             tools: self.tools.clone(),
             selected_tool: self.selected_tool,
@@ -216,27 +1794,70 @@ impl Clone for App {
             result_sender,
             result_receiver,
             show_help: self.show_help,
+            help_scroll: self.help_scroll,
+            gates: self.gates.clone(),
+            draft: self.draft,
+            changed_base: self.changed_base.clone(),
+            changes: self.changes.clone(),
+            status_message: self.status_message.clone(),
+            paths: self.paths.clone(),
+            baseline: self.baseline.clone(),
+            // The watcher holds OS resources and is not shared; watch mode
+            // starts off in the copy
+            watcher: None,
+            watch_pending: Vec::new(),
+            fix_sender,
+            fix_receiver,
+            fix_preview: self.fix_preview.clone(),
+            fix_scroll: self.fix_scroll,
+            applied_fixes: self.applied_fixes.clone(),
+            pane: self.pane,
+            selected_row: self.selected_row,
+            row_state: ListState::default(),
+            verifying: self.verifying.clone(),
+            cache: self.cache.clone(),
+            history: self.history.clone(),
+            history_records: self.history_records.clone(),
+            flaky: self.flaky.clone(),
+            flaky_reruns: self.flaky_reruns,
+            targeted: self.targeted.clone(),
+            explorer: self.explorer.clone(),
+            explorer_sender,
+            explorer_receiver,
+            coverage_mode: self.coverage_mode,
+            coverage_dir: self.coverage_dir.clone(),
+            coverage_runs: self.coverage_runs.clone(),
+            coverage: self.coverage.clone(),
+            availability: self.availability.clone(),
+            availability_receiver,
+            verdicts: self.verdicts.clone(),
         }
     }
 }
 
-/// Helper function to create a centered rect using up certain percentage of the available rect `r`
+/// Check in the background whether the program of each of `tools` is
+/// installed, sending the outcomes on `sender` as they come in
+fn probe_tools(tools: &[Tool], directory: &Path, sender: &mpsc::Sender<(String, Availability)>) {
+    for tool in tools {
+        let tool = tool.clone();
+        let directory = directory.to_path_buf();
+        let sender = sender.clone();
+        tokio::spawn(async move {
+            let availability = availability::check(&tool, &directory).await;
+            let _ = sender.send((tool.name, availability)).await;
+        });
+    }
+}
+
+/// A `width` by `height` rect centered in `r`, shrunk to fit it
 fn centered_rect(width: u16, height: u16, r: Rect) -> Rect {
-    let popup_layout = Layout::default()
-        .direction(Direction::Vertical)
-        .constraints([
-            Constraint::Percentage(((100 - height as u16 * 100 / r.height) / 2) as u16),
-            Constraint::Length(height),
-            Constraint::Percentage(((100 - height as u16 * 100 / r.height) / 2) as u16),
-        ])
-        .split(r);
-
-    Layout::default()
-        .direction(Direction::Horizontal)
-        .constraints([
-            Constraint::Percentage(((100 - width as u16 * 100 / r.width) / 2) as u16),
-            Constraint::Length(width),
-            Constraint::Percentage(((100 - width as u16 * 100 / r.width) / 2) as u16),
-        ])
-        .split(popup_layout[1])[1]
+    // A popup larger than the terminal fills it
+    let width = width.min(r.width);
+    let height = height.min(r.height);
+    Rect {
+        x: r.x + r.width.saturating_sub(width) / 2,
+        y: r.y + r.height.saturating_sub(height) / 2,
+        width,
+        height,
+    }
 } 
\ No newline at end of file
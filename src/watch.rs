@@ -0,0 +1,143 @@
+use crate::paths::PathFilter;
+use anyhow::{Context, Result};
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeSet, HashMap};
+use std::path::Path;
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+/// How long the file system must stay quiet before changes are reported, so
+/// that a save touching several files (or an editor's write-and-rename)
+/// triggers a single rerun
+pub const DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// Watches a directory for changes to files that are not ignored
+pub struct ChangeWatcher {
+    filter: PathFilter,
+    watcher: RecommendedWatcher,
+    events: mpsc::Receiver<notify::Result<notify::Event>>,
+    /// Changed paths, relative to the watched directory, not yet reported
+    pending: BTreeSet<String>,
+    last_change: Option<Instant>,
+    /// Content hashes of the files below the watched directory, by relative
+    /// path, so that writes leaving a file as it was (e.g. a tool rewriting
+    /// its cache or a snapshot unchanged) are not reported
+    hashes: HashMap<String, [u8; 32]>,
+}
+
+impl ChangeWatcher {
+    /// Watch `directory` and every directory below it that is not ignored by
+    /// the ignore files or `ignore_patterns`. Ignored directories such as
+    /// `target` or `node_modules` are never watched at all.
+    pub fn new(directory: &Path, ignore_patterns: &[String]) -> Result<Self> {
+        let root = directory
+            .canonicalize()
+            .with_context(|| format!("Could not watch {}", directory.display()))?;
+        let (sender, events) = mpsc::channel();
+        let watcher = notify::recommended_watcher(move |event| {
+            let _ = sender.send(event);
+        })
+        .context("Could not start the file watcher")?;
+        let mut change_watcher = Self {
+            filter: PathFilter::new(&root, ignore_patterns),
+            watcher,
+            events,
+            pending: BTreeSet::new(),
+            last_change: None,
+            hashes: HashMap::new(),
+        };
+        change_watcher.watch_tree(&root)?;
+        for entry in change_watcher.filter.walk() {
+            if entry.file_type().is_some_and(|t| t.is_file()) {
+                if let (Ok(relative), Some(hash)) =
+                    (entry.path().strip_prefix(&root), content_hash(entry.path()))
+                {
+                    change_watcher.hashes.insert(relative.to_string_lossy().to_string(), hash);
+                }
+            }
+        }
+        Ok(change_watcher)
+    }
+
+    /// Watch `directory` and the directories below it that are not ignored
+    fn watch_tree(&mut self, directory: &Path) -> Result<()> {
+        self.watcher
+            .watch(directory, RecursiveMode::NonRecursive)
+            .with_context(|| format!("Could not watch {}", directory.display()))?;
+        let below = if directory == self.filter.root() {
+            self.filter.clone()
+        } else {
+            PathFilter::new(directory, self.filter.patterns())
+        };
+        for entry in below.walk() {
+            if entry.file_type().is_some_and(|t| t.is_dir())
+                && !self.filter.is_ignored(entry.path(), true)
+            {
+                self.watcher
+                    .watch(entry.path(), RecursiveMode::NonRecursive)
+                    .with_context(|| format!("Could not watch {}", entry.path().display()))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Collect pending notifications without blocking. Returns the changed
+    /// paths once nothing has changed for [`DEBOUNCE`].
+    pub fn poll(&mut self) -> Option<Vec<String>> {
+        self.collect();
+
+        let settled = self.last_change.is_some_and(|at| at.elapsed() >= DEBOUNCE);
+        if !settled || self.pending.is_empty() {
+            return None;
+        }
+        self.last_change = None;
+        Some(std::mem::take(&mut self.pending).into_iter().collect())
+    }
+
+    /// Move the notifications received so far into `pending`
+    fn collect(&mut self) {
+        while let Ok(event) = self.events.try_recv() {
+            let Ok(event) = event else { continue };
+            if matches!(event.kind, EventKind::Access(_)) {
+                continue;
+            }
+            for path in event.paths {
+                if path.is_dir() {
+                    // New directories need watches of their own; their
+                    // contents are reported by later events
+                    if matches!(event.kind, EventKind::Create(_))
+                        && !self.filter.is_ignored(&path, true)
+                    {
+                        let _ = self.watch_tree(&path);
+                    }
+                    continue;
+                }
+                if self.filter.is_ignored(&path, false) {
+                    continue;
+                }
+                let Ok(relative) = path.strip_prefix(self.filter.root()) else {
+                    continue;
+                };
+                let relative = relative.to_string_lossy().to_string();
+                // Deleted files have no hash, nor do files created and
+                // removed again (e.g. an editor's temporary copy)
+                let hash = content_hash(&path);
+                if self.hashes.get(&relative) == hash.as_ref() {
+                    continue;
+                }
+                match hash {
+                    Some(hash) => self.hashes.insert(relative.clone(), hash),
+                    None => self.hashes.remove(&relative),
+                };
+                self.pending.insert(relative);
+                self.last_change = Some(Instant::now());
+            }
+        }
+    }
+}
+
+fn content_hash(path: &Path) -> Option<[u8; 32]> {
+    let contents = std::fs::read(path).ok()?;
+    Some(Sha256::digest(contents).into())
+}